@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::InstructionData;
+
+pub use crate::cpi::accounts::{SwapXToY, SwapYToX};
+
+/// Swaps X for Y through an Obric v3 trading pair from another program.
+/// `ctx.program` must be the Obric v3 program account.
+pub fn swap_x_to_y<'a, 'b, 'c, 'info>(
+    ctx: CpiContext<'a, 'b, 'c, 'info, SwapXToY<'info>>,
+    input_x: u64,
+    min_output_amt: u64,
+) -> Result<()> {
+    crate::cpi::swap_x_to_y(ctx, input_x, min_output_amt)
+}
+
+/// Swaps Y for X through an Obric v3 trading pair from another program.
+/// `ctx.program` must be the Obric v3 program account.
+pub fn swap_y_to_x<'a, 'b, 'c, 'info>(
+    ctx: CpiContext<'a, 'b, 'c, 'info, SwapYToX<'info>>,
+    input_y: u64,
+    min_output_amt: u64,
+) -> Result<()> {
+    crate::cpi::swap_y_to_x(ctx, input_y, min_output_amt)
+}
+
+/// Builds the raw `swap_x_to_y` instruction, for clients that only have pubkeys.
+pub fn swap_x_to_y_instruction(
+    accounts: crate::accounts::SwapXToY,
+    input_x: u64,
+    min_output_amt: u64,
+) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::SwapXToY {
+            _input_x: input_x,
+            _min_output_amt: min_output_amt,
+        }
+        .data(),
+    }
+}
+
+/// Builds the raw `swap_y_to_x` instruction, for clients that only have pubkeys.
+pub fn swap_y_to_x_instruction(
+    accounts: crate::accounts::SwapYToX,
+    input_y: u64,
+    min_output_amt: u64,
+) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::SwapYToX {
+            _input_y: input_y,
+            _min_output_amt: min_output_amt,
+        }
+        .data(),
+    }
+}
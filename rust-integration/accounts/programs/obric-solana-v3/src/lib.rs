@@ -1,6 +1,12 @@
 pub mod consts;
+#[cfg(feature = "cpi")]
+pub mod cpi_client;
 pub mod errors;
+#[cfg(feature = "serde")]
+pub mod serde_pubkey;
 pub mod state;
+#[cfg(feature = "test-only")]
+pub mod test_only;
 
 use anchor_lang::prelude::*;
 use anchor_spl::token::{Mint, Token, TokenAccount};
@@ -19,10 +25,14 @@ pub mod obric_solana_v3 {
     use super::*;
 
     pub fn swap_x_to_y(_ctx: Context<SwapXToY>, _input_x: u64, _min_output_amt: u64) -> Result<()> {
+        #[cfg(feature = "test-only")]
+        crate::test_only::swap_x_to_y(_ctx, _input_x, _min_output_amt)?;
         Ok(())
     }
 
     pub fn swap_y_to_x(_ctx: Context<SwapYToX>, _input_y: u64, _min_output_amt: u64) -> Result<()> {
+        #[cfg(feature = "test-only")]
+        crate::test_only::swap_y_to_x(_ctx, _input_y, _min_output_amt)?;
         Ok(())
     }
 }
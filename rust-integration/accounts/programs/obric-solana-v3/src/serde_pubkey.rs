@@ -0,0 +1,13 @@
+//! `#[serde(with = "crate::serde_pubkey")]` for `Pubkey` fields, as base58 strings.
+use anchor_lang::prelude::Pubkey;
+use serde::{Deserialize, Deserializer, Serializer};
+use std::str::FromStr;
+
+pub fn serialize<S: Serializer>(pubkey: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&pubkey.to_string())
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+    let pubkey = String::deserialize(deserializer)?;
+    Pubkey::from_str(&pubkey).map_err(serde::de::Error::custom)
+}
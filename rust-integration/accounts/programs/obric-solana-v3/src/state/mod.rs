@@ -4,5 +4,11 @@ pub use trading_pair::*;
 pub mod price_feed;
 pub use price_feed::*;
 
+pub mod quote;
+pub use quote::*;
+
 pub mod larix;
 pub use larix::*;
+
+pub use obric_math::units;
+pub use units::*;
@@ -5,6 +5,7 @@ use pyth_sdk::Price;
 use pyth_sdk_solana::state::load_price_account;
 
 use crate::errors::ObricError;
+use crate::state::units::{NormalizedPrice, TokenX, TokenY};
 
 #[derive(Clone)]
 pub struct PriceFeed(pyth_sdk::PriceFeed);
@@ -12,10 +13,33 @@ pub struct PriceFeed(pyth_sdk::PriceFeed);
 impl PriceFeed {
     pub fn price_normalized(&self) -> Result<Price> {
         let p = self.0.get_price_unchecked();
-        let price = p.scale_to_exponent(-3).unwrap();
+        let price = p.scale_to_exponent(-3).ok_or(ObricError::PythError)?;
         require!(price.price > 0, ObricError::NegativePrice);
         Ok(price)
     }
+
+    /// Prices of `x` and `y` at the common exponent of `normalize_price_pair`,
+    /// with the exponent. The error names the feed whose price is unusable;
+    /// `ObricError::from` turns it into the program's.
+    pub fn normalized_pair(
+        x: &PriceFeed,
+        y: &PriceFeed,
+    ) -> std::result::Result<(NormalizedPrice<TokenX>, NormalizedPrice<TokenY>, i32), PriceError>
+    {
+        let (x, y) = (x.get_price_unchecked(), y.get_price_unchecked());
+        obric_math::price::normalize_price_pair((x.price, x.expo), (y.price, y.expo))
+    }
+}
+
+pub use obric_math::price::{MIN_NORMALIZED_PRICE, NORMALIZED_EXPO};
+pub use obric_math::{PriceError, PriceSide};
+
+/// `obric_math::price::normalize_price_pair` with the program's errors.
+pub fn normalize_price_pair(
+    price_x: (i64, i32),
+    price_y: (i64, i32),
+) -> Result<(NormalizedPrice<TokenX>, NormalizedPrice<TokenY>, i32)> {
+    Ok(obric_math::price::normalize_price_pair(price_x, price_y).map_err(ObricError::from)?)
 }
 
 impl anchor_lang::IdlBuild for PriceFeed {}
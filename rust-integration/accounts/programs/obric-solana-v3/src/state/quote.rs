@@ -0,0 +1,3 @@
+// the curve math lives in `obric_math`, shared with the SDKs
+pub use obric_math::quote::QuoteBreakdown;
+pub use obric_math::v3::CurvePoint;
@@ -1,28 +1,43 @@
 use anchor_lang::prelude::*;
-use num::pow;
 
-use crate::{consts, errors::ObricError};
+use crate::{
+    errors::ObricError,
+    state::quote::{CurvePoint, QuoteBreakdown},
+    state::units::{Amount, CurveAmount, Invariant, Mult, NormalizedPrice, TokenX, TokenY},
+};
+use obric_math::{price::mults, v3::Curve};
 
 #[account]
-#[derive(Default, Debug)]
+#[derive(Default, Debug, PartialEq, InitSpace)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SSTradingPair {
     pub is_initialized: bool,
 
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_pubkey"))]
     pub x_price_feed_id: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_pubkey"))]
     pub y_price_feed_id: Pubkey,
 
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_pubkey"))]
     pub reserve_x: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_pubkey"))]
     pub reserve_y: Pubkey,
 
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_pubkey"))]
     pub reserve_x_ctoken: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_pubkey"))]
     pub reserve_y_ctoken: Pubkey,
 
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_pubkey"))]
     pub protocol_fee_x: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_pubkey"))]
     pub protocol_fee_y: Pubkey,
 
     pub bump: u8,
     // mints
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_pubkey"))]
     pub mint_x: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_pubkey"))]
     pub mint_y: Pubkey,
 
     // these numbers are synced with lending interface, and are updated after every tx
@@ -56,178 +71,139 @@ pub struct SSTradingPair {
 }
 
 impl SSTradingPair {
-    pub const LEN: usize = 8 + 1 + 32 * 8 + 1 + 32 * 2 + 8 * 6 + 16 + 8 * 6 + 2 + 254; // 8 for internal anchor
+    pub const LEN: usize = 8 + SSTradingPair::INIT_SPACE; // 8 for internal anchor
+
+    pub fn mult_x(&self) -> Mult<TokenX> {
+        Mult::new(self.mult_x)
+    }
+
+    pub fn mult_y(&self) -> Mult<TokenY> {
+        Mult::new(self.mult_y)
+    }
+
+    pub fn invariant(&self) -> Invariant {
+        Invariant::new(self.big_k)
+    }
+
+    /// The curve of this pair in `obric_math`, which does the quoting.
+    pub fn curve(&self) -> Curve {
+        Curve {
+            concentration: self.concentration,
+            target_y: Amount::new(self.target_y),
+            deposit_x: Amount::new(self.deposit_x),
+            borrow_x: Amount::new(self.borrow_x),
+            deposit_y: Amount::new(self.deposit_y),
+            borrow_y: Amount::new(self.borrow_y),
+            mult_x: self.mult_x(),
+            mult_y: self.mult_y(),
+            fee_millionth: self.fee_millionth,
+            protocol_fee_share_thousandth: self.protocol_fee_share_thousandth,
+        }
+    }
 
     #[inline(never)]
-    pub fn update_price(&mut self, price_x: u64, price_y: u64) -> Result<()> {
-        let x_decimals = self.decimals_x;
-        let y_decimals = self.decimals_y;
-        let (x_deci_mult, y_deci_mult) = if x_decimals > y_decimals {
-            (1 as u64, pow(10, usize::from(x_decimals - y_decimals)))
-        } else if y_decimals > x_decimals {
-            (pow(10, usize::from(y_decimals - x_decimals)), 1 as u64)
-        } else {
-            (1 as u64, 1 as u64)
-        };
-
-        self.mult_x = price_x.checked_mul(x_deci_mult).unwrap();
-        self.mult_y = price_y.checked_mul(y_deci_mult).unwrap();
+    pub fn update_price(
+        &mut self,
+        price_x: NormalizedPrice<TokenX>,
+        price_y: NormalizedPrice<TokenY>,
+    ) -> Result<()> {
+        let (mult_x, mult_y) =
+            mults(price_x, price_y, self.decimals_x, self.decimals_y).map_err(ObricError::from)?;
+        self.mult_x = mult_x.get();
+        self.mult_y = mult_y.get();
 
         Ok(())
     }
 
-    pub fn update_target_y(&mut self, new_target_y: u64) -> Result<()> {
-        let target_y_k = (new_target_y as u128)
-            .checked_mul(self.concentration as u128)
-            .unwrap();
-        let target_x_k = target_y_k
-            .checked_mul(self.mult_y as u128)
-            .unwrap()
-            .checked_div(self.mult_x as u128)
-            .unwrap();
-        self.target_y = new_target_y;
-        self.big_k = target_x_k.checked_mul(target_y_k).unwrap();
+    pub fn update_target_y(&mut self, new_target_y: Amount<TokenY>) -> Result<()> {
+        let big_k = self
+            .curve()
+            .invariant_at_target(new_target_y)
+            .map_err(ObricError::from)?;
+        self.target_y = new_target_y.get();
+        self.big_k = big_k.get();
 
         Ok(())
     }
 
-    pub fn compute_target_y(&self) -> u64 {
-        let deposit_x_value = self.deposit_x.checked_mul(self.mult_x).unwrap();
-        let borrow_x_value = self.borrow_x.checked_mul(self.mult_x).unwrap();
-        let deposit_y_value = self.deposit_y.checked_mul(self.mult_y).unwrap();
-        let borrow_y_value = self.borrow_y.checked_mul(self.mult_y).unwrap();
-        let deposit_value = deposit_x_value.checked_add(deposit_y_value).unwrap();
-        let borrow_value = borrow_x_value.checked_add(borrow_y_value).unwrap();
-        let net_value = deposit_value.checked_sub(borrow_value).unwrap();
-        let target_y = net_value.checked_div(self.mult_y).unwrap();
-
-        target_y
+    /// Net value of the deposits and borrows in units of Y. Values are taken
+    /// in u128, so only a target that does not fit in u64 fails.
+    pub fn compute_target_y(&self) -> Result<Amount<TokenY>> {
+        Ok(self.curve().compute_target_y().map_err(ObricError::from)?)
     }
 
-    /*
-    When quoting, we assume X and Y are equally priced. We adjust our output amounts by the price of X only after the
-    quote has been computed.
-
-    here we do not compute current_x_K or target_x_K, instead we compute:
-    - current_xp_K = current_x_K * mult_x / mult_y
-    - target_xp_K = target_x_K * mult_x / mult_y = target_y_K
+    pub fn get_pool_values_for_quoting(
+        &self,
+    ) -> Result<(
+        Invariant,
+        CurveAmount<TokenX>,
+        CurveAmount<TokenY>,
+        Amount<TokenX>,
+        Amount<TokenY>,
+    )> {
+        let point = self.curve_point()?;
+        Ok((
+            point.big_k,
+            point.current_x_k,
+            point.current_y_k,
+            point.available_x,
+            point.available_y,
+        ))
+    }
 
-    At "target"/equilibrium:
-    - target_y = deposited amount of Y
-    - target_y_K = deposited amount of Y * concentration
-    - targeted deposit_x = targeted borrow_x = 0
-    - targeted deposit_y = target_y
-    */
-    pub fn get_pool_values_for_quoting(&self) -> Result<(u128, u64, u64, u64, u64)> {
-        // u64 or u128 here?
-        let target_y_k = self.concentration.checked_mul(self.target_y).unwrap();
-        let target_x_k = target_y_k
-            .checked_mul(self.mult_y)
-            .unwrap()
-            .checked_div(self.mult_x)
-            .unwrap();
-        let current_y_k = target_y_k
-            .checked_add(self.deposit_y)
-            .unwrap()
-            .checked_sub(self.target_y)
-            .unwrap();
-        let current_x_k = target_x_k
-            .checked_add(self.deposit_x)
-            .unwrap()
-            .checked_sub(self.borrow_x)
-            .unwrap();
-        let big_k = (current_x_k as u128)
-            .checked_mul(current_y_k as u128)
-            .unwrap();
-
-        let temp = target_x_k
-            .checked_div(self.concentration)
-            .unwrap()
-            .checked_add(self.deposit_x)
-            .unwrap();
-        let available_x = if temp > self.borrow_x {
-            temp.checked_sub(self.borrow_x).unwrap()
-        } else {
-            0
-        };
-        let available_y = self.deposit_y;
-
-        Ok((big_k, current_x_k, current_y_k, available_x, available_y))
+    /// The pool values every quote starts from. Only changes with the state,
+    /// so quotes between two updates can share it.
+    pub fn curve_point(&self) -> Result<CurvePoint> {
+        Ok(self.curve().curve_point().map_err(ObricError::from)?)
     }
 
     /**
     Returns (output_to_user, fee_to_protocol)
     */
-    pub fn quote_x_to_y(&self, input_x: u64) -> Result<(u64, u64, u64)> {
-        let (big_k, current_x_k, current_y_k, _available_x, available_y) =
-            self.get_pool_values_for_quoting()?;
-
-        // 2. find new (x, y) on curve-K
-        let new_x_k = current_x_k.checked_add(input_x).unwrap();
-        let new_y_k: u64 = big_k
-            .checked_div(new_x_k as u128)
-            .unwrap()
-            .try_into()
-            .unwrap();
-
-        let output_before_fee_y = current_y_k.checked_sub(new_y_k).unwrap();
-        require!(
-            output_before_fee_y < available_y,
-            ObricError::InsufficientActiveY
-        );
-
-        let fee_y = output_before_fee_y
-            .checked_mul(self.fee_millionth)
-            .unwrap()
-            .checked_div(consts::MILLION)
-            .unwrap();
-        let output_after_fee_y = output_before_fee_y.checked_sub(fee_y).unwrap();
-
-        let protocol_fee_y = fee_y
-            .checked_mul(self.protocol_fee_share_thousandth)
-            .unwrap()
-            .checked_div(1000)
-            .unwrap();
-        let lp_fee_y = fee_y.checked_sub(protocol_fee_y).unwrap();
-
-        Ok((output_after_fee_y, protocol_fee_y, lp_fee_y))
+    pub fn quote_x_to_y(&self, input_x: Amount<TokenX>) -> Result<(u64, u64, u64)> {
+        let quote = self.quote_x_to_y_breakdown(input_x)?;
+        Ok((quote.output_after_fee, quote.protocol_fee, quote.lp_fee))
+    }
+
+    /// v3 pays no rebate, `fee_before_rebate` is the charged fee.
+    pub fn quote_x_to_y_breakdown(&self, input_x: Amount<TokenX>) -> Result<QuoteBreakdown> {
+        self.quote_x_to_y_at(&self.curve_point()?, input_x)
+    }
+
+    /// `quote_x_to_y_breakdown` from a `curve_point` computed beforehand.
+    pub fn quote_x_to_y_at(
+        &self,
+        point: &CurvePoint,
+        input_x: Amount<TokenX>,
+    ) -> Result<QuoteBreakdown> {
+        Ok(self
+            .curve()
+            .quote_x_to_y_at(point, input_x)
+            .map_err(ObricError::from)?)
     }
 
     /**
     Returns (output_to_user, fee_to_protocol, fee_to_reserve_x)
     */
-    pub fn quote_y_to_x(&self, input_y: u64) -> Result<(u64, u64, u64)> {
-        let (big_k, current_x_k, current_y_k, available_x, _available_y) =
-            self.get_pool_values_for_quoting()?;
-
-        // 2. find new (x, y) on curve-K
-        let new_y_k = current_y_k.checked_add(input_y).unwrap();
-        let new_x_k: u64 = big_k
-            .checked_div(new_y_k as u128)
-            .unwrap()
-            .try_into()
-            .unwrap();
-
-        let output_before_fee_x = current_x_k.checked_sub(new_x_k).unwrap();
-        require!(
-            output_before_fee_x < available_x,
-            ObricError::InsufficientActiveX
-        );
-
-        let fee_x = output_before_fee_x
-            .checked_mul(self.fee_millionth)
-            .unwrap()
-            .checked_div(consts::MILLION)
-            .unwrap();
-        let output_after_fee_x = output_before_fee_x.checked_sub(fee_x).unwrap();
-
-        let protocol_fee_x = fee_x
-            .checked_mul(self.protocol_fee_share_thousandth)
-            .unwrap()
-            .checked_div(1000)
-            .unwrap();
-        let lp_fee_x = fee_x.checked_sub(protocol_fee_x).unwrap();
-
-        Ok((output_after_fee_x, protocol_fee_x, lp_fee_x))
+    pub fn quote_y_to_x(&self, input_y: Amount<TokenY>) -> Result<(u64, u64, u64)> {
+        let quote = self.quote_y_to_x_breakdown(input_y)?;
+        Ok((quote.output_after_fee, quote.protocol_fee, quote.lp_fee))
+    }
+
+    /// v3 pays no rebate, `fee_before_rebate` is the charged fee.
+    pub fn quote_y_to_x_breakdown(&self, input_y: Amount<TokenY>) -> Result<QuoteBreakdown> {
+        self.quote_y_to_x_at(&self.curve_point()?, input_y)
+    }
+
+    /// `quote_y_to_x_breakdown` from a `curve_point` computed beforehand.
+    pub fn quote_y_to_x_at(
+        &self,
+        point: &CurvePoint,
+        input_y: Amount<TokenY>,
+    ) -> Result<QuoteBreakdown> {
+        Ok(self
+            .curve()
+            .quote_y_to_x_at(point, input_y)
+            .map_err(ObricError::from)?)
     }
 }
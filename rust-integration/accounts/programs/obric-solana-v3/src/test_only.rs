@@ -0,0 +1,192 @@
+use crate::consts::TRADING_PAIR_SEED;
+use crate::errors::ObricError;
+use crate::state::{Amount, PriceFeed, SSTradingPair};
+use crate::{SwapXToY, SwapYToX};
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Transfer};
+
+/*
+Executable swaps for the `test-only` build, so that SDK quotes can be checked against real token
+movements under `solana-program-test`.
+
+Larix is not invoked: tokens stay in reserve_x / reserve_y and deposit_x, borrow_x and deposit_y
+are book-kept the way the lending interface would report them after the swap.
+*/
+
+fn refresh_pool(
+    trading_pair: &mut SSTradingPair,
+    x_price_feed: &PriceFeed,
+    y_price_feed: &PriceFeed,
+) -> Result<()> {
+    let (price_x, price_y, _) =
+        PriceFeed::normalized_pair(x_price_feed, y_price_feed).map_err(ObricError::from)?;
+    trading_pair.update_price(price_x, price_y)?;
+    let target_y = trading_pair.compute_target_y()?;
+    trading_pair.update_target_y(target_y)
+}
+
+pub fn swap_x_to_y(ctx: Context<SwapXToY>, input_x: u64, min_output_amt: u64) -> Result<()> {
+    require!(input_x > 0, ObricError::InvalidInputAmount);
+    let accounts = ctx.accounts;
+
+    refresh_pool(
+        &mut accounts.trading_pair,
+        &accounts.x_price_feed,
+        &accounts.y_price_feed,
+    )?;
+    let (output_y, protocol_fee_y, _lp_fee_y) =
+        accounts.trading_pair.quote_x_to_y(Amount::new(input_x))?;
+    require!(output_y > 0, ObricError::InsufficientActiveY);
+    require!(
+        output_y >= min_output_amt,
+        ObricError::OutputAmountLessThanExpected
+    );
+
+    token::transfer(
+        CpiContext::new(
+            accounts.token_program.to_account_info(),
+            Transfer {
+                from: accounts.user_token_account_x.to_account_info(),
+                to: accounts.reserve_x.to_account_info(),
+                authority: accounts.user.to_account_info(),
+            },
+        ),
+        input_x,
+    )?;
+
+    let mint_x = accounts.trading_pair.mint_x;
+    let mint_y = accounts.trading_pair.mint_y;
+    let bump = [accounts.trading_pair.bump];
+    let seeds: &[&[u8]] = &[
+        TRADING_PAIR_SEED.as_bytes(),
+        mint_x.as_ref(),
+        mint_y.as_ref(),
+        &bump,
+    ];
+    let signer = &[seeds];
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            Transfer {
+                from: accounts.reserve_y.to_account_info(),
+                to: accounts.user_token_account_y.to_account_info(),
+                authority: accounts.trading_pair.to_account_info(),
+            },
+            signer,
+        ),
+        output_y,
+    )?;
+    if protocol_fee_y > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                accounts.token_program.to_account_info(),
+                Transfer {
+                    from: accounts.reserve_y.to_account_info(),
+                    to: accounts.protocol_fee_y.to_account_info(),
+                    authority: accounts.trading_pair.to_account_info(),
+                },
+                signer,
+            ),
+            protocol_fee_y,
+        )?;
+    }
+
+    let trading_pair = &mut accounts.trading_pair;
+    let repay_x = std::cmp::min(trading_pair.borrow_x, input_x);
+    trading_pair.borrow_x -= repay_x;
+    trading_pair.deposit_x = trading_pair
+        .deposit_x
+        .checked_add(input_x - repay_x)
+        .ok_or(ObricError::NumOverflowing)?;
+    trading_pair.deposit_y = output_y
+        .checked_add(protocol_fee_y)
+        .and_then(|output_total| trading_pair.deposit_y.checked_sub(output_total))
+        .ok_or(ObricError::NumOverflowing)?;
+    Ok(())
+}
+
+pub fn swap_y_to_x(ctx: Context<SwapYToX>, input_y: u64, min_output_amt: u64) -> Result<()> {
+    require!(input_y > 0, ObricError::InvalidInputAmount);
+    let accounts = ctx.accounts;
+
+    refresh_pool(
+        &mut accounts.trading_pair,
+        &accounts.x_price_feed,
+        &accounts.y_price_feed,
+    )?;
+    let (output_x, protocol_fee_x, _lp_fee_x) =
+        accounts.trading_pair.quote_y_to_x(Amount::new(input_y))?;
+    require!(output_x > 0, ObricError::InsufficientActiveX);
+    require!(
+        output_x >= min_output_amt,
+        ObricError::OutputAmountLessThanExpected
+    );
+
+    token::transfer(
+        CpiContext::new(
+            accounts.token_program.to_account_info(),
+            Transfer {
+                from: accounts.user_token_account_y.to_account_info(),
+                to: accounts.reserve_y.to_account_info(),
+                authority: accounts.user.to_account_info(),
+            },
+        ),
+        input_y,
+    )?;
+
+    let mint_x = accounts.trading_pair.mint_x;
+    let mint_y = accounts.trading_pair.mint_y;
+    let bump = [accounts.trading_pair.bump];
+    let seeds: &[&[u8]] = &[
+        TRADING_PAIR_SEED.as_bytes(),
+        mint_x.as_ref(),
+        mint_y.as_ref(),
+        &bump,
+    ];
+    let signer = &[seeds];
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            Transfer {
+                from: accounts.reserve_x.to_account_info(),
+                to: accounts.user_token_account_x.to_account_info(),
+                authority: accounts.trading_pair.to_account_info(),
+            },
+            signer,
+        ),
+        output_x,
+    )?;
+    if protocol_fee_x > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                accounts.token_program.to_account_info(),
+                Transfer {
+                    from: accounts.reserve_x.to_account_info(),
+                    to: accounts.protocol_fee_x.to_account_info(),
+                    authority: accounts.trading_pair.to_account_info(),
+                },
+                signer,
+            ),
+            protocol_fee_x,
+        )?;
+    }
+
+    // withdraw from the deposit first, borrow the rest
+    let trading_pair = &mut accounts.trading_pair;
+    let output_total = output_x
+        .checked_add(protocol_fee_x)
+        .ok_or(ObricError::NumOverflowing)?;
+    let withdraw_x = std::cmp::min(trading_pair.deposit_x, output_total);
+    trading_pair.deposit_x -= withdraw_x;
+    trading_pair.borrow_x = trading_pair
+        .borrow_x
+        .checked_add(output_total - withdraw_x)
+        .ok_or(ObricError::NumOverflowing)?;
+    trading_pair.deposit_y = trading_pair
+        .deposit_y
+        .checked_add(input_y)
+        .ok_or(ObricError::NumOverflowing)?;
+    Ok(())
+}
@@ -0,0 +1,322 @@
+#![cfg(feature = "cpi")]
+
+use anchor_lang::prelude::{AccountInfo, CpiContext, Pubkey};
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program_error::ProgramError;
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use obric_solana_v3::cpi_client;
+use obric_solana_v3::instruction::{SwapXToY, SwapYToX};
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::account::Account;
+use solana_sdk::signature::Signer;
+use solana_sdk::transaction::Transaction;
+
+const SWAP_X_TO_Y_ACCOUNTS: usize = 28;
+const SWAP_Y_TO_X_ACCOUNTS: usize = 29;
+const RECORD_LEN: usize = 1 + 8 + 8 + SWAP_Y_TO_X_ACCOUNTS * 32;
+
+/// Stands in for Obric v3: checks the instruction and records the direction,
+/// decoded arguments and account keys into the trading pair account.
+fn mock_obric(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let (is_x_to_y, input_amt, min_output_amt, user_index) = if data[..8] == SwapXToY::DISCRIMINATOR
+    {
+        let args = SwapXToY::try_from_slice(&data[8..])
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+        if accounts.len() != SWAP_X_TO_Y_ACCOUNTS {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        (true, args._input_x, args._min_output_amt, 25)
+    } else if data[..8] == SwapYToX::DISCRIMINATOR {
+        let args = SwapYToX::try_from_slice(&data[8..])
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+        if accounts.len() != SWAP_Y_TO_X_ACCOUNTS {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        (false, args._input_y, args._min_output_amt, 23)
+    } else {
+        return Err(ProgramError::InvalidInstructionData);
+    };
+    if !accounts[user_index].is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut record = accounts[0].try_borrow_mut_data()?;
+    record.fill(0);
+    record[0] = is_x_to_y as u8;
+    record[1..9].copy_from_slice(&input_amt.to_le_bytes());
+    record[9..17].copy_from_slice(&min_output_amt.to_le_bytes());
+    for (i, account) in accounts.iter().enumerate() {
+        record[17 + i * 32..17 + (i + 1) * 32].copy_from_slice(account.key.as_ref());
+    }
+    Ok(())
+}
+
+/// A minimal integrator: the first account is the Obric program and the rest
+/// are the swap accounts, in `SwapXToY` or `SwapYToX` order.
+fn integrator(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let (obric_program, a) = accounts
+        .split_first()
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let is_x_to_y = data[0] == 1;
+    let input_amt = u64::from_le_bytes(data[1..9].try_into().unwrap());
+    let min_output_amt = u64::from_le_bytes(data[9..17].try_into().unwrap());
+
+    if is_x_to_y {
+        let ctx = CpiContext::new(
+            obric_program.clone(),
+            cpi_client::SwapXToY {
+                trading_pair: a[0].clone(),
+                mint_x: a[1].clone(),
+                mint_y: a[2].clone(),
+                mint_x_ctoken: a[3].clone(),
+                mint_y_ctoken: a[4].clone(),
+                user_token_account_x: a[5].clone(),
+                user_token_account_y: a[6].clone(),
+                reserve_x: a[7].clone(),
+                reserve_y: a[8].clone(),
+                reserve_x_ctoken: a[9].clone(),
+                reserve_y_ctoken: a[10].clone(),
+                protocol_fee_y: a[11].clone(),
+                x_price_feed: a[12].clone(),
+                y_price_feed: a[13].clone(),
+                larix_reserve_liquidity_supply_x: a[14].clone(),
+                larix_reserve_liquidity_supply_y: a[15].clone(),
+                larix_destination_reserve_ctoken_x: a[16].clone(),
+                larix_destination_reserve_ctoken_y: a[17].clone(),
+                larix_reserve_x: a[18].clone(),
+                larix_reserve_y: a[19].clone(),
+                larix_obligation: a[20].clone(),
+                larix_lending_market: a[21].clone(),
+                larix_market_authority: a[22].clone(),
+                larix_x_oracle: a[23].clone(),
+                larix_y_oracle: a[24].clone(),
+                user: a[25].clone(),
+                token_program: a[26].clone(),
+                larix_program: a[27].clone(),
+            },
+        );
+        cpi_client::swap_x_to_y(ctx, input_amt, min_output_amt)?;
+    } else {
+        let ctx = CpiContext::new(
+            obric_program.clone(),
+            cpi_client::SwapYToX {
+                trading_pair: a[0].clone(),
+                mint_x_ctoken: a[1].clone(),
+                mint_y_ctoken: a[2].clone(),
+                user_token_account_x: a[3].clone(),
+                user_token_account_y: a[4].clone(),
+                reserve_x: a[5].clone(),
+                reserve_y: a[6].clone(),
+                reserve_x_ctoken: a[7].clone(),
+                reserve_y_ctoken: a[8].clone(),
+                protocol_fee_x: a[9].clone(),
+                x_price_feed: a[10].clone(),
+                y_price_feed: a[11].clone(),
+                larix_reserve_liquidity_supply_x: a[12].clone(),
+                larix_reserve_liquidity_supply_y: a[13].clone(),
+                larix_destination_reserve_ctoken_x: a[14].clone(),
+                larix_destination_reserve_ctoken_y: a[15].clone(),
+                larix_reserve_x: a[16].clone(),
+                larix_reserve_y: a[17].clone(),
+                larix_obligation: a[18].clone(),
+                larix_lending_market: a[19].clone(),
+                larix_market_authority: a[20].clone(),
+                larix_x_oracle: a[21].clone(),
+                larix_y_oracle: a[22].clone(),
+                user: a[23].clone(),
+                token_program: a[24].clone(),
+                larix_program: a[25].clone(),
+                larix_oracle_program: a[26].clone(),
+                larix_mint: a[27].clone(),
+                larix_reserve_fee_receiver_x: a[28].clone(),
+            },
+        );
+        cpi_client::swap_y_to_x(ctx, input_amt, min_output_amt)?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_swap_cpi() {
+    let integrator_id = Pubkey::new_unique();
+    let mut program_test = ProgramTest::new(
+        "obric_solana_v3",
+        obric_solana_v3::ID,
+        processor!(mock_obric),
+    );
+    program_test.add_program("integrator", integrator_id, processor!(integrator));
+
+    let trading_pair = Pubkey::new_unique();
+    program_test.add_account(
+        trading_pair,
+        Account {
+            lamports: 1_000_000_000,
+            data: vec![0; RECORD_LEN],
+            owner: obric_solana_v3::ID,
+            ..Account::default()
+        },
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    for (is_x_to_y, input_amt, min_output_amt) in [(true, 1_000u64, 990u64), (false, 2_000, 1_980)]
+    {
+        let (len, user_index) = if is_x_to_y {
+            (SWAP_X_TO_Y_ACCOUNTS, 25)
+        } else {
+            (SWAP_Y_TO_X_ACCOUNTS, 23)
+        };
+        let mut swap_accounts = vec![trading_pair];
+        swap_accounts.extend((1..len).map(|_| Pubkey::new_unique()));
+        swap_accounts[user_index] = payer.pubkey();
+
+        let mut accounts = vec![AccountMeta::new_readonly(obric_solana_v3::ID, false)];
+        accounts.extend(
+            swap_accounts
+                .iter()
+                .map(|k| AccountMeta::new(*k, *k == payer.pubkey())),
+        );
+
+        let mut data = vec![is_x_to_y as u8];
+        data.extend_from_slice(&input_amt.to_le_bytes());
+        data.extend_from_slice(&min_output_amt.to_le_bytes());
+
+        let tx = Transaction::new_signed_with_payer(
+            &[Instruction {
+                program_id: integrator_id,
+                accounts,
+                data,
+            }],
+            Some(&payer.pubkey()),
+            &[&payer],
+            recent_blockhash,
+        );
+        banks_client.process_transaction(tx).await.unwrap();
+
+        let record = banks_client
+            .get_account(trading_pair)
+            .await
+            .unwrap()
+            .unwrap()
+            .data;
+        assert_eq!(record[0], is_x_to_y as u8);
+        assert_eq!(record[1..9], input_amt.to_le_bytes());
+        assert_eq!(record[9..17], min_output_amt.to_le_bytes());
+        let recorded_keys: Vec<Pubkey> = record[17..]
+            .chunks(32)
+            .take(len)
+            .map(|k| Pubkey::try_from(k).unwrap())
+            .collect();
+        assert_eq!(recorded_keys, swap_accounts);
+    }
+}
+
+#[test]
+fn test_swap_x_to_y_instruction() {
+    let keys: Vec<Pubkey> = (0..SWAP_X_TO_Y_ACCOUNTS)
+        .map(|_| Pubkey::new_unique())
+        .collect();
+    let ix = cpi_client::swap_x_to_y_instruction(
+        obric_solana_v3::accounts::SwapXToY {
+            trading_pair: keys[0],
+            mint_x: keys[1],
+            mint_y: keys[2],
+            mint_x_ctoken: keys[3],
+            mint_y_ctoken: keys[4],
+            user_token_account_x: keys[5],
+            user_token_account_y: keys[6],
+            reserve_x: keys[7],
+            reserve_y: keys[8],
+            reserve_x_ctoken: keys[9],
+            reserve_y_ctoken: keys[10],
+            protocol_fee_y: keys[11],
+            x_price_feed: keys[12],
+            y_price_feed: keys[13],
+            larix_reserve_liquidity_supply_x: keys[14],
+            larix_reserve_liquidity_supply_y: keys[15],
+            larix_destination_reserve_ctoken_x: keys[16],
+            larix_destination_reserve_ctoken_y: keys[17],
+            larix_reserve_x: keys[18],
+            larix_reserve_y: keys[19],
+            larix_obligation: keys[20],
+            larix_lending_market: keys[21],
+            larix_market_authority: keys[22],
+            larix_x_oracle: keys[23],
+            larix_y_oracle: keys[24],
+            user: keys[25],
+            token_program: keys[26],
+            larix_program: keys[27],
+        },
+        7,
+        6,
+    );
+
+    assert_eq!(ix.program_id, obric_solana_v3::ID);
+    assert_eq!(
+        ix.accounts.iter().map(|m| m.pubkey).collect::<Vec<_>>(),
+        keys
+    );
+    assert!(ix.accounts[25].is_signer);
+    assert_eq!(ix.accounts.iter().filter(|m| m.is_signer).count(), 1);
+    assert_eq!(ix.data[..8], SwapXToY::DISCRIMINATOR);
+    let args = SwapXToY::try_from_slice(&ix.data[8..]).unwrap();
+    assert_eq!(args._input_x, 7);
+    assert_eq!(args._min_output_amt, 6);
+}
+
+#[test]
+fn test_swap_y_to_x_instruction() {
+    let keys: Vec<Pubkey> = (0..SWAP_Y_TO_X_ACCOUNTS)
+        .map(|_| Pubkey::new_unique())
+        .collect();
+    let ix = cpi_client::swap_y_to_x_instruction(
+        obric_solana_v3::accounts::SwapYToX {
+            trading_pair: keys[0],
+            mint_x_ctoken: keys[1],
+            mint_y_ctoken: keys[2],
+            user_token_account_x: keys[3],
+            user_token_account_y: keys[4],
+            reserve_x: keys[5],
+            reserve_y: keys[6],
+            reserve_x_ctoken: keys[7],
+            reserve_y_ctoken: keys[8],
+            protocol_fee_x: keys[9],
+            x_price_feed: keys[10],
+            y_price_feed: keys[11],
+            larix_reserve_liquidity_supply_x: keys[12],
+            larix_reserve_liquidity_supply_y: keys[13],
+            larix_destination_reserve_ctoken_x: keys[14],
+            larix_destination_reserve_ctoken_y: keys[15],
+            larix_reserve_x: keys[16],
+            larix_reserve_y: keys[17],
+            larix_obligation: keys[18],
+            larix_lending_market: keys[19],
+            larix_market_authority: keys[20],
+            larix_x_oracle: keys[21],
+            larix_y_oracle: keys[22],
+            user: keys[23],
+            token_program: keys[24],
+            larix_program: keys[25],
+            larix_oracle_program: keys[26],
+            larix_mint: keys[27],
+            larix_reserve_fee_receiver_x: keys[28],
+        },
+        9,
+        8,
+    );
+
+    assert_eq!(ix.program_id, obric_solana_v3::ID);
+    assert_eq!(
+        ix.accounts.iter().map(|m| m.pubkey).collect::<Vec<_>>(),
+        keys
+    );
+    assert!(ix.accounts[23].is_signer);
+    assert_eq!(ix.accounts.iter().filter(|m| m.is_signer).count(), 1);
+    assert_eq!(ix.data[..8], SwapYToX::DISCRIMINATOR);
+    let args = SwapYToX::try_from_slice(&ix.data[8..]).unwrap();
+    assert_eq!(args._input_y, 9);
+    assert_eq!(args._min_output_amt, 8);
+}
@@ -0,0 +1,210 @@
+use obric_solana_v3::state::{Amount, QuoteBreakdown, SSTradingPair};
+use proptest::prelude::*;
+
+/// A pool valued at its prices as a swap leaves it, with deposits and
+/// borrows of X and at least the value of the X it owes deposited in Y. Pools
+/// run from dust to `big_k` in the u128 region; those the curve cannot place
+/// are left out, as the program rejects them.
+fn pool() -> impl Strategy<Value = SSTradingPair> {
+    (
+        (mult(), mult(), 1..=1_000u64),
+        (
+            amount(),
+            amount(),
+            prop_oneof![0..=100_000_000_000_000u64, 0..=1_000_000_000_000_000_000u64],
+        ),
+        (0..=50_000u64, 0..=1_000u64),
+    )
+        .prop_filter_map(
+            "the curve cannot place the pool",
+            |(prices, deposits, fees)| {
+                let (mult_x, mult_y, concentration) = prices;
+                let (deposit_x, borrow_x, extra_y) = deposits;
+                let (fee_millionth, protocol_fee_share_thousandth) = fees;
+                let owed_y = ((borrow_x - borrow_x.min(deposit_x)) as u128 * mult_x as u128)
+                    .div_ceil(mult_y as u128);
+                let mut state = SSTradingPair {
+                    mult_x,
+                    mult_y,
+                    concentration,
+                    deposit_x,
+                    borrow_x,
+                    deposit_y: u64::try_from(owed_y).ok()?.checked_add(extra_y)?,
+                    fee_millionth,
+                    protocol_fee_share_thousandth,
+                    ..SSTradingPair::default()
+                };
+                state.target_y = state.compute_target_y().ok()?.get();
+                state.get_pool_values_for_quoting().ok().map(|_| state)
+            },
+        )
+}
+
+/// Mults as `update_price` sets them: a normalized price of at least 1_000
+/// when the feeds allow, times 10^d on the side with d fewer decimals, so up
+/// to 1e8 and past it for cheap tokens against ones with more decimals.
+fn mult() -> impl Strategy<Value = u64> {
+    prop_oneof![1..=10_000u64, 10_000..=1_000_000_000u64]
+}
+
+/// Deposits and borrows from dust to 10^16.
+fn amount() -> impl Strategy<Value = u64> {
+    prop_oneof![0..=10_000_000_000u64, 0..=10_000_000_000_000_000u64]
+}
+
+/// Inputs from dust to more than any pool holds. Two of them add up in u64.
+fn size() -> impl Strategy<Value = u64> {
+    prop_oneof![
+        1..=1_000u64,
+        1..=1_000_000u64,
+        1..=100_000_000_000_000u64,
+        1..=u64::MAX / 2
+    ]
+}
+
+/// `None` when the pool cannot pay.
+fn quote(state: &SSTradingPair, x_to_y: bool, input: u64) -> Option<QuoteBreakdown> {
+    let quote = match x_to_y {
+        true => state.quote_x_to_y_breakdown(Amount::new(input)),
+        false => state.quote_y_to_x_breakdown(Amount::new(input)),
+    };
+    quote.ok()
+}
+
+/// The pool right after the swap, booked like the program does: X repays the
+/// borrow before it is deposited and is withdrawn before it is borrowed. The
+/// target is not revalued, that waits for the next swap.
+fn swapped(
+    state: &SSTradingPair,
+    x_to_y: bool,
+    input: u64,
+    quote: &QuoteBreakdown,
+) -> SSTradingPair {
+    let mut state = state.clone();
+    let paid = quote.output_after_fee + quote.protocol_fee;
+    if x_to_y {
+        let repay_x = state.borrow_x.min(input);
+        state.borrow_x -= repay_x;
+        state.deposit_x += input - repay_x;
+        state.deposit_y -= paid;
+    } else {
+        let withdraw_x = state.deposit_x.min(paid);
+        state.deposit_x -= withdraw_x;
+        state.borrow_x += paid - withdraw_x;
+        state.deposit_y += input;
+    }
+    state
+}
+
+/// How far the floors of the curve can move a quote: a unit of input at the
+/// marginal price of the pool, in units of output, plus one. `None` when the
+/// curve cannot place the pool.
+fn rounding(state: &SSTradingPair, x_to_y: bool) -> Option<u128> {
+    let (_, current_x_k, current_y_k, _, _) = state.get_pool_values_for_quoting().ok()?;
+    let (input_k, output_k) = match x_to_y {
+        true => (current_x_k.get(), current_y_k.get()),
+        false => (current_y_k.get(), current_x_k.get()),
+    };
+    Some(output_k.div_ceil(input_k.max(1)) + 1)
+}
+
+proptest! {
+    #[test]
+    fn output_grows_with_input(
+        state in pool(),
+        x_to_y in any::<bool>(),
+        input in size(),
+        more in size(),
+    ) {
+        let larger = quote(&state, x_to_y, input + more);
+        prop_assume!(larger.is_some());
+        let larger = larger.unwrap();
+        // a pool paying a trade pays the smaller ones
+        let smaller = quote(&state, x_to_y, input);
+        prop_assert!(smaller.is_some());
+        let smaller = smaller.unwrap();
+        prop_assert!(smaller.output_before_fee <= larger.output_before_fee);
+        prop_assert!(smaller.output_after_fee <= larger.output_after_fee);
+    }
+
+    #[test]
+    fn average_price_worsens_with_size(
+        state in pool(),
+        x_to_y in any::<bool>(),
+        input in size(),
+        more in size(),
+    ) {
+        let larger_input = input + more;
+        let (smaller, larger) = (quote(&state, x_to_y, input), quote(&state, x_to_y, larger_input));
+        prop_assume!(smaller.is_some() && larger.is_some());
+        let (smaller, larger) = (smaller.unwrap(), larger.unwrap());
+        let rounding = rounding(&state, x_to_y).unwrap();
+
+        // output / input does not grow, give or take the rounding of the smaller
+        // quote; a product past u128 is larger than the other side
+        let (input, larger_input) = (input as u128, larger_input as u128);
+        prop_assert!(
+            (smaller.output_before_fee as u128)
+                .saturating_add(rounding)
+                .saturating_mul(larger_input)
+                >= larger.output_before_fee as u128 * input
+        );
+        prop_assert!(
+            (smaller.output_after_fee as u128)
+                .saturating_add(rounding + 1)
+                .saturating_mul(larger_input)
+                >= larger.output_after_fee as u128 * input
+        );
+    }
+
+    #[test]
+    fn round_trips_do_not_pay(state in pool(), x_to_y in any::<bool>(), input in size()) {
+        let there = quote(&state, x_to_y, input);
+        prop_assume!(there.is_some_and(|there| there.output_after_fee > 0));
+        let there = there.unwrap();
+        let after = swapped(&state, x_to_y, input, &there);
+        let (Some(back), Some(rounding_after)) = (
+            quote(&after, !x_to_y, there.output_after_fee),
+            rounding(&after, !x_to_y),
+        ) else {
+            return Ok(());
+        };
+
+        // v3 values `big_k` from the reserves, so the swap keeps the input
+        // side on curve-K exact and leaves the output side where the reserves
+        // put it, less what was paid. The quote solved that side as
+        // floor(big_k / input side), so it can pay up to a unit more than the
+        // curve. The LP fee stays in the reserve and covers that unit; without
+        // one the round trip keeps it, worth less than `rounding` of the input
+        // at the price the swap leaves.
+        let slack = match there.lp_fee > 0 {
+            true => 0,
+            false => rounding_after,
+        };
+        prop_assert!(back.output_after_fee as u128 <= input as u128 + slack);
+    }
+
+    #[test]
+    fn fees_add_up(state in pool(), x_to_y in any::<bool>(), input in size()) {
+        let quote = quote(&state, x_to_y, input);
+        prop_assume!(quote.is_some());
+        let quote = quote.unwrap();
+        prop_assert!(quote.fee_before_rebate <= quote.output_before_fee);
+        // v3 pays no rebate
+        prop_assert_eq!(quote.rebate, 0);
+        prop_assert_eq!(quote.protocol_fee + quote.lp_fee, quote.fee_before_rebate);
+        prop_assert_eq!(
+            quote.output_after_fee + quote.protocol_fee + quote.lp_fee,
+            quote.output_before_fee
+        );
+
+        let split = match x_to_y {
+            true => state.quote_x_to_y(Amount::new(input)),
+            false => state.quote_y_to_x(Amount::new(input)),
+        };
+        prop_assert_eq!(
+            split.unwrap(),
+            (quote.output_after_fee, quote.protocol_fee, quote.lp_fee)
+        );
+    }
+}
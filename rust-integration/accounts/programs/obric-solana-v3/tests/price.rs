@@ -0,0 +1,43 @@
+use anchor_lang::Result;
+use obric_solana_v3::state::{normalize_price_pair, NORMALIZED_EXPO};
+
+/// `normalize_price_pair` with the prices unwrapped.
+fn normalized(price_x: (i64, i32), price_y: (i64, i32)) -> Result<(u64, u64, i32)> {
+    let (price_x, price_y, expo) = normalize_price_pair(price_x, price_y)?;
+    Ok((price_x.get(), price_y.get(), expo))
+}
+
+#[test]
+fn prices_of_a_dollar_or_more_keep_the_usual_exponent() {
+    // SOL and USDC at 10^-8
+    assert_eq!(
+        normalized((150_00000000, -8), (1_00000000, -8)).unwrap(),
+        (150_000, 1_000, NORMALIZED_EXPO)
+    );
+}
+
+#[test]
+fn low_prices_are_not_rounded_to_zero() {
+    // BONK at $0.00002 against USDC, both at 10^-8
+    assert_eq!(
+        normalized((2_000, -8), (1_00000000, -8)).unwrap(),
+        (2_000, 1_00000000, -8)
+    );
+    // $0.0015 stops as soon as it has three digits
+    assert_eq!(
+        normalized((150_000, -8), (1_00000000, -8)).unwrap(),
+        (1_500, 1_000_000, -6)
+    );
+    // never finer than the feeds, and the exponents may differ
+    assert_eq!(normalized((2, -5), (1_000, -3)).unwrap(), (2, 100_000, -5));
+}
+
+#[test]
+fn unusable_prices_are_rejected() {
+    assert!(normalized((0, -8), (1_00000000, -8)).is_err());
+    assert!(normalized((-1_00000000, -8), (1_00000000, -8)).is_err());
+    assert!(normalized((i64::MAX, 2), (1, -8)).is_err());
+    // exponents are untrusted account data
+    assert!(normalized((1, i32::MAX), (1, -8)).is_err());
+    assert!(normalized((1, i32::MIN), (1, i32::MIN)).is_err());
+}
@@ -0,0 +1,467 @@
+#![cfg(feature = "test-only")]
+
+use anchor_lang::prelude::{AccountInfo, Pubkey};
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::{AccountSerialize, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use jupiter_amm_interface::{Amm, KeyedAccount, QuoteParams};
+use larix_lending::state::obligation::Obligation as LarixObligation;
+use larix_lending::state::reserve::Reserve as LarixReserve;
+use obric_sdk_common::fixtures::{
+    mint_account, owned_token_account, packed_account, price_account,
+};
+use obric_sdk_common::simulation::{BanksSimulator, QuoteConsistencyChecker};
+use obric_solana_v3::consts;
+use obric_solana_v3::state::SSTradingPair;
+use obric_v3_sdk::obric_v3_amm::ObricV3Amm;
+use solana_program_test::{processor, BanksClient, BanksClientError, ProgramTest};
+use solana_sdk::account::Account;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use std::collections::HashMap;
+
+// $150 and $1 with a pyth exponent of -8
+const PRICE_X: i64 = 150_00000000;
+const PRICE_Y: i64 = 1_00000000;
+const DECIMALS_X: u8 = 9;
+const DECIMALS_Y: u8 = 6;
+const DEPOSIT_X: u64 = 100_000_000_000;
+const DEPOSIT_Y: u64 = 15_000_000_000;
+
+fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    // anchor's entry wants `&'info [AccountInfo<'info>]`
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    obric_solana_v3::entry(program_id, accounts, data)
+}
+
+fn add_mint(program_test: &mut ProgramTest, mint: Pubkey, decimals: u8) {
+    program_test.add_account(mint, mint_account(decimals));
+}
+
+fn add_token_account(
+    program_test: &mut ProgramTest,
+    mint: Pubkey,
+    owner: Pubkey,
+    amount: u64,
+) -> Pubkey {
+    let token_account = Pubkey::new_unique();
+    program_test.add_account(token_account, owned_token_account(mint, owner, amount));
+    token_account
+}
+
+/// The checker swaps from and to the user's associated token accounts.
+fn add_user_token_account(
+    program_test: &mut ProgramTest,
+    mint: Pubkey,
+    user: Pubkey,
+    amount: u64,
+) -> Pubkey {
+    let token_account = get_associated_token_address(&user, &mint);
+    program_test.add_account(token_account, owned_token_account(mint, user, amount));
+    token_account
+}
+
+/// Adds a Larix reserve for `mint` with its liquidity and collateral supply accounts.
+fn add_larix_reserve(program_test: &mut ProgramTest, mint: Pubkey) -> LarixReserve {
+    let ctoken_mint = Pubkey::new_unique();
+    add_mint(program_test, ctoken_mint, 9);
+    let authority = consts::larix::market::authority::ID;
+
+    let mut reserve = LarixReserve::default();
+    reserve.version = larix_lending::state::PROGRAM_VERSION;
+    reserve.lending_market = consts::larix::market::ID;
+    reserve.liquidity.mint_pubkey = mint;
+    reserve.liquidity.supply_pubkey = add_token_account(program_test, mint, authority, 0);
+    reserve.liquidity.fee_receiver = Pubkey::new_unique();
+    reserve.collateral.mint_pubkey = ctoken_mint;
+    reserve.collateral.supply_pubkey = add_token_account(program_test, ctoken_mint, authority, 0);
+    program_test.add_account(
+        consts::mint_to_larix_reserve(&mint).unwrap(),
+        packed_account(reserve.clone(), larix_lending::ID),
+    );
+    reserve
+}
+
+async fn balance(banks_client: &mut BanksClient, token_account: Pubkey) -> u64 {
+    banks_client
+        .get_packed_account_data::<spl_token::state::Account>(token_account)
+        .await
+        .unwrap()
+        .amount
+}
+
+struct Fixture {
+    banks_client: BanksClient,
+    payer: Keypair,
+    user: Keypair,
+    trading_pair: Pubkey,
+    state: SSTradingPair,
+    larix_reserve_x: LarixReserve,
+    larix_reserve_y: LarixReserve,
+    obligation: Pubkey,
+    user_token_account_x: Pubkey,
+    user_token_account_y: Pubkey,
+}
+
+async fn setup() -> Fixture {
+    let mut program_test = ProgramTest::new(
+        "obric_solana_v3",
+        obric_solana_v3::ID,
+        processor!(process_instruction),
+    );
+
+    // Larix reserves are only known for these mints
+    let mint_x = consts::mints::sol::ID;
+    let mint_y = consts::mints::usdc::ID;
+    add_mint(&mut program_test, mint_x, DECIMALS_X);
+    add_mint(&mut program_test, mint_y, DECIMALS_Y);
+    add_mint(&mut program_test, consts::mints::larix::ID, 6);
+    let (trading_pair, bump) = Pubkey::find_program_address(
+        &[
+            consts::TRADING_PAIR_SEED.as_bytes(),
+            mint_x.as_ref(),
+            mint_y.as_ref(),
+        ],
+        &obric_solana_v3::ID,
+    );
+
+    let larix_reserve_x = add_larix_reserve(&mut program_test, mint_x);
+    let larix_reserve_y = add_larix_reserve(&mut program_test, mint_y);
+    let (obligation, _) = Pubkey::find_program_address(
+        &[
+            consts::LARIX_OBLIGATION_SEED.as_bytes(),
+            mint_x.as_ref(),
+            mint_y.as_ref(),
+        ],
+        &obric_solana_v3::ID,
+    );
+    let mut larix_obligation = LarixObligation::default();
+    larix_obligation.version = larix_lending::state::PROGRAM_VERSION;
+    program_test.add_account(
+        obligation,
+        packed_account(larix_obligation, larix_lending::ID),
+    );
+
+    let x_price_feed = Pubkey::new_unique();
+    let y_price_feed = Pubkey::new_unique();
+    program_test.add_account(x_price_feed, price_account(PRICE_X));
+    program_test.add_account(y_price_feed, price_account(PRICE_Y));
+
+    // tokens stay in the reserves in the test-only build; leave room for borrowing X
+    let reserve_x = add_token_account(&mut program_test, mint_x, trading_pair, 10 * DEPOSIT_X);
+    let reserve_y = add_token_account(&mut program_test, mint_y, trading_pair, DEPOSIT_Y);
+    let reserve_x_ctoken = add_token_account(
+        &mut program_test,
+        larix_reserve_x.collateral.mint_pubkey,
+        trading_pair,
+        0,
+    );
+    let reserve_y_ctoken = add_token_account(
+        &mut program_test,
+        larix_reserve_y.collateral.mint_pubkey,
+        trading_pair,
+        0,
+    );
+    let protocol_fee_x = add_token_account(&mut program_test, mint_x, Pubkey::new_unique(), 0);
+    let protocol_fee_y = add_token_account(&mut program_test, mint_y, Pubkey::new_unique(), 0);
+
+    let user = Keypair::new();
+    let user_token_account_x =
+        add_user_token_account(&mut program_test, mint_x, user.pubkey(), 100_000_000_000);
+    let user_token_account_y =
+        add_user_token_account(&mut program_test, mint_y, user.pubkey(), 100_000_000_000);
+
+    let state = SSTradingPair {
+        is_initialized: true,
+        x_price_feed_id: x_price_feed,
+        y_price_feed_id: y_price_feed,
+        reserve_x,
+        reserve_y,
+        reserve_x_ctoken,
+        reserve_y_ctoken,
+        protocol_fee_x,
+        protocol_fee_y,
+        bump,
+        mint_x,
+        mint_y,
+        deposit_x: DEPOSIT_X,
+        deposit_y: DEPOSIT_Y,
+        concentration: 50,
+        fee_millionth: 1_000,
+        protocol_fee_share_thousandth: 200,
+        decimals_x: DECIMALS_X,
+        decimals_y: DECIMALS_Y,
+        ..SSTradingPair::default()
+    };
+    let mut data = vec![];
+    state.try_serialize(&mut data).unwrap();
+    program_test.add_account(
+        trading_pair,
+        Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: obric_solana_v3::ID,
+            ..Account::default()
+        },
+    );
+
+    let (banks_client, payer, _) = program_test.start().await;
+    Fixture {
+        banks_client,
+        payer,
+        user,
+        trading_pair,
+        state,
+        larix_reserve_x,
+        larix_reserve_y,
+        obligation,
+        user_token_account_x,
+        user_token_account_y,
+    }
+}
+
+/// The pool as the SDK sees it after `update`.
+async fn sdk_pool(fixture: &mut Fixture) -> ObricV3Amm {
+    let key = fixture.trading_pair;
+    let account = fixture
+        .banks_client
+        .get_account(key)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut amm = ObricV3Amm::from_keyed_account(&KeyedAccount {
+        key,
+        account,
+        params: None,
+    })
+    .unwrap();
+    let mut accounts_map = HashMap::new();
+    for key in amm.get_accounts_to_update() {
+        let account = fixture
+            .banks_client
+            .get_account(key)
+            .await
+            .unwrap()
+            .unwrap();
+        accounts_map.insert(key, account);
+    }
+    amm.update(&accounts_map).unwrap();
+    amm
+}
+
+/// The SDK's quote: the output and the protocol fee.
+async fn sdk_quote(fixture: &mut Fixture, is_x_to_y: bool, in_amount: u64) -> (u64, u64) {
+    let amm = sdk_pool(fixture).await;
+    let (input_mint, output_mint) = if is_x_to_y {
+        (amm.state.mint_x, amm.state.mint_y)
+    } else {
+        (amm.state.mint_y, amm.state.mint_x)
+    };
+    let quote = amm
+        .quote(&QuoteParams {
+            input_mint,
+            in_amount,
+            output_mint,
+        })
+        .unwrap();
+    (quote.out_amount, quote.fee_amount)
+}
+
+fn swap_instruction(
+    fixture: &Fixture,
+    is_x_to_y: bool,
+    input_amt: u64,
+    min_output_amt: u64,
+) -> Instruction {
+    let state = &fixture.state;
+    let (larix_reserve_x, larix_reserve_y) = (&fixture.larix_reserve_x, &fixture.larix_reserve_y);
+    let (accounts, data) = if is_x_to_y {
+        (
+            obric_solana_v3::accounts::SwapXToY {
+                trading_pair: fixture.trading_pair,
+                mint_x: state.mint_x,
+                mint_y: state.mint_y,
+                mint_x_ctoken: larix_reserve_x.collateral.mint_pubkey,
+                mint_y_ctoken: larix_reserve_y.collateral.mint_pubkey,
+                user_token_account_x: fixture.user_token_account_x,
+                user_token_account_y: fixture.user_token_account_y,
+                reserve_x: state.reserve_x,
+                reserve_y: state.reserve_y,
+                reserve_x_ctoken: state.reserve_x_ctoken,
+                reserve_y_ctoken: state.reserve_y_ctoken,
+                protocol_fee_y: state.protocol_fee_y,
+                x_price_feed: state.x_price_feed_id,
+                y_price_feed: state.y_price_feed_id,
+                larix_reserve_liquidity_supply_x: larix_reserve_x.liquidity.supply_pubkey,
+                larix_reserve_liquidity_supply_y: larix_reserve_y.liquidity.supply_pubkey,
+                larix_destination_reserve_ctoken_x: larix_reserve_x.collateral.supply_pubkey,
+                larix_destination_reserve_ctoken_y: larix_reserve_y.collateral.supply_pubkey,
+                larix_reserve_x: consts::mint_to_larix_reserve(&state.mint_x).unwrap(),
+                larix_reserve_y: consts::mint_to_larix_reserve(&state.mint_y).unwrap(),
+                larix_obligation: fixture.obligation,
+                larix_lending_market: consts::larix::market::ID,
+                larix_market_authority: consts::larix::market::authority::ID,
+                larix_x_oracle: Pubkey::new_unique(),
+                larix_y_oracle: Pubkey::new_unique(),
+                user: fixture.user.pubkey(),
+                token_program: spl_token::ID,
+                larix_program: larix_lending::ID,
+            }
+            .to_account_metas(None),
+            obric_solana_v3::instruction::SwapXToY {
+                _input_x: input_amt,
+                _min_output_amt: min_output_amt,
+            }
+            .data(),
+        )
+    } else {
+        (
+            obric_solana_v3::accounts::SwapYToX {
+                trading_pair: fixture.trading_pair,
+                mint_x_ctoken: larix_reserve_x.collateral.mint_pubkey,
+                mint_y_ctoken: larix_reserve_y.collateral.mint_pubkey,
+                user_token_account_x: fixture.user_token_account_x,
+                user_token_account_y: fixture.user_token_account_y,
+                reserve_x: state.reserve_x,
+                reserve_y: state.reserve_y,
+                reserve_x_ctoken: state.reserve_x_ctoken,
+                reserve_y_ctoken: state.reserve_y_ctoken,
+                protocol_fee_x: state.protocol_fee_x,
+                x_price_feed: state.x_price_feed_id,
+                y_price_feed: state.y_price_feed_id,
+                larix_reserve_liquidity_supply_x: larix_reserve_x.liquidity.supply_pubkey,
+                larix_reserve_liquidity_supply_y: larix_reserve_y.liquidity.supply_pubkey,
+                larix_destination_reserve_ctoken_x: larix_reserve_x.collateral.supply_pubkey,
+                larix_destination_reserve_ctoken_y: larix_reserve_y.collateral.supply_pubkey,
+                larix_reserve_x: consts::mint_to_larix_reserve(&state.mint_x).unwrap(),
+                larix_reserve_y: consts::mint_to_larix_reserve(&state.mint_y).unwrap(),
+                larix_obligation: fixture.obligation,
+                larix_lending_market: consts::larix::market::ID,
+                larix_market_authority: consts::larix::market::authority::ID,
+                larix_x_oracle: Pubkey::new_unique(),
+                larix_y_oracle: Pubkey::new_unique(),
+                user: fixture.user.pubkey(),
+                token_program: spl_token::ID,
+                larix_program: larix_lending::ID,
+                larix_oracle_program: consts::larix::oracle::ID,
+                larix_mint: consts::mints::larix::ID,
+                larix_reserve_fee_receiver_x: larix_reserve_x.liquidity.fee_receiver,
+            }
+            .to_account_metas(None),
+            obric_solana_v3::instruction::SwapYToX {
+                _input_y: input_amt,
+                _min_output_amt: min_output_amt,
+            }
+            .data(),
+        )
+    };
+    Instruction {
+        program_id: obric_solana_v3::ID,
+        accounts,
+        data,
+    }
+}
+
+async fn swap(
+    fixture: &mut Fixture,
+    is_x_to_y: bool,
+    input_amt: u64,
+    min_output_amt: u64,
+) -> Result<(), BanksClientError> {
+    let ix = swap_instruction(fixture, is_x_to_y, input_amt, min_output_amt);
+    let recent_blockhash = fixture.banks_client.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&fixture.payer.pubkey()),
+        &[&fixture.payer, &fixture.user],
+        recent_blockhash,
+    );
+    fixture.banks_client.process_transaction(tx).await
+}
+
+#[tokio::test]
+async fn test_swap_matches_quote() {
+    let mut fixture = setup().await;
+
+    for (is_x_to_y, input_amt) in [
+        (true, 1_000_000_000u64),
+        (false, 300_000_000),
+        (true, 12_345),
+        (false, 7_654_321),
+        // large enough to push borrow_x above zero
+        (false, 20_000_000_000),
+    ] {
+        let (expected_output, expected_protocol_fee) =
+            sdk_quote(&mut fixture, is_x_to_y, input_amt).await;
+
+        let (user_out, protocol_fee) = if is_x_to_y {
+            (fixture.user_token_account_y, fixture.state.protocol_fee_y)
+        } else {
+            (fixture.user_token_account_x, fixture.state.protocol_fee_x)
+        };
+        let user_out_before = balance(&mut fixture.banks_client, user_out).await;
+        let protocol_fee_before = balance(&mut fixture.banks_client, protocol_fee).await;
+
+        swap(&mut fixture, is_x_to_y, input_amt, expected_output)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            balance(&mut fixture.banks_client, user_out).await - user_out_before,
+            expected_output
+        );
+        assert_eq!(
+            balance(&mut fixture.banks_client, protocol_fee).await - protocol_fee_before,
+            expected_protocol_fee
+        );
+    }
+
+    assert!(sdk_pool(&mut fixture).await.state.borrow_x > 0);
+}
+
+#[tokio::test]
+async fn test_swap_rejects_min_output_above_quote() {
+    let mut fixture = setup().await;
+    let (expected_output, _) = sdk_quote(&mut fixture, false, 1_000_000).await;
+
+    assert!(swap(&mut fixture, false, 1_000_000, expected_output + 1)
+        .await
+        .is_err());
+    swap(&mut fixture, false, 1_000_000, expected_output)
+        .await
+        .unwrap();
+}
+
+#[test]
+fn test_quote_consistency_checker() {
+    // the simulator drives the bank from outside an async test
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let mut fixture = runtime.block_on(setup());
+    let mut amm = runtime.block_on(sdk_pool(&mut fixture));
+    let checker = QuoteConsistencyChecker {
+        simulator: BanksSimulator {
+            client: fixture.banks_client.clone(),
+            runtime: &runtime,
+            payer: &fixture.payer,
+            user: &fixture.user,
+        },
+        tolerance_bps: 0,
+    };
+
+    // the SDK's own instructions, paid exactly the quote by the test-only program
+    let swaps = checker.check(&mut amm, &[12_345, 300_000_000]).unwrap();
+    assert_eq!(swaps.len(), 4);
+    for swap in &swaps {
+        assert!(swap.executed_out > 0);
+        assert_eq!(swap.divergence_bps(), 0);
+    }
+}
@@ -0,0 +1,173 @@
+use obric_solana_v3::state::{normalize_price_pair, Amount, NormalizedPrice, SSTradingPair};
+
+/// `compute_target_y` as it was in u64.
+fn target_y_in_u64(state: &SSTradingPair) -> Option<u64> {
+    let deposit_value = state
+        .deposit_x
+        .checked_mul(state.mult_x)?
+        .checked_add(state.deposit_y.checked_mul(state.mult_y)?)?;
+    let borrow_value = state
+        .borrow_x
+        .checked_mul(state.mult_x)?
+        .checked_add(state.borrow_y.checked_mul(state.mult_y)?)?;
+    deposit_value
+        .checked_sub(borrow_value)?
+        .checked_div(state.mult_y)
+}
+
+/// `get_pool_values_for_quoting` as it was in u64.
+fn pool_values_in_u64(state: &SSTradingPair) -> Option<(u128, u64, u64, u64, u64)> {
+    let target_y_k = state.concentration.checked_mul(state.target_y)?;
+    let target_x_k = target_y_k
+        .checked_mul(state.mult_y)?
+        .checked_div(state.mult_x)?;
+    let current_y_k = target_y_k
+        .checked_add(state.deposit_y)?
+        .checked_sub(state.target_y)?;
+    let current_x_k = target_x_k
+        .checked_add(state.deposit_x)?
+        .checked_sub(state.borrow_x)?;
+    let big_k = current_x_k as u128 * current_y_k as u128;
+    let temp = target_x_k
+        .checked_div(state.concentration)?
+        .checked_add(state.deposit_x)?;
+    let available_x = temp.saturating_sub(state.borrow_x);
+    Some((
+        big_k,
+        current_x_k,
+        current_y_k,
+        available_x,
+        state.deposit_y,
+    ))
+}
+
+/// SOL (9 decimals) against USDC (6 decimals) at $150 / $1.
+fn sol_usdc(deposit_x: u64, borrow_x: u64, deposit_y: u64) -> SSTradingPair {
+    let mut state = SSTradingPair {
+        concentration: 100,
+        decimals_x: 9,
+        decimals_y: 6,
+        deposit_x,
+        borrow_x,
+        deposit_y,
+        ..SSTradingPair::default()
+    };
+    state
+        .update_price(NormalizedPrice::new(150_000), NormalizedPrice::new(1_000))
+        .unwrap();
+    state
+}
+
+#[test]
+fn matches_the_u64_math_where_it_did_not_overflow() {
+    for deposit_y in [1_000_000_000, 10_000_000_000] {
+        for (deposit_x, borrow_x) in [(0, 0), (5_000_000_000, 0), (0, 2_000_000_000), (7, 3)] {
+            let mut state = sol_usdc(deposit_x, borrow_x, deposit_y);
+            let target_y = state.compute_target_y().unwrap();
+            assert_eq!(Some(target_y.get()), target_y_in_u64(&state));
+            state.update_target_y(target_y).unwrap();
+
+            let (big_k, current_x_k, current_y_k, available_x, available_y) =
+                state.get_pool_values_for_quoting().unwrap();
+            let (big_k_u64, current_x_k_u64, current_y_k_u64, available_x_u64, available_y_u64) =
+                pool_values_in_u64(&state).unwrap();
+            assert_eq!(big_k.get(), big_k_u64);
+            assert_eq!(current_x_k.get(), current_x_k_u64 as u128);
+            assert_eq!(current_y_k.get(), current_y_k_u64 as u128);
+            assert_eq!(
+                (available_x.get(), available_y.get()),
+                (available_x_u64, available_y_u64)
+            );
+        }
+    }
+}
+
+#[test]
+fn target_y_past_the_u64_boundary() {
+    // the largest deposit of X whose value still fit in u64, and one more
+    let mult_x = sol_usdc(0, 0, 0).mult_x;
+    let last = u64::MAX / mult_x;
+    let state = sol_usdc(last, 0, 0);
+    assert_eq!(
+        state.compute_target_y().unwrap().get(),
+        target_y_in_u64(&state).unwrap()
+    );
+    let state = sol_usdc(last + 1, 0, 0);
+    assert_eq!(target_y_in_u64(&state), None);
+    assert_eq!(
+        state.compute_target_y().unwrap().get() as u128,
+        (last as u128 + 1) * mult_x as u128 / state.mult_y as u128
+    );
+
+    // 100M SOL and 10B USDC
+    let state = sol_usdc(100_000_000_000_000_000, 0, 10_000_000_000_000_000);
+    assert_eq!(target_y_in_u64(&state), None);
+    assert_eq!(
+        state.compute_target_y().unwrap().get(),
+        25_000_000_000_000_000
+    );
+
+    // a target that does not fit in u64 fails instead of wrapping
+    let state = sol_usdc(u64::MAX, 0, u64::MAX);
+    assert!(state.compute_target_y().is_err());
+    // and so do borrows worth more than the deposits
+    let state = sol_usdc(0, 1, 0);
+    assert!(state.compute_target_y().is_err());
+}
+
+#[test]
+fn pool_values_past_the_u64_boundary() {
+    // X at $150 against Y at $1, both with 6 decimals
+    let mut state = SSTradingPair {
+        concentration: 100,
+        decimals_x: 6,
+        decimals_y: 6,
+        ..SSTradingPair::default()
+    };
+    state
+        .update_price(NormalizedPrice::new(150_000), NormalizedPrice::new(1_000))
+        .unwrap();
+    // the smallest target for which `concentration * target_y` leaves u64
+    let target_y = u64::MAX / 100 + 1;
+    state.deposit_y = target_y;
+    state.update_target_y(Amount::new(target_y)).unwrap();
+    assert!(pool_values_in_u64(&state).is_none());
+
+    let (big_k, current_x_k, current_y_k, available_x, _) =
+        state.get_pool_values_for_quoting().unwrap();
+    let (current_x_k, current_y_k) = (current_x_k.get(), current_y_k.get());
+    assert_eq!(current_y_k, target_y as u128 * 100);
+    assert_eq!(current_x_k, current_y_k * 1_000 / 150_000);
+    assert_eq!(big_k.get(), current_x_k * current_y_k);
+    assert_eq!(available_x.get() as u128, current_x_k / 100);
+
+    let (output, _, _) = state.quote_y_to_x(Amount::new(1_500_000_000)).unwrap();
+    assert!(output > 9_900_000 && output <= 10_000_000);
+    let (output, _, _) = state.quote_x_to_y(Amount::new(10_000_000)).unwrap();
+    assert!(output > 1_490_000_000 && output <= 1_500_000_000);
+}
+
+#[test]
+fn quotes_a_bonk_priced_pool() {
+    // BONK at $0.00002 with 5 decimals against USDC, 20k USDC deposited
+    let (price_x, price_y, _) = normalize_price_pair((2_000, -8), (1_00000000, -8)).unwrap();
+    let mut state = SSTradingPair {
+        concentration: 100,
+        decimals_x: 5,
+        decimals_y: 6,
+        deposit_y: 20_000_000_000,
+        fee_millionth: 1_000,
+        ..SSTradingPair::default()
+    };
+    state.update_price(price_x, price_y).unwrap();
+    assert_eq!((state.mult_x, state.mult_y), (20_000, 100_000_000));
+    let target_y = state.compute_target_y().unwrap();
+    assert_eq!(target_y.get(), 20_000_000_000);
+    state.update_target_y(target_y).unwrap();
+    // `target_y_k * mult_y` is past u64 here
+    assert!(pool_values_in_u64(&state).is_none());
+
+    // 1M BONK for $20, less the 10 bps fee and a little slippage
+    let (output, _, _) = state.quote_x_to_y(Amount::new(100_000_000_000)).unwrap();
+    assert!(output > 19_950_000 && output < 20_000_000);
+}
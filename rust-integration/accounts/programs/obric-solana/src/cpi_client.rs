@@ -0,0 +1,53 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::InstructionData;
+
+pub use crate::cpi::accounts::Swap;
+
+/// Swaps through an Obric v2 trading pair from another program.
+/// `ctx.program` must be the Obric v2 program account.
+pub fn swap<'a, 'b, 'c, 'info>(
+    ctx: CpiContext<'a, 'b, 'c, 'info, Swap<'info>>,
+    is_x_to_y: bool,
+    input_amt: u64,
+    min_output_amt: u64,
+) -> Result<()> {
+    crate::cpi::swap(ctx, is_x_to_y, input_amt, min_output_amt)
+}
+
+/// Swaps `input_x` of X for Y, `swap` with `is_x_to_y` set.
+pub fn swap_x_to_y<'a, 'b, 'c, 'info>(
+    ctx: CpiContext<'a, 'b, 'c, 'info, Swap<'info>>,
+    input_x: u64,
+    min_output_amt: u64,
+) -> Result<()> {
+    swap(ctx, true, input_x, min_output_amt)
+}
+
+/// Swaps `input_y` of Y for X, `swap` with `is_x_to_y` unset.
+pub fn swap_y_to_x<'a, 'b, 'c, 'info>(
+    ctx: CpiContext<'a, 'b, 'c, 'info, Swap<'info>>,
+    input_y: u64,
+    min_output_amt: u64,
+) -> Result<()> {
+    swap(ctx, false, input_y, min_output_amt)
+}
+
+/// Builds the raw `swap` instruction, for clients that only have pubkeys.
+pub fn swap_instruction(
+    accounts: crate::accounts::Swap,
+    is_x_to_y: bool,
+    input_amt: u64,
+    min_output_amt: u64,
+) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::Swap {
+            _is_x_to_y: is_x_to_y,
+            _input_amt: input_amt,
+            _min_output_amt: min_output_amt,
+        }
+        .data(),
+    }
+}
@@ -1,4 +1,5 @@
 use anchor_lang::error_code;
+use obric_math::{MathError, PriceError};
 
 #[error_code]
 pub enum ObricError {
@@ -33,3 +34,21 @@ pub enum ObricError {
 pub type AmmError = ObricError;
 
 impl std::error::Error for ObricError {}
+
+impl From<MathError> for ObricError {
+    fn from(err: MathError) -> Self {
+        match err {
+            MathError::Overflow => ObricError::NumOverflowing,
+            MathError::NegativePrice => ObricError::NegativePrice,
+            MathError::PriceOutOfRange => ObricError::PythError,
+            MathError::InsufficientActiveX => ObricError::InsufficientActiveX,
+            MathError::InsufficientActiveY => ObricError::InsufficientActiveY,
+        }
+    }
+}
+
+impl From<PriceError> for ObricError {
+    fn from(err: PriceError) -> Self {
+        err.source.into()
+    }
+}
@@ -1,6 +1,12 @@
 pub mod consts;
+#[cfg(feature = "cpi")]
+pub mod cpi_client;
 pub mod errors;
+#[cfg(feature = "serde")]
+pub mod serde_pubkey;
 pub mod state;
+#[cfg(feature = "test-only")]
+pub mod test_only;
 
 use crate::errors::ObricError;
 use crate::state::{PriceFeed, SSTradingPair};
@@ -20,6 +26,8 @@ pub mod obric_solana {
         _input_amt: u64,
         _min_output_amt: u64,
     ) -> Result<()> {
+        #[cfg(feature = "test-only")]
+        crate::test_only::swap(_ctx, _is_x_to_y, _input_amt, _min_output_amt)?;
         Ok(())
     }
 }
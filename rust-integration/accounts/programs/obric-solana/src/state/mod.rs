@@ -3,3 +3,9 @@ pub use trading_pair::*;
 
 pub mod price_feed;
 pub use price_feed::*;
+
+pub mod quote;
+pub use quote::*;
+
+pub use obric_math::units;
+pub use units::*;
@@ -0,0 +1,121 @@
+use crate::consts::TRADING_PAIR_SEED;
+use crate::errors::ObricError;
+use crate::state::{Amount, PriceFeed};
+use crate::Swap;
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Transfer};
+
+/// Executes a swap with the `SSTradingPair` curve so that SDK quotes can be
+/// checked against real token movements under `solana-program-test`.
+/// Only compiled with the `test-only` feature.
+pub fn swap(
+    ctx: Context<Swap>,
+    is_x_to_y: bool,
+    input_amt: u64,
+    min_output_amt: u64,
+) -> Result<()> {
+    require!(input_amt > 0, ObricError::InvalidInputAmount);
+    let accounts = ctx.accounts;
+
+    let (price_x, price_y, _) =
+        PriceFeed::normalized_pair(&accounts.x_price_feed, &accounts.y_price_feed)
+            .map_err(ObricError::from)?;
+    accounts.trading_pair.update_price(
+        price_x,
+        price_y,
+        accounts.mint_x.decimals,
+        accounts.mint_y.decimals,
+    )?;
+
+    let current_x = Amount::new(accounts.reserve_x.amount);
+    let current_y = Amount::new(accounts.reserve_y.amount);
+    let (output, protocol_fee, _lp_fee) = if is_x_to_y {
+        accounts
+            .trading_pair
+            .quote_x_to_y(Amount::new(input_amt), current_x, current_y)?
+    } else {
+        accounts
+            .trading_pair
+            .quote_y_to_x(Amount::new(input_amt), current_x, current_y)?
+    };
+    if output == 0 {
+        return if is_x_to_y {
+            err!(ObricError::InsufficientActiveY)
+        } else {
+            err!(ObricError::InsufficientActiveX)
+        };
+    }
+    require!(
+        output >= min_output_amt,
+        ObricError::OutputAmountLessThanExpected
+    );
+
+    let (user_source, reserve_in, reserve_out, user_destination) = if is_x_to_y {
+        (
+            &accounts.user_token_account_x,
+            &accounts.reserve_x,
+            &accounts.reserve_y,
+            &accounts.user_token_account_y,
+        )
+    } else {
+        (
+            &accounts.user_token_account_y,
+            &accounts.reserve_y,
+            &accounts.reserve_x,
+            &accounts.user_token_account_x,
+        )
+    };
+
+    token::transfer(
+        CpiContext::new(
+            accounts.token_program.to_account_info(),
+            Transfer {
+                from: user_source.to_account_info(),
+                to: reserve_in.to_account_info(),
+                authority: accounts.user.to_account_info(),
+            },
+        ),
+        input_amt,
+    )?;
+
+    let mint_x = accounts.mint_x.key();
+    let mint_y = accounts.mint_y.key();
+    let bump = [accounts.trading_pair.bump];
+    let seeds: &[&[u8]] = &[
+        TRADING_PAIR_SEED.as_bytes(),
+        mint_x.as_ref(),
+        mint_y.as_ref(),
+        &bump,
+    ];
+    let signer = &[seeds];
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            Transfer {
+                from: reserve_out.to_account_info(),
+                to: user_destination.to_account_info(),
+                authority: accounts.trading_pair.to_account_info(),
+            },
+            signer,
+        ),
+        output,
+    )?;
+
+    if protocol_fee > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                accounts.token_program.to_account_info(),
+                Transfer {
+                    from: reserve_out.to_account_info(),
+                    to: accounts.protocol_fee.to_account_info(),
+                    authority: accounts.trading_pair.to_account_info(),
+                },
+                signer,
+            ),
+            protocol_fee,
+        )?;
+    }
+
+    Ok(())
+}
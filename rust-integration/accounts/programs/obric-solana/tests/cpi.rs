@@ -0,0 +1,179 @@
+#![cfg(feature = "cpi")]
+
+use anchor_lang::prelude::{AccountInfo, CpiContext, Pubkey};
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program_error::ProgramError;
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use obric_solana::cpi_client;
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::account::Account;
+use solana_sdk::signature::Signer;
+use solana_sdk::transaction::Transaction;
+
+const SWAP_ACCOUNTS: usize = 12;
+const RECORD_LEN: usize = 1 + 8 + 8 + SWAP_ACCOUNTS * 32;
+
+/// Stands in for Obric v2: checks the instruction and records the decoded
+/// arguments and account keys into the trading pair account.
+fn mock_obric(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if data.len() < 8 || data[..8] != obric_solana::instruction::Swap::DISCRIMINATOR {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let args = obric_solana::instruction::Swap::try_from_slice(&data[8..])
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+    if accounts.len() != SWAP_ACCOUNTS {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if !accounts[10].is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut record = accounts[0].try_borrow_mut_data()?;
+    record[0] = args._is_x_to_y as u8;
+    record[1..9].copy_from_slice(&args._input_amt.to_le_bytes());
+    record[9..17].copy_from_slice(&args._min_output_amt.to_le_bytes());
+    for (i, account) in accounts.iter().enumerate() {
+        record[17 + i * 32..17 + (i + 1) * 32].copy_from_slice(account.key.as_ref());
+    }
+    Ok(())
+}
+
+/// A minimal integrator: the first account is the Obric program and the rest
+/// are the swap accounts, in `Swap` order.
+fn integrator(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let (obric_program, a) = accounts
+        .split_first()
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let is_x_to_y = data[0] == 1;
+    let input_amt = u64::from_le_bytes(data[1..9].try_into().unwrap());
+    let min_output_amt = u64::from_le_bytes(data[9..17].try_into().unwrap());
+
+    let ctx = CpiContext::new(
+        obric_program.clone(),
+        cpi_client::Swap {
+            trading_pair: a[0].clone(),
+            mint_x: a[1].clone(),
+            mint_y: a[2].clone(),
+            reserve_x: a[3].clone(),
+            reserve_y: a[4].clone(),
+            user_token_account_x: a[5].clone(),
+            user_token_account_y: a[6].clone(),
+            protocol_fee: a[7].clone(),
+            x_price_feed: a[8].clone(),
+            y_price_feed: a[9].clone(),
+            user: a[10].clone(),
+            token_program: a[11].clone(),
+        },
+    );
+    if is_x_to_y {
+        cpi_client::swap_x_to_y(ctx, input_amt, min_output_amt)?;
+    } else {
+        cpi_client::swap_y_to_x(ctx, input_amt, min_output_amt)?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_swap_cpi() {
+    let integrator_id = Pubkey::new_unique();
+    let mut program_test =
+        ProgramTest::new("obric_solana", obric_solana::ID, processor!(mock_obric));
+    program_test.add_program("integrator", integrator_id, processor!(integrator));
+
+    let trading_pair = Pubkey::new_unique();
+    program_test.add_account(
+        trading_pair,
+        Account {
+            lamports: 1_000_000_000,
+            data: vec![0; RECORD_LEN],
+            owner: obric_solana::ID,
+            ..Account::default()
+        },
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    for (is_x_to_y, input_amt, min_output_amt) in [(true, 1_000u64, 990u64), (false, 2_000, 1_980)]
+    {
+        let mut swap_accounts = vec![trading_pair];
+        swap_accounts.extend((0..9).map(|_| Pubkey::new_unique()));
+        swap_accounts.push(payer.pubkey());
+        swap_accounts.push(anchor_spl::token::ID);
+
+        let mut accounts = vec![AccountMeta::new_readonly(obric_solana::ID, false)];
+        accounts.extend(
+            swap_accounts[..10]
+                .iter()
+                .map(|k| AccountMeta::new(*k, false)),
+        );
+        accounts.push(AccountMeta::new(payer.pubkey(), true));
+        accounts.push(AccountMeta::new_readonly(anchor_spl::token::ID, false));
+
+        let mut data = vec![is_x_to_y as u8];
+        data.extend_from_slice(&input_amt.to_le_bytes());
+        data.extend_from_slice(&min_output_amt.to_le_bytes());
+
+        let tx = Transaction::new_signed_with_payer(
+            &[Instruction {
+                program_id: integrator_id,
+                accounts,
+                data,
+            }],
+            Some(&payer.pubkey()),
+            &[&payer],
+            recent_blockhash,
+        );
+        banks_client.process_transaction(tx).await.unwrap();
+
+        let record = banks_client
+            .get_account(trading_pair)
+            .await
+            .unwrap()
+            .unwrap()
+            .data;
+        assert_eq!(record[0], is_x_to_y as u8);
+        assert_eq!(record[1..9], input_amt.to_le_bytes());
+        assert_eq!(record[9..17], min_output_amt.to_le_bytes());
+        let recorded_keys: Vec<Pubkey> = record[17..]
+            .chunks(32)
+            .map(|k| Pubkey::try_from(k).unwrap())
+            .collect();
+        assert_eq!(recorded_keys, swap_accounts);
+    }
+}
+
+#[test]
+fn test_swap_instruction() {
+    let keys: Vec<Pubkey> = (0..SWAP_ACCOUNTS).map(|_| Pubkey::new_unique()).collect();
+    let ix = cpi_client::swap_instruction(
+        obric_solana::accounts::Swap {
+            trading_pair: keys[0],
+            mint_x: keys[1],
+            mint_y: keys[2],
+            reserve_x: keys[3],
+            reserve_y: keys[4],
+            user_token_account_x: keys[5],
+            user_token_account_y: keys[6],
+            protocol_fee: keys[7],
+            x_price_feed: keys[8],
+            y_price_feed: keys[9],
+            user: keys[10],
+            token_program: keys[11],
+        },
+        false,
+        5,
+        4,
+    );
+
+    assert_eq!(ix.program_id, obric_solana::ID);
+    assert_eq!(
+        ix.accounts.iter().map(|m| m.pubkey).collect::<Vec<_>>(),
+        keys
+    );
+    assert!(ix.accounts[10].is_signer);
+    assert_eq!(ix.data[..8], obric_solana::instruction::Swap::DISCRIMINATOR);
+    let args = obric_solana::instruction::Swap::try_from_slice(&ix.data[8..]).unwrap();
+    assert!(!args._is_x_to_y);
+    assert_eq!(args._input_amt, 5);
+    assert_eq!(args._min_output_amt, 4);
+}
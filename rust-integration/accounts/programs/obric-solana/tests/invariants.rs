@@ -0,0 +1,235 @@
+use obric_solana::state::{Amount, CurvePoint, QuoteBreakdown, SSTradingPair, TokenX, TokenY};
+use proptest::prelude::*;
+
+/// A pool balanced at `target_x` for its prices, holding from none to twice
+/// its target of X and at least the value of the X it is missing in Y. Pools
+/// run from dust to `big_k` in the u128 region; those the curve cannot place
+/// are left out, as the program rejects them.
+fn pool() -> impl Strategy<Value = Pool> {
+    (
+        (mult(), mult(), 2..=1_000u64),
+        (
+            prop_oneof![1_000..=10_000_000_000u64, 1_000..=10_000_000_000_000_000u64],
+            0..=2_000u64,
+            prop_oneof![0..=100_000_000_000_000u64, 0..=1_000_000_000_000_000_000u64],
+        ),
+        (0..=50_000u64, 0..=100u64, 0..=1_000u64),
+    )
+        .prop_filter_map(
+            "the curve cannot place the pool",
+            |(prices, reserves, fees)| {
+                let (mult_x, mult_y, concentration) = prices;
+                let (target_x, x_permille, extra_y) = reserves;
+                let (fee_millionth, rebate_percentage, protocol_fee_share_thousandth) = fees;
+                let target_x_k = target_x as u128 * concentration as u128;
+                let current_x = target_x * x_permille / 1_000;
+                let missing_y = ((target_x - current_x.min(target_x)) as u128 * mult_x as u128)
+                    .div_ceil(mult_y as u128);
+                let current_y = u64::try_from(missing_y).ok()?.checked_add(extra_y)?;
+                let pool = Pool {
+                    state: SSTradingPair {
+                        mult_x,
+                        mult_y,
+                        concentration,
+                        target_x,
+                        big_k: target_x_k
+                            .checked_mul(target_x_k)?
+                            .checked_mul(mult_x as u128)?
+                            / mult_y as u128,
+                        fee_millionth,
+                        rebate_percentage,
+                        protocol_fee_share_thousandth,
+                        ..SSTradingPair::default()
+                    },
+                    current_x: Amount::new(current_x),
+                    current_y: Amount::new(current_y),
+                };
+                pool.point().map(|_| pool)
+            },
+        )
+}
+
+/// Mults as `update_price` sets them: a normalized price of at least 1_000
+/// when the feeds allow, times 10^d on the side with d fewer decimals, so up
+/// to 1e8 and past it for cheap tokens against ones with more decimals.
+fn mult() -> impl Strategy<Value = u64> {
+    prop_oneof![1..=10_000u64, 10_000..=1_000_000_000u64]
+}
+
+/// Inputs from dust to more than any pool holds. Two of them add up in u64.
+fn size() -> impl Strategy<Value = u64> {
+    prop_oneof![
+        1..=1_000u64,
+        1..=1_000_000u64,
+        1..=100_000_000_000_000u64,
+        1..=u64::MAX / 2
+    ]
+}
+
+#[derive(Clone, Debug)]
+struct Pool {
+    state: SSTradingPair,
+    current_x: Amount<TokenX>,
+    current_y: Amount<TokenY>,
+}
+
+impl Pool {
+    /// `None` when a swap has left the curve unable to place the reserves.
+    fn point(&self) -> Option<CurvePoint> {
+        self.state.curve_point(self.current_x, self.current_y).ok()
+    }
+
+    /// All zeros when the reserve cannot pay, like the program. `None` when
+    /// the program fails, which fees on outputs past u64 / `fee_millionth` do.
+    fn quote(&self, x_to_y: bool, input: u64) -> Option<QuoteBreakdown> {
+        let (input, current_x, current_y) = (Amount::new(input), self.current_x, self.current_y);
+        let quote = match x_to_y {
+            true => self
+                .state
+                .quote_x_to_y_breakdown(input, current_x, current_y),
+            false => self
+                .state
+                .quote_y_to_x_breakdown(input, current_x, current_y),
+        };
+        quote.ok()
+    }
+
+    /// The pool after the swap, paying the output and protocol fee from its reserve.
+    fn swapped(&self, x_to_y: bool, input: u64, quote: &QuoteBreakdown) -> Pool {
+        let paid = quote.output_after_fee + quote.protocol_fee;
+        let (current_x, current_y) = match x_to_y {
+            true => (self.current_x.get() + input, self.current_y.get() - paid),
+            false => (self.current_x.get() - paid, self.current_y.get() + input),
+        };
+        Pool {
+            current_x: Amount::new(current_x),
+            current_y: Amount::new(current_y),
+            ..self.clone()
+        }
+    }
+}
+
+/// How far the floors of the curve can move a quote: a unit of input at the
+/// marginal price of `point`, in units of output, plus one.
+fn rounding(point: &CurvePoint, x_to_y: bool) -> u128 {
+    let (input_k, output_k) = match x_to_y {
+        true => (point.current_x_k.get(), point.current_y_k.get()),
+        false => (point.current_y_k.get(), point.current_x_k.get()),
+    };
+    output_k.div_ceil(input_k.max(1)) + 1
+}
+
+proptest! {
+    #[test]
+    fn output_grows_with_input(
+        pool in pool(),
+        x_to_y in any::<bool>(),
+        input in size(),
+        more in size(),
+    ) {
+        let larger = pool.quote(x_to_y, input + more);
+        // the reserve cannot pay it
+        prop_assume!(larger.is_some_and(|larger| larger.output_before_fee > 0));
+        let larger = larger.unwrap();
+        // a pool paying a trade pays the smaller ones
+        let smaller = pool.quote(x_to_y, input);
+        prop_assert!(smaller.is_some());
+        prop_assert!(smaller.unwrap().output_before_fee <= larger.output_before_fee);
+
+        // The rebate is paid in whole percents of the input moving toward the
+        // target, so past the target a larger trade can get a smaller rebate
+        // and pay out a little less. Without it the output after fee grows too.
+        let mut no_rebate = pool.clone();
+        no_rebate.state.rebate_percentage = 0;
+        let smaller = no_rebate.quote(x_to_y, input).unwrap();
+        let larger = no_rebate.quote(x_to_y, input + more).unwrap();
+        prop_assert!(smaller.output_after_fee <= larger.output_after_fee);
+    }
+
+    #[test]
+    fn average_price_worsens_with_size(
+        pool in pool(),
+        x_to_y in any::<bool>(),
+        input in size(),
+        more in size(),
+    ) {
+        let larger_input = input + more;
+        let larger = pool.quote(x_to_y, larger_input);
+        prop_assume!(larger.is_some_and(|larger| larger.output_before_fee > 0));
+        let (smaller, larger) = (pool.quote(x_to_y, input).unwrap(), larger.unwrap());
+        let rounding = rounding(&pool.point().unwrap(), x_to_y);
+
+        // output / input does not grow, give or take the rounding of the smaller
+        // quote; a product past u128 is larger than the other side
+        let (input, larger_input) = (input as u128, larger_input as u128);
+        prop_assert!(
+            (smaller.output_before_fee as u128)
+                .saturating_add(rounding)
+                .saturating_mul(larger_input)
+                >= larger.output_before_fee as u128 * input
+        );
+        prop_assert!(
+            (smaller.output_after_fee as u128)
+                .saturating_add(rounding + 1)
+                .saturating_mul(larger_input)
+                >= larger.output_after_fee as u128 * input
+        );
+    }
+
+    #[test]
+    fn round_trips_do_not_pay(pool in pool(), x_to_y in any::<bool>(), input in size()) {
+        let there = pool.quote(x_to_y, input);
+        prop_assume!(there.is_some_and(|there| there.output_after_fee > 0));
+        let there = there.unwrap();
+        let swapped = pool.swapped(x_to_y, input, &there);
+        let (Some(after), Some(back)) = (
+            swapped.point(),
+            swapped.quote(!x_to_y, there.output_after_fee),
+        ) else {
+            return Ok(());
+        };
+
+        // Curve-K places X from the reserves and solves Y = floor(big_k / X),
+        // and neither `big_k` nor `target_x` moves with a swap. From X, the
+        // swap moves X on the curve by exactly `input`; the Y coming back
+        // ends at or below the Y it started from, and floor(big_k / Y) there
+        // is at least the X it started from, so at most `input` comes back.
+        // From Y, the swap ends at X = floor(big_k / Y), up to a unit of X
+        // short of the curve, paying up to a unit of X too many. The LP fee
+        // stays in the reserve and covers that unit; without one the round
+        // trip keeps it, worth less than `rounding` of Y at the price the
+        // swap leaves.
+        let slack = match x_to_y || there.lp_fee > 0 {
+            true => 0,
+            false => rounding(&after, !x_to_y),
+        };
+        prop_assert!(back.output_after_fee as u128 <= input as u128 + slack);
+    }
+
+    #[test]
+    fn fees_add_up(pool in pool(), x_to_y in any::<bool>(), input in size()) {
+        let quote = pool.quote(x_to_y, input);
+        prop_assume!(quote.is_some());
+        let quote = quote.unwrap();
+        prop_assert!(quote.fee_before_rebate <= quote.output_before_fee);
+        prop_assert!(quote.rebate <= quote.fee_before_rebate);
+        prop_assert_eq!(
+            quote.protocol_fee + quote.lp_fee,
+            quote.fee_before_rebate - quote.rebate
+        );
+        prop_assert_eq!(
+            quote.output_after_fee + quote.protocol_fee + quote.lp_fee,
+            quote.output_before_fee
+        );
+
+        let (input, current_x, current_y) = (Amount::new(input), pool.current_x, pool.current_y);
+        let split = match x_to_y {
+            true => pool.state.quote_x_to_y(input, current_x, current_y),
+            false => pool.state.quote_y_to_x(input, current_x, current_y),
+        };
+        prop_assert_eq!(
+            split.unwrap(),
+            (quote.output_after_fee, quote.protocol_fee, quote.lp_fee)
+        );
+    }
+}
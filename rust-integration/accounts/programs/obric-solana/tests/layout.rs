@@ -0,0 +1,56 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, AccountSerialize};
+use obric_solana::state::SSTradingPair;
+
+fn trading_pair() -> SSTradingPair {
+    SSTradingPair {
+        is_initialized: true,
+        x_price_feed_id: Pubkey::new_unique(),
+        y_price_feed_id: Pubkey::new_unique(),
+        reserve_x: Pubkey::new_unique(),
+        reserve_y: Pubkey::new_unique(),
+        protocol_fee_x: Pubkey::new_unique(),
+        protocol_fee_y: Pubkey::new_unique(),
+        bump: 254,
+        mint_x: Pubkey::new_unique(),
+        mint_y: Pubkey::new_unique(),
+        concentration: 100,
+        big_k: u128::MAX - 1,
+        target_x: 1_000_000_000_000,
+        cumulative_volume: 42,
+        mult_x: 150_000,
+        mult_y: 1_000_000,
+        fee_millionth: 1_000,
+        rebate_percentage: 50,
+        protocol_fee_share_thousandth: 200,
+        volume_record: [1, 2, 3, 4, 5, 6, 7, 8],
+        volume_time_record: [-1, -2, -3, -4, -5, -6, -7, -8],
+        padding: [u64::MAX; 24],
+    }
+}
+
+#[test]
+fn test_serialized_size_matches_len() {
+    let mut data = vec![];
+    trading_pair().try_serialize(&mut data).unwrap();
+    assert_eq!(data.len(), SSTradingPair::LEN);
+
+    let mut data = vec![];
+    SSTradingPair::default().try_serialize(&mut data).unwrap();
+    assert_eq!(data.len(), SSTradingPair::LEN);
+}
+
+#[test]
+fn test_serialize_deserialize_clone_round_trip() {
+    let state = trading_pair();
+    let mut data = vec![];
+    state.try_serialize(&mut data).unwrap();
+
+    let decoded = SSTradingPair::try_deserialize(&mut &data[..]).unwrap();
+    assert_eq!(decoded, state);
+    assert_eq!(decoded.clone(), state);
+
+    let mut reserialized = vec![];
+    decoded.clone().try_serialize(&mut reserialized).unwrap();
+    assert_eq!(reserialized, data);
+}
@@ -0,0 +1,401 @@
+#![cfg(feature = "test-only")]
+
+use anchor_lang::prelude::{AccountInfo, Pubkey};
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::{AccountSerialize, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use jupiter_amm_interface::{Amm, KeyedAccount, QuoteParams};
+use obric_sdk_common::fixtures::{mint_account, owned_token_account, price_account};
+use obric_sdk_common::simulation::{BanksSimulator, QuoteConsistencyChecker, SwapSimulator};
+use obric_solana::consts::TRADING_PAIR_SEED;
+use obric_solana::state::{NormalizedPrice, SSTradingPair};
+use obric_v2_sdk::obric_v2_amm::ObricV2Amm;
+use obric_v2_sdk::token::MintCache;
+use solana_program_test::{processor, BanksClient, ProgramTest};
+use solana_sdk::account::Account;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use std::collections::HashMap;
+
+// $150 and $1 with a pyth exponent of -8
+const PRICE_X: i64 = 150_00000000;
+const PRICE_Y: i64 = 1_00000000;
+const DECIMALS_X: u8 = 9;
+const DECIMALS_Y: u8 = 6;
+const RESERVE_X: u64 = 1_000_000_000_000;
+const RESERVE_Y: u64 = 150_000_000_000;
+
+fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    // anchor's entry wants `&'info [AccountInfo<'info>]`
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    obric_solana::entry(program_id, accounts, data)
+}
+
+fn add_mint(program_test: &mut ProgramTest, decimals: u8) -> Pubkey {
+    let mint = Pubkey::new_unique();
+    program_test.add_account(mint, mint_account(decimals));
+    mint
+}
+
+fn add_token_account(
+    program_test: &mut ProgramTest,
+    mint: Pubkey,
+    owner: Pubkey,
+    amount: u64,
+) -> Pubkey {
+    let token_account = Pubkey::new_unique();
+    program_test.add_account(token_account, owned_token_account(mint, owner, amount));
+    token_account
+}
+
+/// The checker swaps from and to the user's associated token accounts.
+fn add_user_token_account(
+    program_test: &mut ProgramTest,
+    mint: Pubkey,
+    user: Pubkey,
+    amount: u64,
+) -> Pubkey {
+    let token_account = get_associated_token_address(&user, &mint);
+    program_test.add_account(token_account, owned_token_account(mint, user, amount));
+    token_account
+}
+
+async fn balance(banks_client: &mut BanksClient, token_account: Pubkey) -> u64 {
+    banks_client
+        .get_packed_account_data::<spl_token::state::Account>(token_account)
+        .await
+        .unwrap()
+        .amount
+}
+
+struct Fixture {
+    banks_client: BanksClient,
+    payer: Keypair,
+    user: Keypair,
+    state: SSTradingPair,
+    accounts: obric_solana::accounts::Swap,
+}
+
+async fn setup() -> Fixture {
+    let mut program_test = ProgramTest::new(
+        "obric_solana",
+        obric_solana::ID,
+        processor!(process_instruction),
+    );
+
+    let user = Keypair::new();
+    let mint_x = add_mint(&mut program_test, DECIMALS_X);
+    let mint_y = add_mint(&mut program_test, DECIMALS_Y);
+    let (trading_pair, bump) = Pubkey::find_program_address(
+        &[
+            TRADING_PAIR_SEED.as_bytes(),
+            mint_x.as_ref(),
+            mint_y.as_ref(),
+        ],
+        &obric_solana::ID,
+    );
+
+    let x_price_feed = Pubkey::new_unique();
+    let y_price_feed = Pubkey::new_unique();
+    program_test.add_account(x_price_feed, price_account(PRICE_X));
+    program_test.add_account(y_price_feed, price_account(PRICE_Y));
+
+    let reserve_x = add_token_account(&mut program_test, mint_x, trading_pair, RESERVE_X);
+    let reserve_y = add_token_account(&mut program_test, mint_y, trading_pair, RESERVE_Y);
+    let protocol_fee_x = add_token_account(&mut program_test, mint_x, Pubkey::new_unique(), 0);
+    let protocol_fee_y = add_token_account(&mut program_test, mint_y, Pubkey::new_unique(), 0);
+    let user_token_account_x =
+        add_user_token_account(&mut program_test, mint_x, user.pubkey(), 100_000_000_000);
+    let user_token_account_y =
+        add_user_token_account(&mut program_test, mint_y, user.pubkey(), 100_000_000_000);
+
+    // mult_x / mult_y as the program computes them from the feeds above
+    let (mult_x, mult_y) = (150_000u128, 1_000_000u128);
+    let concentration = 100u64;
+    let target_x = RESERVE_X;
+    let target_x_k = target_x as u128 * concentration as u128;
+    let mut state = SSTradingPair {
+        is_initialized: true,
+        x_price_feed_id: x_price_feed,
+        y_price_feed_id: y_price_feed,
+        reserve_x,
+        reserve_y,
+        protocol_fee_x,
+        protocol_fee_y,
+        bump,
+        mint_x,
+        mint_y,
+        concentration,
+        big_k: target_x_k * target_x_k * mult_x / mult_y,
+        target_x,
+        fee_millionth: 1_000,
+        rebate_percentage: 50,
+        protocol_fee_share_thousandth: 200,
+        ..SSTradingPair::default()
+    };
+    let mut data = vec![];
+    state.try_serialize(&mut data).unwrap();
+    program_test.add_account(
+        trading_pair,
+        Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: obric_solana::ID,
+            ..Account::default()
+        },
+    );
+
+    state
+        .update_price(
+            NormalizedPrice::new((PRICE_X / 100_000) as u64),
+            NormalizedPrice::new((PRICE_Y / 100_000) as u64),
+            DECIMALS_X,
+            DECIMALS_Y,
+        )
+        .unwrap();
+    assert_eq!(
+        (state.mult_x as u128, state.mult_y as u128),
+        (mult_x, mult_y)
+    );
+
+    let (banks_client, payer, _) = program_test.start().await;
+    Fixture {
+        banks_client,
+        payer,
+        accounts: obric_solana::accounts::Swap {
+            trading_pair,
+            mint_x,
+            mint_y,
+            reserve_x,
+            reserve_y,
+            user_token_account_x,
+            user_token_account_y,
+            protocol_fee: protocol_fee_y,
+            x_price_feed,
+            y_price_feed,
+            user: user.pubkey(),
+            token_program: spl_token::ID,
+        },
+        user,
+        state,
+    }
+}
+
+/// The SDK's pool, updated from the accounts it reads as they are now.
+async fn sdk_pool(fixture: &mut Fixture) -> ObricV2Amm {
+    let key = fixture.accounts.trading_pair;
+    let account = fixture
+        .banks_client
+        .get_account(key)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut amm = ObricV2Amm::from_keyed_account(&KeyedAccount {
+        key,
+        account,
+        params: None,
+    })
+    .unwrap()
+    .with_mint_cache(MintCache::default());
+    let mut accounts_map = HashMap::new();
+    for key in amm.get_accounts_to_update() {
+        let account = fixture
+            .banks_client
+            .get_account(key)
+            .await
+            .unwrap()
+            .unwrap();
+        accounts_map.insert(key, account);
+    }
+    amm.update(&accounts_map).unwrap();
+    amm
+}
+
+/// The SDK's quote, from the pool and the accounts it reads as they are now.
+/// Returns the output and the protocol fee.
+async fn sdk_quote(fixture: &mut Fixture, is_x_to_y: bool, in_amount: u64) -> (u64, u64) {
+    let amm = sdk_pool(fixture).await;
+    let (input_mint, output_mint) = if is_x_to_y {
+        (amm.state.mint_x, amm.state.mint_y)
+    } else {
+        (amm.state.mint_y, amm.state.mint_x)
+    };
+    let quote_params = QuoteParams {
+        input_mint,
+        in_amount,
+        output_mint,
+    };
+    let quote = amm.quote(&quote_params).unwrap();
+    let breakdown = amm.quote_breakdown(&quote_params).unwrap();
+    (quote.out_amount, breakdown.protocol_fee)
+}
+
+async fn swap(
+    fixture: &mut Fixture,
+    is_x_to_y: bool,
+    input_amt: u64,
+    min_output_amt: u64,
+) -> Result<(), solana_program_test::BanksClientError> {
+    let mut accounts = obric_solana::accounts::Swap { ..fixture.accounts };
+    accounts.protocol_fee = if is_x_to_y {
+        fixture.state.protocol_fee_y
+    } else {
+        fixture.state.protocol_fee_x
+    };
+    let recent_blockhash = fixture.banks_client.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: obric_solana::ID,
+            accounts: accounts.to_account_metas(None),
+            data: obric_solana::instruction::Swap {
+                _is_x_to_y: is_x_to_y,
+                _input_amt: input_amt,
+                _min_output_amt: min_output_amt,
+            }
+            .data(),
+        }],
+        Some(&fixture.payer.pubkey()),
+        &[&fixture.payer, &fixture.user],
+        recent_blockhash,
+    );
+    fixture.banks_client.process_transaction(tx).await
+}
+
+#[tokio::test]
+async fn test_swap_matches_quote() {
+    let mut fixture = setup().await;
+    let a = obric_solana::accounts::Swap { ..fixture.accounts };
+
+    for (is_x_to_y, input_amt) in [
+        (true, 10_000_000_000u64),
+        (false, 3_000_000_000),
+        (true, 12_345),
+        (false, 7_654_321),
+    ] {
+        let current_x = balance(&mut fixture.banks_client, a.reserve_x).await;
+        let current_y = balance(&mut fixture.banks_client, a.reserve_y).await;
+        let (expected_output, expected_protocol_fee) =
+            sdk_quote(&mut fixture, is_x_to_y, input_amt).await;
+
+        let (user_out, protocol_fee) = if is_x_to_y {
+            (a.user_token_account_y, fixture.state.protocol_fee_y)
+        } else {
+            (a.user_token_account_x, fixture.state.protocol_fee_x)
+        };
+        let user_out_before = balance(&mut fixture.banks_client, user_out).await;
+        let protocol_fee_before = balance(&mut fixture.banks_client, protocol_fee).await;
+
+        swap(&mut fixture, is_x_to_y, input_amt, expected_output)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            balance(&mut fixture.banks_client, user_out).await - user_out_before,
+            expected_output
+        );
+        assert_eq!(
+            balance(&mut fixture.banks_client, protocol_fee).await - protocol_fee_before,
+            expected_protocol_fee
+        );
+        let (reserve_in, reserve_out, before_in, before_out) = if is_x_to_y {
+            (a.reserve_x, a.reserve_y, current_x, current_y)
+        } else {
+            (a.reserve_y, a.reserve_x, current_y, current_x)
+        };
+        assert_eq!(
+            balance(&mut fixture.banks_client, reserve_in).await,
+            before_in + input_amt
+        );
+        assert_eq!(
+            balance(&mut fixture.banks_client, reserve_out).await,
+            before_out - expected_output - expected_protocol_fee
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_swap_rejects_min_output_above_quote() {
+    let mut fixture = setup().await;
+    let (expected_output, _) = sdk_quote(&mut fixture, true, 1_000_000_000).await;
+
+    assert!(swap(&mut fixture, true, 1_000_000_000, expected_output + 1)
+        .await
+        .is_err());
+    swap(&mut fixture, true, 1_000_000_000, expected_output)
+        .await
+        .unwrap();
+}
+
+/// Pays `bps` less than the swaps it runs execute.
+struct Skimming<S>(S, u64);
+
+impl<S: SwapSimulator> SwapSimulator for Skimming<S> {
+    fn user(&self) -> Pubkey {
+        self.0.user()
+    }
+
+    fn accounts(&self, keys: &[Pubkey]) -> anyhow::Result<HashMap<Pubkey, Account>> {
+        self.0.accounts(keys)
+    }
+
+    fn simulate(
+        &self,
+        instruction: Instruction,
+        destination: Pubkey,
+    ) -> anyhow::Result<(u64, u64)> {
+        let (before, after) = self.0.simulate(instruction, destination)?;
+        Ok((before, after - (after - before) * self.1 / 10_000))
+    }
+}
+
+#[test]
+fn test_quote_consistency_checker() {
+    // the simulator drives the bank from outside an async test
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let mut fixture = runtime.block_on(setup());
+    let mut amm = runtime.block_on(sdk_pool(&mut fixture));
+    let simulator = BanksSimulator {
+        client: fixture.banks_client.clone(),
+        runtime: &runtime,
+        payer: &fixture.payer,
+        user: &fixture.user,
+    };
+
+    // the test-only program pays exactly the quote
+    let checker = QuoteConsistencyChecker {
+        simulator,
+        tolerance_bps: 0,
+    };
+    let swaps = checker.check(&mut amm, &[12_345, 1_000_000_000]).unwrap();
+    assert_eq!(swaps.len(), 4);
+    for swap in &swaps {
+        assert!(swap.executed_out > 0);
+        assert_eq!(swap.divergence_bps(), 0);
+    }
+
+    // one paying 1% less fails a tighter tolerance, and passes a looser one
+    let checker = QuoteConsistencyChecker {
+        simulator: Skimming(checker.simulator, 100),
+        tolerance_bps: 50,
+    };
+    let error = checker.check(&mut amm, &[2_000_000_000]).unwrap_err();
+    assert!(error.to_string().contains("but executed"));
+    let checker = QuoteConsistencyChecker {
+        tolerance_bps: 100,
+        ..checker
+    };
+    let swaps = checker.check(&mut amm, &[3_000_000_000]).unwrap();
+    assert_eq!(swaps.len(), 2);
+    assert!(swaps
+        .iter()
+        .all(|swap| (99..=100).contains(&swap.divergence_bps())));
+}
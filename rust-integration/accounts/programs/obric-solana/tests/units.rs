@@ -0,0 +1,61 @@
+use obric_solana::state::{
+    Amount, CurveAmount, Invariant, Mult, NormalizedPrice, SSTradingPair, TokenX, TokenY,
+};
+
+#[test]
+fn the_side_with_fewer_decimals_is_scaled_up() {
+    // SOL (9 decimals) at $150 against USDC (6 decimals) at $1
+    let price_x: NormalizedPrice<TokenX> = NormalizedPrice::new(150_000);
+    let price_y: NormalizedPrice<TokenY> = NormalizedPrice::new(1_000);
+    assert_eq!(price_x.to_mult(9, 6), Some(Mult::new(150_000)));
+    assert_eq!(price_y.to_mult(6, 9), Some(Mult::new(1_000_000)));
+
+    let mut state = SSTradingPair::default();
+    state.update_price(price_x, price_y, 9, 6).unwrap();
+    assert_eq!(
+        (state.mult_x(), state.mult_y()),
+        (Mult::new(150_000), Mult::new(1_000_000))
+    );
+
+    // a scale that does not fit fails instead of wrapping
+    assert_eq!(NormalizedPrice::<TokenX>::new(u64::MAX).to_mult(0, 1), None);
+    assert!(state.update_price(price_x, price_y, 0, 30).is_err());
+}
+
+#[test]
+fn values_convert_between_sides() {
+    let (mult_x, mult_y) = (Mult::<TokenX>::new(150_000), Mult::<TokenY>::new(1_000_000));
+    // 1 SOL is worth 150 USDC
+    let one_sol: Amount<TokenX> = Amount::new(1_000_000_000);
+    assert_eq!(
+        one_sol.value(mult_x).to_amount(mult_y),
+        Some(Amount::new(150_000_000))
+    );
+    // and past u64 there is no amount
+    let value = Amount::<TokenX>::new(u64::MAX).value(mult_x);
+    assert_eq!(value.to_amount(Mult::<TokenY>::new(1)), None);
+}
+
+#[test]
+fn curve_coordinates() {
+    let (mult_x, mult_y) = (Mult::<TokenX>::new(150_000), Mult::<TokenY>::new(1_000));
+    let target_x_k = Amount::<TokenX>::new(1_000_000_000).concentrated(100);
+    assert_eq!(target_x_k.get(), 100_000_000_000);
+    assert_eq!(
+        target_x_k.deconcentrated(100),
+        Some(CurveAmount::new(1_000_000_000))
+    );
+
+    // at target both coordinates hold the same value
+    let target_y_k = target_x_k.convert(mult_x, mult_y).unwrap();
+    assert_eq!(target_y_k.get(), 15_000_000_000_000);
+    let big_k = target_x_k.invariant(target_y_k).unwrap();
+    assert_eq!(big_k.balanced(mult_x, mult_y), Some(target_x_k));
+    assert_eq!(big_k.solve(target_x_k), Some(target_y_k));
+
+    assert_eq!(Invariant::new(1).solve(CurveAmount::<TokenY>::new(0)), None);
+    assert_eq!(
+        CurveAmount::<TokenX>::new(u64::MAX as u128 + 1).to_amount(),
+        None
+    );
+}
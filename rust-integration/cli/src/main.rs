@@ -0,0 +1,349 @@
+mod pool;
+mod source;
+
+use crate::pool::Pool;
+use crate::source::AccountSource;
+use anyhow::{anyhow, Result};
+use clap::{Parser, Subcommand};
+use jupiter_amm_interface::{Amm, Quote, QuoteParams};
+use obric_math::quote::exact_in;
+use obric_sdk_common::router::Router;
+use solana_sdk::pubkey::Pubkey;
+use std::cell::RefCell;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(name = "obric-cli", about = "Quote and inspect Obric v2 and v3 pools")]
+struct Cli {
+    /// Solana RPC node url
+    #[arg(long, env = "SOLANA_RPC")]
+    rpc_url: Option<String>,
+    /// Directory of `solana account --output json` files, read instead of the RPC
+    #[arg(long)]
+    snapshot: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List v2 and v3 pools with their mints, decimals, fee and concentration
+    Pools,
+    /// Quote every pool trading the pair
+    Quote {
+        #[arg(long = "in")]
+        input_mint: Pubkey,
+        #[arg(long = "out")]
+        output_mint: Pubkey,
+        /// Raw token amount, of the input mint (or of the output mint with --exact-out)
+        #[arg(long)]
+        amount: u64,
+        /// Find the smallest input that yields at least `amount` of output
+        #[arg(long)]
+        exact_out: bool,
+    },
+    /// Best route over every pool: a single pool, a split across pools, or two hops
+    Route {
+        #[arg(long = "in")]
+        input_mint: Pubkey,
+        #[arg(long = "out")]
+        output_mint: Pubkey,
+        /// Raw token amount of the input mint
+        #[arg(long)]
+        amount: u64,
+    },
+    /// Print the decoded trading pair and the derived curve values
+    Inspect { pool: Pubkey },
+    /// Print the swap account metas
+    Accounts {
+        pool: Pubkey,
+        /// Swapping wallet, its associated token accounts are used
+        #[arg(long, default_value_t = Pubkey::default())]
+        user: Pubkey,
+        /// Swap Y to X instead of X to Y
+        #[arg(long)]
+        y_to_x: bool,
+    },
+}
+
+/// The pool's quote for `in_amount`, an error when it pays nothing.
+fn quote(amm: &dyn Amm, input_mint: Pubkey, output_mint: Pubkey, in_amount: u64) -> Result<Quote> {
+    let quote = amm.quote(&QuoteParams {
+        input_mint,
+        in_amount,
+        output_mint,
+    })?;
+    if quote.not_enough_liquidity || quote.out_amount == 0 {
+        return Err(anyhow!(
+            "not enough liquidity for {} of {}",
+            in_amount,
+            input_mint
+        ));
+    }
+    Ok(quote)
+}
+
+/// Smallest input quoting at least `out_amount`, see `obric_math::quote::exact_in`.
+/// Inputs the pool fails or lacks the liquidity for bound the search, inputs
+/// quoting nothing only pay too little. When no input fills, the error of the
+/// last one tried.
+fn quote_exact_out(
+    amm: &dyn Amm,
+    input_mint: Pubkey,
+    output_mint: Pubkey,
+    out_amount: u64,
+) -> Result<(u64, Quote)> {
+    let last_error = RefCell::new(None);
+    let quote_in = |in_amount| {
+        let quote = amm
+            .quote(&QuoteParams {
+                input_mint,
+                in_amount,
+                output_mint,
+            })
+            .map_err(|e| *last_error.borrow_mut() = Some(e))
+            .ok()?;
+        if quote.not_enough_liquidity {
+            *last_error.borrow_mut() = Some(anyhow!(
+                "not enough liquidity for {} of {}",
+                in_amount,
+                input_mint
+            ));
+            return None;
+        }
+        Some(quote)
+    };
+    exact_in(out_amount, quote_in, |quote| quote.out_amount).ok_or_else(|| {
+        last_error
+            .into_inner()
+            .unwrap_or_else(|| anyhow!("no input quotes {} of {}", out_amount, output_mint))
+    })
+}
+
+fn format_decimals(decimals: Option<u8>) -> String {
+    decimals.map_or("?".to_string(), |decimals| decimals.to_string())
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let source = match (&cli.snapshot, &cli.rpc_url) {
+        (Some(dir), _) => AccountSource::snapshot(dir)?,
+        (None, Some(url)) => AccountSource::rpc(url),
+        (None, None) => return Err(anyhow!("pass --rpc-url, set SOLANA_RPC or pass --snapshot")),
+    };
+
+    match cli.command {
+        Command::Pools => {
+            for mut pool in Pool::load_all(&source)? {
+                if let Err(e) = pool.update(&source) {
+                    eprintln!("{}: update failed: {}", pool.amm().key(), e);
+                }
+                let (mint_x, mint_y) = pool.mints();
+                let (x_decimals, y_decimals) = pool.decimals();
+                println!("{} {}", pool.amm().label(), pool.amm().key());
+                println!(
+                    "  mint_x: {} ({} decimals)",
+                    mint_x,
+                    format_decimals(x_decimals)
+                );
+                println!(
+                    "  mint_y: {} ({} decimals)",
+                    mint_y,
+                    format_decimals(y_decimals)
+                );
+                println!(
+                    "  fee_millionth: {}, concentration: {}",
+                    pool.fee_millionth(),
+                    pool.concentration()
+                );
+            }
+        }
+        Command::Quote {
+            input_mint,
+            output_mint,
+            amount,
+            exact_out,
+        } => {
+            let mut quoted = 0;
+            for mut pool in Pool::load_all(&source)? {
+                let (mint_x, mint_y) = pool.mints();
+                if !(mint_x.eq(&input_mint) && mint_y.eq(&output_mint)
+                    || mint_x.eq(&output_mint) && mint_y.eq(&input_mint))
+                {
+                    continue;
+                }
+                quoted += 1;
+                if let Err(e) = pool.update(&source) {
+                    eprintln!("{}: update failed: {}", pool.amm().key(), e);
+                    continue;
+                }
+                let amm = pool.amm();
+                let result = if exact_out {
+                    quote_exact_out(amm, input_mint, output_mint, amount)
+                } else {
+                    quote(amm, input_mint, output_mint, amount).map(|quote| (amount, quote))
+                };
+                match result {
+                    Ok((in_amount, quote)) => println!(
+                        "{} {}: in {}, out {}, fee {} of {}",
+                        amm.label(),
+                        amm.key(),
+                        in_amount,
+                        quote.out_amount,
+                        quote.fee_amount,
+                        quote.fee_mint
+                    ),
+                    Err(e) => println!("{} {}: {}", amm.label(), amm.key(), e),
+                }
+            }
+            if quoted == 0 {
+                return Err(anyhow!("no pool trades {} / {}", input_mint, output_mint));
+            }
+        }
+        Command::Route {
+            input_mint,
+            output_mint,
+            amount,
+        } => {
+            let mut pools = Pool::load_all(&source)?;
+            pools.retain_mut(|pool| match pool.update(&source) {
+                Ok(()) => true,
+                Err(e) => {
+                    eprintln!("{}: update failed: {}", pool.amm().key(), e);
+                    false
+                }
+            });
+            let router = Router::new(pools.iter().map(Pool::amm).collect());
+            let route = router
+                .best_route(input_mint, output_mint, amount)
+                .ok_or(anyhow!("no route from {} to {}", input_mint, output_mint))?;
+            for (hop, legs) in route.hops.iter().enumerate() {
+                for leg in legs {
+                    println!(
+                        "hop {} {}: {} {} -> {} {}, fee {} of {}",
+                        hop,
+                        leg.pool,
+                        leg.in_amount,
+                        leg.input_mint,
+                        leg.out_amount,
+                        leg.output_mint,
+                        leg.fee_amount,
+                        leg.fee_mint
+                    );
+                }
+            }
+            println!("in {}, out {}", route.in_amount(), route.out_amount());
+        }
+        Command::Inspect { pool } => Pool::load(&source, &pool)?.print_inspect()?,
+        Command::Accounts { pool, user, y_to_x } => {
+            let pool = Pool::load(&source, &pool)?;
+            let (mint_x, mint_y) = pool.mints();
+            let source_mint = if y_to_x { mint_y } else { mint_x };
+            for (index, meta) in pool
+                .swap_account_metas(source_mint, user)?
+                .iter()
+                .enumerate()
+            {
+                println!(
+                    "{:>2} {} {}{}",
+                    index,
+                    meta.pubkey,
+                    if meta.is_writable { "w" } else { "-" },
+                    if meta.is_signer { "s" } else { "-" }
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jupiter_amm_interface::{KeyedAccount, SwapAndAccountMetas, SwapParams};
+    use solana_sdk::account::Account;
+    use std::collections::HashMap;
+
+    /// Pays half the input, at most 500, and fails past 1_000_000 in.
+    #[derive(Clone)]
+    struct HalfPool;
+
+    impl Amm for HalfPool {
+        fn from_keyed_account(_keyed_account: &KeyedAccount) -> Result<Self> {
+            Err(anyhow!("HalfPool has no account"))
+        }
+
+        fn label(&self) -> String {
+            String::from("Half")
+        }
+
+        fn program_id(&self) -> Pubkey {
+            Pubkey::default()
+        }
+
+        fn key(&self) -> Pubkey {
+            Pubkey::default()
+        }
+
+        fn get_reserve_mints(&self) -> Vec<Pubkey> {
+            vec![]
+        }
+
+        fn get_accounts_to_update(&self) -> Vec<Pubkey> {
+            vec![]
+        }
+
+        fn update(&mut self, _accounts_map: &HashMap<Pubkey, Account>) -> Result<()> {
+            Ok(())
+        }
+
+        fn quote(&self, quote_params: &QuoteParams) -> Result<Quote> {
+            if quote_params.in_amount > 1_000_000 {
+                return Err(anyhow!("cannot fill {}", quote_params.in_amount));
+            }
+            Ok(Quote {
+                out_amount: (quote_params.in_amount / 2).min(500),
+                ..Quote::default()
+            })
+        }
+
+        fn get_swap_and_account_metas(
+            &self,
+            _swap_params: &SwapParams,
+        ) -> Result<SwapAndAccountMetas> {
+            Err(anyhow!("HalfPool only quotes"))
+        }
+
+        fn clone_amm(&self) -> Box<dyn Amm + Send + Sync> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn test_quote_reports_the_error() {
+        let (input_mint, output_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert_eq!(
+            quote(&HalfPool, input_mint, output_mint, 1_000)
+                .unwrap()
+                .out_amount,
+            500
+        );
+        let e = quote(&HalfPool, input_mint, output_mint, 1).unwrap_err();
+        assert!(e.to_string().starts_with("not enough liquidity"));
+        let e = quote(&HalfPool, input_mint, output_mint, 1_000_001).unwrap_err();
+        assert_eq!(e.to_string(), "cannot fill 1000001");
+    }
+
+    #[test]
+    fn test_quote_exact_out() {
+        let (input_mint, output_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        // 99 in pays 49
+        let (in_amount, quote) = quote_exact_out(&HalfPool, input_mint, output_mint, 50).unwrap();
+        assert_eq!((in_amount, quote.out_amount), (100, 50));
+        let (in_amount, _) = quote_exact_out(&HalfPool, input_mint, output_mint, 500).unwrap();
+        assert_eq!(in_amount, 1_000);
+        // more than the pool pays: the search ends on inputs it cannot fill
+        let e = quote_exact_out(&HalfPool, input_mint, output_mint, 501).unwrap_err();
+        assert!(e.to_string().starts_with("cannot fill"));
+    }
+}
@@ -0,0 +1,185 @@
+use crate::source::AccountSource;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anyhow::{anyhow, Result};
+use jupiter_amm_interface::{Amm, KeyedAccount};
+use obric_solana::state::Amount;
+use obric_v2_sdk::obric_v2_amm::ObricV2Amm;
+use obric_v3_sdk::obric_v3_amm::ObricV3Amm;
+use solana_sdk::instruction::AccountMeta;
+use solana_sdk::pubkey::Pubkey;
+
+pub enum Pool {
+    V2(ObricV2Amm),
+    V3(ObricV3Amm),
+}
+
+impl Pool {
+    /// Decodes every v2 and v3 pool of `source`, without updating them.
+    pub fn load_all(source: &AccountSource) -> Result<Vec<Pool>> {
+        let mut pools = vec![];
+        for (key, account) in source.get_program_accounts(&obric_v2_sdk::constants::PROGRAM_ID)? {
+            if account.data.len() == obric_solana::state::SSTradingPair::LEN {
+                let keyed_account = KeyedAccount {
+                    key,
+                    account,
+                    params: None,
+                };
+                pools.push(Pool::V2(ObricV2Amm::from_keyed_account(&keyed_account)?));
+            }
+        }
+        for (key, account) in source.get_program_accounts(&obric_v3_sdk::constants::PROGRAM_ID)? {
+            if account.data.len() == obric_solana_v3::state::SSTradingPair::LEN {
+                let keyed_account = KeyedAccount {
+                    key,
+                    account,
+                    params: None,
+                };
+                pools.push(Pool::V3(ObricV3Amm::from_keyed_account(&keyed_account)?));
+            }
+        }
+        Ok(pools)
+    }
+
+    /// Decodes and updates the pool at `key`.
+    pub fn load(source: &AccountSource, key: &Pubkey) -> Result<Pool> {
+        let account = source.get_account(key)?;
+        let keyed_account = KeyedAccount {
+            key: *key,
+            account,
+            params: None,
+        };
+        let mut pool = if keyed_account.account.owner == obric_v2_sdk::constants::PROGRAM_ID {
+            Pool::V2(ObricV2Amm::from_keyed_account(&keyed_account)?)
+        } else if keyed_account.account.owner == obric_v3_sdk::constants::PROGRAM_ID {
+            Pool::V3(ObricV3Amm::from_keyed_account(&keyed_account)?)
+        } else {
+            return Err(anyhow!("{} is not an Obric pool", key));
+        };
+        pool.update(source)?;
+        Ok(pool)
+    }
+
+    pub fn amm(&self) -> &dyn Amm {
+        match self {
+            Pool::V2(amm) => amm,
+            Pool::V3(amm) => amm,
+        }
+    }
+
+    pub fn update(&mut self, source: &AccountSource) -> Result<()> {
+        let amm: &mut dyn Amm = match self {
+            Pool::V2(amm) => amm,
+            Pool::V3(amm) => amm,
+        };
+        let accounts_map = source.get_accounts(&amm.get_accounts_to_update())?;
+        amm.update(&accounts_map)
+    }
+
+    pub fn mints(&self) -> (Pubkey, Pubkey) {
+        match self {
+            Pool::V2(amm) => (amm.state.mint_x, amm.state.mint_y),
+            Pool::V3(amm) => (amm.state.mint_x, amm.state.mint_y),
+        }
+    }
+
+    /// `None` for a v2 pool whose mints have not been loaded yet.
+    pub fn decimals(&self) -> (Option<u8>, Option<u8>) {
+        match self {
+            Pool::V2(amm) => (amm.x_decimals(), amm.y_decimals()),
+            Pool::V3(amm) => (Some(amm.state.decimals_x), Some(amm.state.decimals_y)),
+        }
+    }
+
+    pub fn fee_millionth(&self) -> u64 {
+        match self {
+            Pool::V2(amm) => amm.state.fee_millionth,
+            Pool::V3(amm) => amm.state.fee_millionth,
+        }
+    }
+
+    pub fn concentration(&self) -> u64 {
+        match self {
+            Pool::V2(amm) => amm.state.concentration,
+            Pool::V3(amm) => amm.state.concentration,
+        }
+    }
+
+    /// Prints the decoded trading pair followed by the values the curve derives from it.
+    pub fn print_inspect(&self) -> Result<()> {
+        println!("{} {}", self.amm().label(), self.amm().key());
+        match self {
+            Pool::V2(amm) => {
+                println!("{:#?}", amm.state);
+                let (current_x, current_y) = amm.current_reserves();
+                let (target_x, target_y) = amm
+                    .state
+                    .get_target_xy(Amount::new(current_x), Amount::new(current_y))?;
+                println!("current_x: {}", current_x);
+                println!("current_y: {}", current_y);
+                println!("target_x: {}", target_x.get());
+                println!("target_y: {}", target_y.get());
+            }
+            Pool::V3(amm) => {
+                println!("{:#?}", amm.state);
+                let (big_k, current_x_k, current_y_k, available_x, available_y) =
+                    amm.state.get_pool_values_for_quoting()?;
+                println!("big_k (quoting): {}", big_k.get());
+                println!("current_x_k: {}", current_x_k.get());
+                println!("current_y_k: {}", current_y_k.get());
+                println!("available_x: {}", available_x.get());
+                println!("available_y: {}", available_y.get());
+            }
+        }
+        Ok(())
+    }
+
+    /// Swap account metas for `user` swapping out of `source_mint`, from the
+    /// user's associated token accounts.
+    pub fn swap_account_metas(
+        &self,
+        source_mint: Pubkey,
+        user: Pubkey,
+    ) -> Result<Vec<AccountMeta>> {
+        let (mint_x, mint_y) = self.mints();
+        let destination_mint = if source_mint.eq(&mint_x) {
+            mint_y
+        } else {
+            mint_x
+        };
+        let token_program = |mint: &Pubkey| match self {
+            Pool::V2(amm) => amm.token_program(mint),
+            Pool::V3(_) => anchor_spl::token::spl_token::id(),
+        };
+        let source = get_associated_token_address_with_program_id(
+            &user,
+            &source_mint,
+            &token_program(&source_mint),
+        );
+        let destination = get_associated_token_address_with_program_id(
+            &user,
+            &destination_mint,
+            &token_program(&destination_mint),
+        );
+        let ix = match self {
+            Pool::V2(amm) => obric_v2_sdk::simulation::swap_instruction(
+                amm,
+                source_mint,
+                user,
+                source,
+                destination,
+                0,
+                0,
+            )?,
+            Pool::V3(amm) => obric_v3_sdk::simulation::swap_instruction(
+                amm,
+                source_mint,
+                user,
+                source,
+                destination,
+                0,
+                0,
+            )?,
+        };
+        Ok(ix.accounts)
+    }
+}
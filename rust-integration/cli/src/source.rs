@@ -0,0 +1,88 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use solana_account_decoder::UiAccount;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The `solana account <KEY> --output json` file format, also accepted by
+/// `solana-test-validator --account`.
+#[derive(Deserialize)]
+struct KeyedUiAccount {
+    pubkey: String,
+    account: UiAccount,
+}
+
+/// Where pool accounts are read from: a live cluster or a snapshot directory.
+pub enum AccountSource {
+    Rpc(RpcClient),
+    Snapshot(HashMap<Pubkey, Account>),
+}
+
+impl AccountSource {
+    pub fn rpc(url: &str) -> Self {
+        Self::Rpc(RpcClient::new(url))
+    }
+
+    /// Loads every `*.json` file of `dir`.
+    pub fn snapshot(dir: &Path) -> Result<Self> {
+        let mut accounts = HashMap::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .map_or(true, |extension| extension != "json")
+            {
+                continue;
+            }
+            let keyed: KeyedUiAccount = serde_json::from_slice(&fs::read(&path)?)
+                .map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+            let account = keyed
+                .account
+                .decode::<Account>()
+                .ok_or(anyhow!("{}: cannot decode account data", path.display()))?;
+            accounts.insert(Pubkey::from_str(&keyed.pubkey)?, account);
+        }
+        Ok(Self::Snapshot(accounts))
+    }
+
+    pub fn get_account(&self, key: &Pubkey) -> Result<Account> {
+        match self {
+            Self::Rpc(client) => Ok(client.get_account(key)?),
+            Self::Snapshot(accounts) => accounts
+                .get(key)
+                .cloned()
+                .ok_or(anyhow!("account {} is not in the snapshot", key)),
+        }
+    }
+
+    /// Fetches `keys`, leaving missing accounts out of the map.
+    pub fn get_accounts(&self, keys: &[Pubkey]) -> Result<HashMap<Pubkey, Account>> {
+        match self {
+            Self::Rpc(client) => Ok(keys
+                .iter()
+                .zip(client.get_multiple_accounts(keys)?)
+                .filter_map(|(key, account)| Some((*key, account?)))
+                .collect()),
+            Self::Snapshot(accounts) => Ok(keys
+                .iter()
+                .filter_map(|key| Some((*key, accounts.get(key)?.clone())))
+                .collect()),
+        }
+    }
+
+    pub fn get_program_accounts(&self, program_id: &Pubkey) -> Result<Vec<(Pubkey, Account)>> {
+        match self {
+            Self::Rpc(client) => Ok(client.get_program_accounts(program_id)?),
+            Self::Snapshot(accounts) => Ok(accounts
+                .iter()
+                .filter(|(_, account)| account.owner.eq(program_id))
+                .map(|(key, account)| (*key, account.clone()))
+                .collect()),
+        }
+    }
+}
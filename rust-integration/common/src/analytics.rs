@@ -0,0 +1,113 @@
+const MILLION: u128 = 1_000_000;
+const SECONDS_PER_YEAR: f64 = 365.0 * 24.0 * 3600.0;
+
+/*
+Volumes are read as the programs record them: the i-th volume record is the volume traded in the
+bucket starting at the i-th time record (unix seconds), both ring buffers, and volumes are in raw
+units of Y. Fees are estimated from `fee_millionth` before rebates, so they are an upper bound of
+what the pool earned. Sums saturate at `u64::MAX` rather than wrap.
+*/
+
+/// The fields of a v2 or v3 pool `PoolAnalytics` reads.
+pub trait AnalyticsSource {
+    fn cumulative_volume(&self) -> u64;
+    /// The volume ring buffer and the start times of its buckets
+    fn volume_records(&self) -> (&[u64], &[i64]);
+    fn fee_millionth(&self) -> u64;
+    fn protocol_fee_share_thousandth(&self) -> u64;
+    /// Pool value in raw units of Y at oracle prices
+    fn pool_value(&self) -> u64;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VolumeBucket {
+    pub start_time: i64,
+    pub volume: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PoolAnalytics {
+    pub cumulative_volume: u64,
+    /// Recorded buckets, oldest first
+    pub buckets: Vec<VolumeBucket>,
+    /// From the oldest bucket to `now`
+    pub window_seconds: i64,
+    pub window_volume: u64,
+    pub fee: u64,
+    pub protocol_fee: u64,
+    pub lp_fee: u64,
+    /// Pool value in raw units of Y at oracle prices
+    pub pool_value: u64,
+    /// Annualized `fee` and `lp_fee` over `pool_value`
+    pub fee_apr: f64,
+    pub lp_fee_apr: f64,
+}
+
+/// Non-empty buckets of the ring buffers, oldest first.
+pub fn volume_buckets(volumes: &[u64], start_times: &[i64]) -> Vec<VolumeBucket> {
+    let mut buckets: Vec<VolumeBucket> = start_times
+        .iter()
+        .zip(volumes.iter())
+        .filter(|(start_time, _)| **start_time > 0)
+        .map(|(start_time, volume)| VolumeBucket {
+            start_time: *start_time,
+            volume: *volume,
+        })
+        .collect();
+    buckets.sort_by_key(|bucket| bucket.start_time);
+    buckets
+}
+
+/// Returns (fee, protocol_fee, lp_fee) charged on `volume`, before rebates.
+pub fn estimate_fees(
+    fee_millionth: u64,
+    protocol_fee_share_thousandth: u64,
+    volume: u64,
+) -> (u64, u64, u64) {
+    let fee = u64::try_from(volume as u128 * fee_millionth as u128 / MILLION).unwrap_or(u64::MAX);
+    let protocol_fee = (fee as u128 * protocol_fee_share_thousandth as u128 / 1000) as u64;
+    let protocol_fee = protocol_fee.min(fee);
+    (fee, protocol_fee, fee - protocol_fee)
+}
+
+fn annualize(amount: u64, pool_value: u64, window_seconds: i64) -> f64 {
+    if pool_value == 0 || window_seconds <= 0 {
+        return 0.0;
+    }
+    amount as f64 / pool_value as f64 * SECONDS_PER_YEAR / window_seconds as f64
+}
+
+impl PoolAnalytics {
+    /// `pool` must have been updated, so that its balances and oracle prices are current.
+    pub fn new(pool: &impl AnalyticsSource, now: i64) -> Self {
+        let (volumes, start_times) = pool.volume_records();
+        let buckets = volume_buckets(volumes, start_times);
+        let window_seconds = buckets
+            .first()
+            .map_or(0, |bucket| now.saturating_sub(bucket.start_time));
+        let window_volume = buckets
+            .iter()
+            .fold(0u64, |sum, bucket| sum.saturating_add(bucket.volume));
+        let (fee, protocol_fee, lp_fee) = estimate_fees(
+            pool.fee_millionth(),
+            pool.protocol_fee_share_thousandth(),
+            window_volume,
+        );
+        let pool_value = pool.pool_value();
+
+        Self {
+            cumulative_volume: pool.cumulative_volume(),
+            buckets,
+            window_seconds,
+            window_volume,
+            fee,
+            protocol_fee,
+            lp_fee,
+            pool_value,
+            fee_apr: annualize(fee, pool_value, window_seconds),
+            lp_fee_apr: annualize(lp_fee, pool_value, window_seconds),
+        }
+    }
+}
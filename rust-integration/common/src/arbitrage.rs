@@ -0,0 +1,190 @@
+use crate::router::{RouteLeg, Router};
+use jupiter_amm_interface::Amm;
+use solana_sdk::pubkey::Pubkey;
+
+/*
+Arbitrage between pools trading the same pair, or between a pool and a reference price.
+
+Both curves are anchored to the oracle, so they only drift apart when inventories differ from
+target. A cycle sells through one pool and buys back through another; against a reference price a
+single leg is valued at that price. Sizes are searched on the quotes, fees and rebates included:
+doubling until the profit falls, then a ternary search around the best doubling, which finds the
+optimum when the profit is concave in the size.
+*/
+
+/// Below this, rounding makes the profit noisy, so the doubling does not stop on a drop.
+const NOISE_FLOOR: u64 = 1 << 10;
+
+/// Raw Y per raw X is `mult_x / mult_y`, the way `update_price` sets them on a pool.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReferencePrice {
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+    pub mult_x: u64,
+    pub mult_y: u64,
+}
+
+impl ReferencePrice {
+    /// `amount` of `mint` in units of the other mint.
+    fn convert(&self, mint: &Pubkey, amount: u64) -> u64 {
+        let (num, den) = if mint.eq(&self.mint_x) {
+            (self.mult_x, self.mult_y)
+        } else {
+            (self.mult_y, self.mult_x)
+        };
+        (amount as u128 * num as u128 / den as u128).min(u64::MAX as u128) as u64
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Opportunity {
+    /// Two legs for a cycle, one against a reference price
+    pub legs: Vec<RouteLeg>,
+    /// Input of the first leg
+    pub in_amount: u64,
+    /// What comes back in the input mint: the output of the last leg for a
+    /// cycle, the output valued at the reference price otherwise
+    pub return_amount: u64,
+    pub profit: u64,
+}
+
+impl Opportunity {
+    pub fn input_mint(&self) -> Pubkey {
+        self.legs[0].input_mint
+    }
+
+    /// Profit in basis points of the input.
+    pub fn profit_bps(&self) -> u64 {
+        (self.profit as u128 * 10_000 / self.in_amount as u128) as u64
+    }
+}
+
+/// Groups `opportunities` by input mint, profits in different mints do not
+/// compare, with the most profitable first in each group.
+fn rank(opportunities: &mut [Opportunity]) {
+    opportunities.sort_by(|a, b| {
+        a.input_mint()
+            .cmp(&b.input_mint())
+            .then_with(|| b.profit.cmp(&a.profit))
+    });
+}
+
+/// Size with the largest positive `profit`, `None` when no size makes a profit.
+fn optimal_size(profit: impl Fn(u64) -> Option<i128>) -> Option<(u64, i128)> {
+    let mut best: Option<(u64, i128)> = None;
+    let (mut amount, mut previous) = (1u64, None);
+    while let Some(current) = profit(amount) {
+        if best.map_or(true, |(_, best)| current > best) {
+            best = Some((amount, current));
+        }
+        if amount >= NOISE_FLOOR && previous.map_or(false, |previous| current < previous) {
+            break;
+        }
+        previous = Some(current);
+        let Some(next) = amount.checked_mul(2) else {
+            break;
+        };
+        amount = next;
+    }
+
+    let (center, _) = best?;
+    let (mut low, mut high) = (center / 2, center.saturating_mul(2));
+    while high - low > 2 {
+        let third = (high - low) / 3;
+        let (left, right) = (low + third, high - third);
+        match (profit(left), profit(right)) {
+            (Some(left_profit), Some(right_profit)) if left_profit < right_profit => low = left,
+            (Some(_), _) => high = right,
+            (None, _) => high = left,
+        }
+    }
+    (low..=high)
+        .filter_map(|amount| Some((amount, profit(amount)?)))
+        .chain(best)
+        .max_by_key(|(_, profit)| *profit)
+        .filter(|(_, profit)| *profit > 0)
+}
+
+/// Profitable cycles selling through one pool and buying back through
+/// another trading the same pair, ranked per input mint.
+pub fn find_cycles(pools: &[&dyn Amm]) -> Vec<Opportunity> {
+    let mut opportunities = vec![];
+    for (i, first) in pools.iter().enumerate() {
+        let mints = first.get_reserve_mints();
+        let (start_mint, middle_mint) = (mints[0], mints[1]);
+        for (j, second) in pools.iter().enumerate() {
+            let second_mints = second.get_reserve_mints();
+            if i == j || !second_mints.contains(&start_mint) || !second_mints.contains(&middle_mint)
+            {
+                continue;
+            }
+            let legs = |in_amount| {
+                let first_leg = Router::quote_leg(*first, start_mint, middle_mint, in_amount)?;
+                let second_leg =
+                    Router::quote_leg(*second, middle_mint, start_mint, first_leg.out_amount)?;
+                Some((first_leg, second_leg))
+            };
+            let Some((in_amount, _)) = optimal_size(|in_amount| {
+                let (_, second_leg) = legs(in_amount)?;
+                Some(second_leg.out_amount as i128 - in_amount as i128)
+            }) else {
+                continue;
+            };
+            let (first_leg, second_leg) = legs(in_amount).unwrap();
+            opportunities.push(Opportunity {
+                in_amount,
+                return_amount: second_leg.out_amount,
+                profit: second_leg.out_amount - in_amount,
+                legs: vec![first_leg, second_leg],
+            });
+        }
+    }
+    rank(&mut opportunities);
+    opportunities
+}
+
+/// Profitable trades through pools trading the pair of `reference`, valuing
+/// the output at the reference price, ranked per input mint.
+pub fn find_against_reference(pools: &[&dyn Amm], reference: &ReferencePrice) -> Vec<Opportunity> {
+    let mut opportunities = vec![];
+    for pool in pools {
+        let mints = pool.get_reserve_mints();
+        if !mints.contains(&reference.mint_x) || !mints.contains(&reference.mint_y) {
+            continue;
+        }
+        for (input_mint, output_mint) in [
+            (reference.mint_x, reference.mint_y),
+            (reference.mint_y, reference.mint_x),
+        ] {
+            let leg = |in_amount| Router::quote_leg(*pool, input_mint, output_mint, in_amount);
+            let Some((in_amount, _)) = optimal_size(|in_amount| {
+                let leg = leg(in_amount)?;
+                Some(reference.convert(&output_mint, leg.out_amount) as i128 - in_amount as i128)
+            }) else {
+                continue;
+            };
+            let leg = leg(in_amount).unwrap();
+            let return_amount = reference.convert(&output_mint, leg.out_amount);
+            opportunities.push(Opportunity {
+                legs: vec![leg],
+                in_amount,
+                return_amount,
+                profit: return_amount - in_amount,
+            });
+        }
+    }
+    rank(&mut opportunities);
+    opportunities
+}
+
+/// Cycles between `pools` and trades against each of `references`. Profits are
+/// in units of each opportunity's input mint, so opportunities are grouped by
+/// input mint and ranked within each group.
+pub fn detect(pools: &[&dyn Amm], references: &[ReferencePrice]) -> Vec<Opportunity> {
+    let mut opportunities = find_cycles(pools);
+    for reference in references {
+        opportunities.extend(find_against_reference(pools, reference));
+    }
+    rank(&mut opportunities);
+    opportunities
+}
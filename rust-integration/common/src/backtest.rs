@@ -0,0 +1,124 @@
+use anyhow::{anyhow, Result};
+use obric_math::price::normalize_price_pair;
+use obric_math::units::{NormalizedPrice, TokenX, TokenY};
+
+/*
+Inputs of the v2 and v3 backtests. Prices are Pyth integers sharing one exponent, scaled for
+`update_price` the way `PriceFeed::normalized_pair` does. At equal timestamps prices apply
+before trades.
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PricePoint {
+    pub timestamp: i64,
+    pub price_x: i64,
+    pub price_y: i64,
+    pub expo: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Trade {
+    pub timestamp: i64,
+    pub is_x_to_y: bool,
+    pub in_amount: u64,
+}
+
+impl PricePoint {
+    /// `price_x` and `price_y` scaled to a common exponent the way `update_price` is fed.
+    pub fn normalized(&self) -> Result<(NormalizedPrice<TokenX>, NormalizedPrice<TokenY>)> {
+        let (price_x, price_y, _) =
+            normalize_price_pair((self.price_x, self.expo), (self.price_y, self.expo))?;
+        Ok((price_x, price_y))
+    }
+}
+
+/// Checks that `prices` is not empty and starts no later than `trades`.
+pub fn check_series(prices: &[PricePoint], trades: &[Trade]) -> Result<()> {
+    let first_price = prices.first().ok_or(anyhow!("empty price series"))?;
+    if trades
+        .first()
+        .map_or(false, |trade| trade.timestamp < first_price.timestamp)
+    {
+        return Err(anyhow!("trades start before the first price"));
+    }
+    Ok(())
+}
+
+/// A step of the replay.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event<'a> {
+    /// The price with its index in the series
+    Price(usize, &'a PricePoint),
+    Trade(&'a Trade),
+}
+
+/// `prices` and `trades`, both sorted by timestamp, merged in time order with
+/// prices first at equal timestamps.
+pub fn events<'a>(
+    prices: &'a [PricePoint],
+    trades: &'a [Trade],
+) -> impl Iterator<Item = Event<'a>> {
+    let (mut next_price, mut next_trade) = (0, 0);
+    std::iter::from_fn(move || {
+        let price_first = match (prices.get(next_price), trades.get(next_trade)) {
+            (Some(price), Some(trade)) => price.timestamp <= trade.timestamp,
+            (price, _) => price.is_some(),
+        };
+        if price_first {
+            let index = next_price;
+            next_price += 1;
+            Some(Event::Price(index, &prices[index]))
+        } else {
+            let trade = trades.get(next_trade)?;
+            next_trade += 1;
+            Some(Event::Trade(trade))
+        }
+    })
+}
+
+fn parse_csv<T>(csv: &str, columns: usize, parse: impl Fn(&[&str]) -> Result<T>) -> Result<Vec<T>> {
+    let mut rows = vec![];
+    for (index, line) in csv.lines().enumerate() {
+        let line = line.trim();
+        // blank lines and a header row
+        if line.is_empty() || (index == 0 && line.starts_with(|c: char| c.is_alphabetic())) {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() != columns {
+            return Err(anyhow!("line {}: expected {} columns", index + 1, columns));
+        }
+        rows.push(parse(&fields).map_err(|e| anyhow!("line {}: {}", index + 1, e))?);
+    }
+    Ok(rows)
+}
+
+/// Rows of `timestamp,price_x,price_y,expo`, with an optional header.
+pub fn parse_prices_csv(csv: &str) -> Result<Vec<PricePoint>> {
+    parse_csv(csv, 4, |fields| {
+        Ok(PricePoint {
+            timestamp: fields[0].parse()?,
+            price_x: fields[1].parse()?,
+            price_y: fields[2].parse()?,
+            expo: fields[3].parse()?,
+        })
+    })
+}
+
+/// Rows of `timestamp,side,in_amount` where side is `x_to_y` or `y_to_x`, with an optional header.
+pub fn parse_trades_csv(csv: &str) -> Result<Vec<Trade>> {
+    parse_csv(csv, 3, |fields| {
+        let is_x_to_y = match fields[1] {
+            "x_to_y" => true,
+            "y_to_x" => false,
+            side => return Err(anyhow!("unknown side {}", side)),
+        };
+        Ok(Trade {
+            timestamp: fields[0].parse()?,
+            is_x_to_y,
+            in_amount: fields[2].parse()?,
+        })
+    })
+}
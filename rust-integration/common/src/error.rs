@@ -0,0 +1,122 @@
+use obric_math::{MathError, PriceError, PriceSide};
+use solana_sdk::pubkey::Pubkey;
+use thiserror::Error;
+
+/// A price age limit to pass to `with_max_price_age`, in seconds either way
+/// from the clock as pyth's `get_price_no_older_than` counts. The AMMs take
+/// prices of any age unless given one, as the programs do.
+pub const MAX_PRICE_AGE: u64 = 60;
+
+/// Why `ObricV2Amm` or `ObricV3Amm` could not load, update, quote or build a
+/// swap, with the pool and the account involved. The `Amm` trait returns it
+/// as an `anyhow::Error`, `downcast_ref::<ObricSdkError>()` gets it back.
+#[derive(Debug, Error)]
+pub enum ObricSdkError {
+    /// Needs an `update` first
+    #[error("pool {pool} has not loaded {what}, update it first")]
+    PoolNotLoaded { pool: Pubkey, what: &'static str },
+    /// Not in the accounts passed to `update`
+    #[error("account {account} of pool {pool} is missing")]
+    AccountNotFound { pool: Pubkey, account: Pubkey },
+    #[error("account {account} of pool {pool} is not a valid {kind}: {source}")]
+    Deserialization {
+        pool: Pubkey,
+        account: Pubkey,
+        kind: &'static str,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// A price the program would not accept either: zero, negative or out of range
+    #[error("oracle {oracle} of pool {pool} has no usable price: {source}")]
+    InvalidOraclePrice {
+        pool: Pubkey,
+        oracle: Pubkey,
+        source: MathError,
+    },
+    /// Published further from the clock than the limit set with
+    /// `with_max_price_age`. The programs take the price anyway, so this only
+    /// keeps quotes off old prices
+    #[error(
+        "oracle {oracle} of pool {pool} last published at {publish_time}, the clock is at {now}"
+    )]
+    StaleOracle {
+        pool: Pubkey,
+        oracle: Pubkey,
+        publish_time: i64,
+        now: i64,
+    },
+    #[error("pool {pool} cannot fill {in_amount} of {input_mint}")]
+    InsufficientLiquidity {
+        pool: Pubkey,
+        input_mint: Pubkey,
+        in_amount: u64,
+    },
+    #[error("mint {mint} is not traded by pool {pool}")]
+    UnsupportedMint { pool: Pubkey, mint: Pubkey },
+    /// The v2 program swaps SPL Token mints only, so `update` does not load Token-2022 pools
+    #[error(
+        "mint {mint} of pool {pool} belongs to {token_program}, the swap takes SPL Token only"
+    )]
+    UnsupportedTokenProgram {
+        pool: Pubkey,
+        mint: Pubkey,
+        token_program: Pubkey,
+    },
+    /// v3 only trades mints Larix lends
+    #[error("mint {mint} of pool {pool} has no Larix reserve")]
+    NoLarixReserve { pool: Pubkey, mint: Pubkey },
+    #[error("pool {pool}: {source}")]
+    Math { pool: Pubkey, source: MathError },
+}
+
+impl ObricSdkError {
+    /// `InsufficientLiquidity` for the curve's insufficient active errors, `Math` otherwise.
+    pub fn from_quote(pool: Pubkey, input_mint: Pubkey, in_amount: u64, source: MathError) -> Self {
+        match source {
+            MathError::InsufficientActiveX | MathError::InsufficientActiveY => {
+                ObricSdkError::InsufficientLiquidity {
+                    pool,
+                    input_mint,
+                    in_amount,
+                }
+            }
+            source => ObricSdkError::Math { pool, source },
+        }
+    }
+
+    /// `StaleOracle` unless `publish_time` is within `max_age` seconds of `now`.
+    pub fn check_price_age(
+        pool: Pubkey,
+        oracle: Pubkey,
+        publish_time: i64,
+        now: i64,
+        max_age: u64,
+    ) -> Result<(), Self> {
+        match publish_time.abs_diff(now) > max_age {
+            true => Err(ObricSdkError::StaleOracle {
+                pool,
+                oracle,
+                publish_time,
+                now,
+            }),
+            false => Ok(()),
+        }
+    }
+
+    /// `InvalidOraclePrice` blaming the oracle of the side `normalize_price_pair`
+    /// could not use.
+    pub fn from_price(
+        pool: Pubkey,
+        oracle_x: Pubkey,
+        oracle_y: Pubkey,
+        PriceError { side, source }: PriceError,
+    ) -> Self {
+        ObricSdkError::InvalidOraclePrice {
+            pool,
+            oracle: match side {
+                PriceSide::X => oracle_x,
+                PriceSide::Y => oracle_y,
+            },
+            source,
+        }
+    }
+}
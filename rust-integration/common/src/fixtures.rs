@@ -0,0 +1,173 @@
+//! Accounts for offline pools: pyth prices, SPL and Token-2022 mints and
+//! token accounts, and the clock. Every account holds a rent-exempt balance, so `solana-program-test` takes
+//! them as they are.
+
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::{
+    TransferFee, TransferFeeConfig,
+};
+use anchor_spl::token_2022::spl_token_2022::extension::{ExtensionType, StateWithExtensionsMut};
+use pyth_sdk_solana::state::{AccountType, PriceStatus, PythnetPriceAccount, MAGIC, VERSION_2};
+use solana_sdk::account::{create_account_for_test, Account};
+use solana_sdk::clock::Clock;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+
+/// The pyth program, owner of the price accounts. Same as
+/// `obric_solana::state::price_feed::ID`.
+pub const PRICE_FEED_OWNER: Pubkey = pubkey!("FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH");
+
+const LAMPORTS: u64 = 1_000_000_000;
+
+/// A trading pyth price account with an exponent of -8, published at 0.
+pub fn price_data(price: i64) -> Vec<u8> {
+    price_data_at(price, 0)
+}
+
+/// `price_data` published at `publish_time`.
+pub fn price_data_at(price: i64, publish_time: i64) -> Vec<u8> {
+    let mut price_account: PythnetPriceAccount = bytemuck::Zeroable::zeroed();
+    price_account.magic = MAGIC;
+    price_account.ver = VERSION_2;
+    price_account.atype = AccountType::Price as u32;
+    price_account.expo = -8;
+    price_account.agg.price = price;
+    price_account.agg.status = PriceStatus::Trading;
+    price_account.prev_price = price;
+    price_account.timestamp = publish_time;
+    price_account.prev_timestamp = publish_time;
+    bytemuck::bytes_of(&price_account).to_vec()
+}
+
+/// `price_data` owned by the pyth program.
+pub fn price_account(price: i64) -> Account {
+    price_account_at(price, 0)
+}
+
+/// `price_data_at` owned by the pyth program.
+pub fn price_account_at(price: i64, publish_time: i64) -> Account {
+    Account {
+        lamports: LAMPORTS,
+        data: price_data_at(price, publish_time),
+        owner: PRICE_FEED_OWNER,
+        ..Account::default()
+    }
+}
+
+pub fn packed_account<T: Pack>(state: T, owner: Pubkey) -> Account {
+    let mut data = vec![0; T::LEN];
+    T::pack(state, &mut data).unwrap();
+    Account {
+        lamports: LAMPORTS,
+        data,
+        owner,
+        ..Account::default()
+    }
+}
+
+/// An SPL mint with a supply of 1_000_000 and no authorities.
+pub fn mint_account(decimals: u8) -> Account {
+    packed_account(
+        spl_token::state::Mint {
+            supply: 1_000_000,
+            decimals,
+            is_initialized: true,
+            ..spl_token::state::Mint::default()
+        },
+        spl_token::ID,
+    )
+}
+
+/// An SPL token account owned by `Pubkey::default()`.
+pub fn token_account(mint: Pubkey, amount: u64) -> Account {
+    owned_token_account(mint, Pubkey::default(), amount)
+}
+
+pub fn owned_token_account(mint: Pubkey, owner: Pubkey, amount: u64) -> Account {
+    packed_account(
+        spl_token::state::Account {
+            mint,
+            owner,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..spl_token::state::Account::default()
+        },
+        spl_token::ID,
+    )
+}
+
+/// A Token-2022 mint charging no transfer fee before `epoch` and
+/// `transfer_fee_basis_points`, uncapped, from `epoch` on.
+pub fn transfer_fee_mint_account(
+    decimals: u8,
+    epoch: u64,
+    transfer_fee_basis_points: u16,
+) -> Account {
+    let len = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+        ExtensionType::TransferFeeConfig,
+    ])
+    .unwrap();
+    let mut data = vec![0; len];
+    let mut mint =
+        StateWithExtensionsMut::<spl_token_2022::state::Mint>::unpack_uninitialized(&mut data)
+            .unwrap();
+    let config = mint.init_extension::<TransferFeeConfig>(true).unwrap();
+    config.newer_transfer_fee = TransferFee {
+        epoch: epoch.into(),
+        maximum_fee: u64::MAX.into(),
+        transfer_fee_basis_points: transfer_fee_basis_points.into(),
+    };
+    mint.base = spl_token_2022::state::Mint {
+        supply: 1_000_000,
+        decimals,
+        is_initialized: true,
+        ..spl_token_2022::state::Mint::default()
+    };
+    mint.pack_base();
+    mint.init_account_type().unwrap();
+    Account {
+        lamports: LAMPORTS,
+        data,
+        owner: spl_token_2022::ID,
+        ..Account::default()
+    }
+}
+
+/// A Token-2022 token account without extensions, owned by `Pubkey::default()`.
+pub fn token_2022_account(mint: Pubkey, amount: u64) -> Account {
+    packed_account(
+        spl_token_2022::state::Account {
+            mint,
+            amount,
+            state: spl_token_2022::state::AccountState::Initialized,
+            ..spl_token_2022::state::Account::default()
+        },
+        spl_token_2022::ID,
+    )
+}
+
+/// The clock sysvar at `epoch` and a unix timestamp of 0.
+pub fn clock_account(epoch: u64) -> Account {
+    clock_account_at(epoch, 0)
+}
+
+/// The clock sysvar at `epoch` and `unix_timestamp`.
+pub fn clock_account_at(epoch: u64, unix_timestamp: i64) -> Account {
+    create_account_for_test(&Clock {
+        epoch,
+        unix_timestamp,
+        ..Clock::default()
+    })
+}
+
+/// Writes `data`, the mainnet account `key`, to `fuzz/recorded/<kind>/`, where
+/// the fuzz target `kind` starts from it.
+pub fn record_fuzz_fixture(kind: &str, key: &Pubkey, data: &[u8]) {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../fuzz/recorded")
+        .join(kind);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join(format!("{}.bin", key)), data).unwrap();
+}
@@ -0,0 +1,13 @@
+//! Code the v2 and v3 SDKs and the wasm bindings share. Depends on neither
+//! accounts crate, so the programs' own tests can use it too.
+
+pub mod analytics;
+pub mod arbitrage;
+pub mod backtest;
+pub mod error;
+#[cfg(feature = "test-only")]
+pub mod fixtures;
+pub mod router;
+pub mod simulation;
+pub mod sweep;
+pub mod token;
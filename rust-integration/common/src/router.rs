@@ -0,0 +1,270 @@
+use anyhow::{anyhow, Result};
+use jupiter_amm_interface::{Amm, QuoteParams, SwapAndAccountMetas, SwapParams};
+use solana_sdk::instruction::AccountMeta;
+use solana_sdk::pubkey::Pubkey;
+
+/*
+Routing over any set of updated pools through the `Amm` trait, so v2 and v3 pools can be mixed.
+
+Splits equalize marginal prices by water-filling: the order is cut in `SPLIT_STEPS` chunks and each
+chunk goes to the pool whose next chunk yields the most output. On concave quotes this leaves the
+marginal output of every pool used within one chunk of the others. Two-hop routes quote the second
+leg with the quoted output of the first, so its input amount is only exact if the first leg fills
+at its quote.
+*/
+
+const SPLIT_STEPS: u64 = 64;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RouteLeg {
+    pub pool: Pubkey,
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    pub in_amount: u64,
+    pub out_amount: u64,
+    pub fee_amount: u64,
+    pub fee_mint: Pubkey,
+}
+
+/// `hops` run one after the other. The legs of a hop trade the same pair in
+/// parallel and split its input.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Route {
+    pub hops: Vec<Vec<RouteLeg>>,
+}
+
+impl Route {
+    pub fn in_amount(&self) -> u64 {
+        self.hops
+            .first()
+            .map_or(0, |legs| legs.iter().map(|leg| leg.in_amount).sum())
+    }
+
+    pub fn out_amount(&self) -> u64 {
+        self.hops
+            .last()
+            .map_or(0, |legs| legs.iter().map(|leg| leg.out_amount).sum())
+    }
+
+    pub fn legs(&self) -> impl Iterator<Item = &RouteLeg> {
+        self.hops.iter().flatten()
+    }
+}
+
+pub struct Router<'a> {
+    pools: Vec<&'a dyn Amm>,
+}
+
+impl<'a> Router<'a> {
+    /// `pools` must have been updated.
+    pub fn new(pools: Vec<&'a dyn Amm>) -> Self {
+        Self { pools }
+    }
+
+    fn pool(&self, key: &Pubkey) -> Option<&'a dyn Amm> {
+        self.pools.iter().copied().find(|amm| amm.key().eq(key))
+    }
+
+    fn pools_trading(&self, input_mint: &Pubkey, output_mint: &Pubkey) -> Vec<&'a dyn Amm> {
+        self.pools
+            .iter()
+            .copied()
+            .filter(|amm| {
+                let mints = amm.get_reserve_mints();
+                mints.contains(input_mint) && mints.contains(output_mint)
+            })
+            .collect()
+    }
+
+    /// `None` when the pool cannot fill `in_amount`.
+    pub fn quote_leg(
+        amm: &dyn Amm,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+        in_amount: u64,
+    ) -> Option<RouteLeg> {
+        let quote = amm
+            .quote(&QuoteParams {
+                input_mint,
+                in_amount,
+                output_mint,
+            })
+            .ok()?;
+        (!quote.not_enough_liquidity && quote.out_amount > 0).then_some(RouteLeg {
+            pool: amm.key(),
+            input_mint,
+            output_mint,
+            in_amount,
+            out_amount: quote.out_amount,
+            fee_amount: quote.fee_amount,
+            fee_mint: quote.fee_mint,
+        })
+    }
+
+    /// Best single pool trading the pair directly.
+    pub fn best_single(
+        &self,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+        in_amount: u64,
+    ) -> Option<Route> {
+        self.pools_trading(&input_mint, &output_mint)
+            .into_iter()
+            .filter_map(|amm| Self::quote_leg(amm, input_mint, output_mint, in_amount))
+            .max_by_key(|leg| leg.out_amount)
+            .map(|leg| Route {
+                hops: vec![vec![leg]],
+            })
+    }
+
+    /// Best route through one intermediate mint, over every pair of pools
+    /// sharing it.
+    pub fn best_two_hop(
+        &self,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+        in_amount: u64,
+    ) -> Option<Route> {
+        let mut best: Option<Route> = None;
+        for first in &self.pools {
+            let mints = first.get_reserve_mints();
+            if !mints.contains(&input_mint) || mints.contains(&output_mint) {
+                continue;
+            }
+            for middle_mint in mints.into_iter().filter(|mint| !mint.eq(&input_mint)) {
+                let Some(first_leg) = Self::quote_leg(*first, input_mint, middle_mint, in_amount)
+                else {
+                    continue;
+                };
+                let Some(second_leg) = self
+                    .pools_trading(&middle_mint, &output_mint)
+                    .into_iter()
+                    .filter_map(|amm| {
+                        Self::quote_leg(amm, middle_mint, output_mint, first_leg.out_amount)
+                    })
+                    .max_by_key(|leg| leg.out_amount)
+                else {
+                    continue;
+                };
+                if best
+                    .as_ref()
+                    .map_or(true, |route| second_leg.out_amount > route.out_amount())
+                {
+                    best = Some(Route {
+                        hops: vec![vec![first_leg], vec![second_leg]],
+                    });
+                }
+            }
+        }
+        best
+    }
+
+    /// Split of `in_amount` across every pool trading the pair directly. When
+    /// the pools cannot take all of it, the split of what they can, so check
+    /// `Route::in_amount`.
+    pub fn best_split(
+        &self,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+        in_amount: u64,
+    ) -> Option<Route> {
+        let pools = self.pools_trading(&input_mint, &output_mint);
+        if pools.is_empty() || in_amount == 0 {
+            return None;
+        }
+        let chunk = (in_amount / SPLIT_STEPS).max(1);
+        let mut allocated = vec![0u64; pools.len()];
+        let mut legs: Vec<Option<RouteLeg>> = vec![None; pools.len()];
+        let mut remaining = in_amount;
+        while remaining > 0 {
+            // the last chunk takes the remainder
+            let step = if remaining < 2 * chunk {
+                remaining
+            } else {
+                chunk
+            };
+            let best = pools
+                .iter()
+                .enumerate()
+                .filter_map(|(index, amm)| {
+                    let leg =
+                        Self::quote_leg(*amm, input_mint, output_mint, allocated[index] + step)?;
+                    let current_out = legs[index].as_ref().map_or(0, |leg| leg.out_amount);
+                    Some((index, leg.out_amount.saturating_sub(current_out), leg))
+                })
+                .max_by_key(|(_, gain, _)| *gain);
+            let Some((index, _, leg)) = best else {
+                break;
+            };
+            allocated[index] += step;
+            legs[index] = Some(leg);
+            remaining -= step;
+        }
+        let legs: Vec<RouteLeg> = legs.into_iter().flatten().collect();
+        (!legs.is_empty()).then_some(Route { hops: vec![legs] })
+    }
+
+    /// Route with the largest output among the best single pool, the best
+    /// split and the best two-hop route, preferring those that fill all of
+    /// `in_amount`.
+    pub fn best_route(
+        &self,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+        in_amount: u64,
+    ) -> Option<Route> {
+        [
+            self.best_single(input_mint, output_mint, in_amount),
+            self.best_split(input_mint, output_mint, in_amount),
+            self.best_two_hop(input_mint, output_mint, in_amount),
+        ]
+        .into_iter()
+        .flatten()
+        // on ties, the route with fewer legs
+        .max_by(|a, b| {
+            (a.in_amount() == in_amount)
+                .cmp(&(b.in_amount() == in_amount))
+                .then(a.out_amount().cmp(&b.out_amount()))
+                .then(b.legs().count().cmp(&a.legs().count()))
+        })
+    }
+
+    /// Swap account metas of every leg of `route`, in order. `token_account`
+    /// maps a mint to the user's token account for it, and each leg's minimum
+    /// output is its quote less `slippage_bps`.
+    pub fn account_metas(
+        &self,
+        route: &Route,
+        user: Pubkey,
+        token_account: impl Fn(&Pubkey) -> Pubkey,
+        slippage_bps: u64,
+    ) -> Result<Vec<Vec<AccountMeta>>> {
+        route
+            .legs()
+            .map(|leg| {
+                let amm = self
+                    .pool(&leg.pool)
+                    .ok_or(anyhow!("pool {} is not in the router", leg.pool))?;
+                let program_id = amm.program_id();
+                let min_out = (leg.out_amount as u128
+                    * 10_000u128.saturating_sub(slippage_bps as u128)
+                    / 10_000) as u64;
+                let SwapAndAccountMetas { account_metas, .. } =
+                    amm.get_swap_and_account_metas(&SwapParams {
+                        in_amount: leg.in_amount,
+                        out_amount: min_out,
+                        source_mint: leg.input_mint,
+                        destination_mint: leg.output_mint,
+                        source_token_account: token_account(&leg.input_mint),
+                        destination_token_account: token_account(&leg.output_mint),
+                        token_transfer_authority: user,
+                        open_order_address: None,
+                        quote_mint_to_referrer: None,
+                        jupiter_program_id: &program_id,
+                        missing_dynamic_accounts_as_default: false,
+                    })?;
+                Ok(account_metas)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,293 @@
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anchor_spl::token::spl_token;
+use anyhow::{anyhow, Result};
+use jupiter_amm_interface::{Amm, QuoteParams};
+use solana_sdk::account::Account;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+
+/// What `QuoteConsistencyChecker` needs from an SDK's AMM besides `Amm`.
+pub trait SimulatedAmm: Amm {
+    /// The swap instruction for `in_amount` of `source_mint`, signed by `user`.
+    fn swap_instruction(
+        &self,
+        source_mint: Pubkey,
+        user: Pubkey,
+        source_token_account: Pubkey,
+        destination_token_account: Pubkey,
+        in_amount: u64,
+        min_output_amt: u64,
+    ) -> Result<Instruction>;
+
+    /// Program of the user's associated token account for `mint`.
+    fn token_program(&self, _mint: &Pubkey) -> Pubkey {
+        spl_token::ID
+    }
+}
+
+/// Where a checker reads accounts and runs swaps: `simulateTransaction` on a
+/// cluster, or a `solana-program-test` bank.
+pub trait SwapSimulator {
+    /// Wallet whose associated token accounts the swaps move.
+    fn user(&self) -> Pubkey;
+
+    /// The current `keys`, leaving out the ones that do not exist.
+    fn accounts(&self, keys: &[Pubkey]) -> Result<HashMap<Pubkey, Account>>;
+
+    /// Balance of the token account `destination` before and after `instruction`.
+    fn simulate(&self, instruction: Instruction, destination: Pubkey) -> Result<(u64, u64)>;
+}
+
+pub struct SimulatedSwap {
+    pub pool: Pubkey,
+    pub input_mint: Pubkey,
+    pub in_amount: u64,
+    pub quoted_out: u64,
+    pub executed_out: u64,
+}
+
+impl SimulatedSwap {
+    /// Divergence between quoted and executed output, in basis points of the quote.
+    pub fn divergence_bps(&self) -> u64 {
+        let diff = self.quoted_out.abs_diff(self.executed_out) as u128;
+        if diff == 0 {
+            0
+        } else if self.quoted_out == 0 {
+            u64::MAX
+        } else {
+            (diff * 10_000 / self.quoted_out as u128) as u64
+        }
+    }
+}
+
+/// Compares an AMM's quotes with the output of the real swap transaction, run
+/// by `simulator` from the accounts the AMM is updated with.
+pub struct QuoteConsistencyChecker<S> {
+    pub simulator: S,
+    pub tolerance_bps: u64,
+}
+
+impl<S: SwapSimulator> QuoteConsistencyChecker<S> {
+    /// Updates `amm` from the simulator and quotes `in_amount` before running
+    /// it. Returns `None` when the pool has not enough liquidity to quote it.
+    pub fn simulate_swap<A: SimulatedAmm>(
+        &self,
+        amm: &mut A,
+        input_mint: Pubkey,
+        in_amount: u64,
+    ) -> Result<Option<SimulatedSwap>> {
+        amm.update(&self.simulator.accounts(&amm.get_accounts_to_update())?)?;
+        let output_mint = match amm.get_reserve_mints()[..] {
+            [mint_x, mint_y] if input_mint.eq(&mint_x) => mint_y,
+            [mint_x, _] => mint_x,
+            _ => return Err(anyhow!("pool {} does not trade a pair", amm.key())),
+        };
+        let quote = amm.quote(&QuoteParams {
+            input_mint,
+            in_amount,
+            output_mint,
+        })?;
+        if quote.not_enough_liquidity {
+            return Ok(None);
+        }
+
+        let user = self.simulator.user();
+        let source = get_associated_token_address_with_program_id(
+            &user,
+            &input_mint,
+            &amm.token_program(&input_mint),
+        );
+        let destination = get_associated_token_address_with_program_id(
+            &user,
+            &output_mint,
+            &amm.token_program(&output_mint),
+        );
+        let ix = amm.swap_instruction(input_mint, user, source, destination, in_amount, 0)?;
+        let (before, after) = self.simulator.simulate(ix, destination)?;
+
+        Ok(Some(SimulatedSwap {
+            pool: amm.key(),
+            input_mint,
+            in_amount,
+            quoted_out: quote.out_amount,
+            executed_out: after.saturating_sub(before),
+        }))
+    }
+
+    /// Simulates every size in both directions, failing on the first
+    /// divergence above `tolerance_bps`.
+    pub fn check<A: SimulatedAmm>(&self, amm: &mut A, sizes: &[u64]) -> Result<Vec<SimulatedSwap>> {
+        let mut results = vec![];
+        for input_mint in amm.get_reserve_mints() {
+            for &in_amount in sizes {
+                let Some(simulated) = self.simulate_swap(amm, input_mint, in_amount)? else {
+                    continue;
+                };
+                if simulated.divergence_bps() > self.tolerance_bps {
+                    return Err(anyhow!(
+                        "pool {} {} -> quoted {} but executed {} ({} bps)",
+                        simulated.pool,
+                        in_amount,
+                        simulated.quoted_out,
+                        simulated.executed_out,
+                        simulated.divergence_bps()
+                    ));
+                }
+                results.push(simulated);
+            }
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(any(feature = "rpc", feature = "banks"))]
+fn token_amount(account: &Account) -> Result<u64> {
+    use crate::token::unpack_token_amount;
+    Ok(unpack_token_amount(&account.data, &account.owner)?)
+}
+
+#[cfg(feature = "rpc")]
+pub use rpc::RpcSimulator;
+
+#[cfg(feature = "rpc")]
+mod rpc {
+    use super::{token_amount, SwapSimulator};
+    use anyhow::{anyhow, Result};
+    use solana_account_decoder::UiAccountEncoding;
+    use solana_client::rpc_client::RpcClient;
+    use solana_client::rpc_config::{
+        RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig,
+    };
+    use solana_sdk::account::Account;
+    use solana_sdk::instruction::Instruction;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::transaction::Transaction;
+    use std::collections::HashMap;
+
+    /// Runs swaps through `simulateTransaction` (mainnet, or a local validator
+    /// loaded with snapshot accounts), so nothing is committed.
+    pub struct RpcSimulator<'a> {
+        pub client: &'a RpcClient,
+        /// Wallet holding both mints on the simulated cluster, signatures are not verified.
+        pub user: Pubkey,
+    }
+
+    impl SwapSimulator for RpcSimulator<'_> {
+        fn user(&self) -> Pubkey {
+            self.user
+        }
+
+        fn accounts(&self, keys: &[Pubkey]) -> Result<HashMap<Pubkey, Account>> {
+            let accounts = self.client.get_multiple_accounts(keys)?;
+            Ok(keys
+                .iter()
+                .zip(accounts)
+                .filter_map(|(key, account)| Some((*key, account?)))
+                .collect())
+        }
+
+        fn simulate(&self, instruction: Instruction, destination: Pubkey) -> Result<(u64, u64)> {
+            let before = token_amount(&self.client.get_account(&destination)?)?;
+            let tx = Transaction::new_with_payer(&[instruction], Some(&self.user));
+            let result = self
+                .client
+                .simulate_transaction_with_config(
+                    &tx,
+                    RpcSimulateTransactionConfig {
+                        sig_verify: false,
+                        replace_recent_blockhash: true,
+                        accounts: Some(RpcSimulateTransactionAccountsConfig {
+                            encoding: Some(UiAccountEncoding::Base64),
+                            addresses: vec![destination.to_string()],
+                        }),
+                        ..RpcSimulateTransactionConfig::default()
+                    },
+                )?
+                .value;
+            if let Some(err) = result.err {
+                return Err(anyhow!(
+                    "simulated swap failed: {}, logs: {:?}",
+                    err,
+                    result.logs
+                ));
+            }
+            let after = result
+                .accounts
+                .and_then(|accounts| accounts.into_iter().next().flatten())
+                .and_then(|account| account.decode::<Account>())
+                .ok_or(anyhow!("simulation returned no destination account"))?;
+            Ok((before, token_amount(&after)?))
+        }
+    }
+}
+
+#[cfg(feature = "banks")]
+pub use banks::BanksSimulator;
+
+#[cfg(feature = "banks")]
+mod banks {
+    use super::{token_amount, SwapSimulator};
+    use anyhow::{anyhow, Result};
+    use solana_banks_client::BanksClient;
+    use solana_sdk::account::Account;
+    use solana_sdk::instruction::Instruction;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::{Keypair, Signer};
+    use solana_sdk::transaction::Transaction;
+    use std::collections::HashMap;
+    use tokio::runtime::Runtime;
+
+    /// Runs swaps on a `solana-program-test` bank, such as one running the
+    /// programs' test-only builds. Swaps are committed, which is why the
+    /// checker updates the AMM before every quote.
+    pub struct BanksSimulator<'a> {
+        pub client: BanksClient,
+        /// Drives `client`, the one `ProgramTest::start` ran on
+        pub runtime: &'a Runtime,
+        pub payer: &'a Keypair,
+        /// Signs for the swaps and holds both mints in its associated token accounts
+        pub user: &'a Keypair,
+    }
+
+    impl SwapSimulator for BanksSimulator<'_> {
+        fn user(&self) -> Pubkey {
+            self.user.pubkey()
+        }
+
+        fn accounts(&self, keys: &[Pubkey]) -> Result<HashMap<Pubkey, Account>> {
+            let mut client = self.client.clone();
+            self.runtime.block_on(async move {
+                let mut accounts = HashMap::new();
+                for &key in keys {
+                    if let Some(account) = client.get_account(key).await? {
+                        accounts.insert(key, account);
+                    }
+                }
+                Ok(accounts)
+            })
+        }
+
+        fn simulate(&self, instruction: Instruction, destination: Pubkey) -> Result<(u64, u64)> {
+            let mut client = self.client.clone();
+            self.runtime.block_on(async move {
+                let before = client
+                    .get_account(destination)
+                    .await?
+                    .ok_or(anyhow!("destination {} does not exist", destination))?;
+                let tx = Transaction::new_signed_with_payer(
+                    &[instruction],
+                    Some(&self.payer.pubkey()),
+                    &[self.payer, self.user],
+                    client.get_latest_blockhash().await?,
+                );
+                client.process_transaction(tx).await?;
+                let after = client
+                    .get_account(destination)
+                    .await?
+                    .ok_or(anyhow!("destination {} was closed", destination))?;
+                Ok((token_amount(&before)?, token_amount(&after)?))
+            })
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use crate::backtest::Trade;
+use std::fmt::Write;
+
+/*
+Ranges, results and trade flows of the v2 and v3 parameter sweeps.
+*/
+
+#[derive(Clone, Debug, Default)]
+pub struct SweepRanges {
+    pub concentration: Vec<u64>,
+    pub fee_millionth: Vec<u64>,
+    pub rebate_percentage: Vec<u64>,
+    pub protocol_fee_share_thousandth: Vec<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SweepResult {
+    pub concentration: u64,
+    pub fee_millionth: u64,
+    pub rebate_percentage: u64,
+    pub protocol_fee_share_thousandth: u64,
+    /// Shortfall against the oracle price, fees included, for each of the sizes
+    /// at the first price, in basis points
+    pub slippage_x_to_y_bps: Vec<u64>,
+    pub slippage_y_to_x_bps: Vec<u64>,
+    pub lp_fee_income: u64,
+    pub pnl_vs_hold: i128,
+    /// Largest distance of X from its target over the replay: `current_x - target_x`
+    /// in v2, the net X position `deposit_x - borrow_x` in v3
+    pub max_drift_x: u128,
+}
+
+/// Deterministic trade flow alternating sides at random, `interval` seconds
+/// apart, with sizes uniform up to `max_in_x` / `max_in_y`.
+pub fn synthetic_trades(
+    count: usize,
+    seed: u64,
+    start: i64,
+    interval: i64,
+    max_in_x: u64,
+    max_in_y: u64,
+) -> Vec<Trade> {
+    // xorshift64, seed 0 would stay 0
+    let mut state = seed.max(1);
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    (0..count)
+        .map(|i| {
+            let is_x_to_y = next() % 2 == 0;
+            let max_in = if is_x_to_y { max_in_x } else { max_in_y };
+            Trade {
+                timestamp: start + i as i64 * interval,
+                is_x_to_y,
+                in_amount: next() % max_in.max(1) + 1,
+            }
+        })
+        .collect()
+}
+
+/// Shortfall of `out` against `ideal_out`, in basis points.
+pub fn slippage_bps(out: u64, ideal_out: u128) -> u64 {
+    if ideal_out == 0 {
+        return 0;
+    }
+    (ideal_out.saturating_sub(out as u128) * 10_000 / ideal_out) as u64
+}
+
+/// One row per result, slippages joined with `/` in the order of the sizes.
+pub fn format_table(results: &[SweepResult]) -> String {
+    let mut table = String::from(
+        "concentration fee_millionth rebate_percentage protocol_fee_share_thousandth \
+         slippage_x_to_y_bps slippage_y_to_x_bps lp_fee_income pnl_vs_hold max_drift_x\n",
+    );
+    let join = |bps: &[u64]| {
+        bps.iter()
+            .map(|bps| bps.to_string())
+            .collect::<Vec<_>>()
+            .join("/")
+    };
+    for result in results {
+        writeln!(
+            table,
+            "{} {} {} {} {} {} {} {} {}",
+            result.concentration,
+            result.fee_millionth,
+            result.rebate_percentage,
+            result.protocol_fee_share_thousandth,
+            join(&result.slippage_x_to_y_bps),
+            join(&result.slippage_y_to_x_bps),
+            result.lp_fee_income,
+            result.pnl_vs_hold,
+            result.max_drift_x
+        )
+        .unwrap();
+    }
+    table
+}
@@ -0,0 +1,64 @@
+use anchor_lang::prelude::ProgramError;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022;
+use solana_sdk::pubkey::Pubkey;
+use spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+
+/// Mint fields needed for quoting, decoded from either token program.
+/// `supply` is the supply at the time the mint was fetched.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MintInfo {
+    pub token_program: Pubkey,
+    pub decimals: u8,
+    pub freeze_authority: Option<Pubkey>,
+    pub supply: u64,
+    pub transfer_fee_config: Option<TransferFeeConfig>,
+}
+
+impl MintInfo {
+    /// Decodes the `data` of a mint account owned by `owner`.
+    pub fn unpack(data: &[u8], owner: &Pubkey) -> Result<Self, ProgramError> {
+        if owner.eq(&spl_token::ID) {
+            let mint = spl_token::state::Mint::unpack(data)?;
+            Ok(Self {
+                token_program: spl_token::ID,
+                decimals: mint.decimals,
+                freeze_authority: mint.freeze_authority.into(),
+                supply: mint.supply,
+                transfer_fee_config: None,
+            })
+        } else if owner.eq(&spl_token_2022::ID) {
+            let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(data)?;
+            Ok(Self {
+                token_program: spl_token_2022::ID,
+                decimals: mint.base.decimals,
+                freeze_authority: mint.base.freeze_authority.into(),
+                supply: mint.base.supply,
+                transfer_fee_config: mint.get_extension::<TransferFeeConfig>().ok().copied(),
+            })
+        } else {
+            Err(ProgramError::IncorrectProgramId)
+        }
+    }
+
+    /// Amount withheld by the transfer fee extension when moving `amount` at `epoch`.
+    pub fn transfer_fee(&self, epoch: u64, amount: u64) -> u64 {
+        self.transfer_fee_config
+            .and_then(|config| config.calculate_epoch_fee(epoch, amount))
+            .unwrap_or(0)
+    }
+}
+
+/// Balance in the `data` of a token account owned by `owner`, either token program.
+pub fn unpack_token_amount(data: &[u8], owner: &Pubkey) -> Result<u64, ProgramError> {
+    if owner.eq(&spl_token::ID) {
+        Ok(spl_token::state::Account::unpack(data)?.amount)
+    } else if owner.eq(&spl_token_2022::ID) {
+        let token_account = StateWithExtensions::<spl_token_2022::state::Account>::unpack(data)?;
+        Ok(token_account.base.amount)
+    } else {
+        Err(ProgramError::IncorrectProgramId)
+    }
+}
@@ -0,0 +1,281 @@
+//! Drives any `Amm` through the lifecycle Jupiter's router puts it through,
+//! offline from recorded accounts, and checks the invariants the router
+//! relies on:
+//!
+//! - the accounts `get_accounts_to_update` asks for are enough for `update`
+//! - quotes in both directions are monotone in the input, and once a size
+//!   cannot be filled no larger one is
+//! - mints the pool does not trade are errors, not empty quotes
+//! - `get_accounts_len` is the length of the longer of the two swaps' account
+//!   metas
+//! - `clone_amm` quotes like the original
+//!
+//! ```ignore
+//! let amm: ObricV2Amm = Conformance::default().check(&keyed_account, &accounts)?;
+//! ```
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use jupiter_amm_interface::{Amm, KeyedAccount, Quote, QuoteParams, SwapParams};
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+
+pub struct Conformance {
+    /// Input amounts quoted in both directions, in increasing order
+    pub sizes: Vec<u64>,
+    /// Rounds of `get_accounts_to_update` then `update`, as the router repeats them
+    pub update_rounds: usize,
+    /// Off for adapters which keep the trait's default `get_accounts_len`
+    pub check_accounts_len: bool,
+}
+
+impl Default for Conformance {
+    fn default() -> Self {
+        Self {
+            // 1 to 10^18
+            sizes: (0..19).map(|exp| 10u64.pow(exp)).collect(),
+            update_rounds: 2,
+            check_accounts_len: true,
+        }
+    }
+}
+
+impl Conformance {
+    /// Runs every check on the pool in `keyed_account`, with `accounts` standing
+    /// in for the RPC, and returns the updated AMM.
+    pub fn check<T: Amm>(
+        &self,
+        keyed_account: &KeyedAccount,
+        accounts: &HashMap<Pubkey, Account>,
+    ) -> Result<T> {
+        let mut amm = T::from_keyed_account(keyed_account)?;
+        ensure!(
+            amm.key() == keyed_account.key,
+            "pool {} reports key {}",
+            keyed_account.key,
+            amm.key()
+        );
+        ensure!(
+            amm.program_id() == keyed_account.account.owner,
+            "pool {} reports program {} but is owned by {}",
+            amm.key(),
+            amm.program_id(),
+            keyed_account.account.owner
+        );
+        let mints = amm.get_reserve_mints();
+        ensure!(
+            mints.len() == 2 && mints[0] != mints[1],
+            "pool {} reserve mints {:?} are not a pair",
+            amm.key(),
+            mints
+        );
+
+        for _ in 0..self.update_rounds {
+            update(&mut amm, accounts)?;
+        }
+        let mut most_metas = 0;
+        for (input_mint, output_mint) in [(mints[0], mints[1]), (mints[1], mints[0])] {
+            self.check_quotes(&amm, input_mint, output_mint)?;
+            most_metas = most_metas.max(self.check_swap_metas(&amm, input_mint, output_mint)?);
+        }
+        if self.check_accounts_len {
+            ensure!(
+                most_metas == amm.get_accounts_len(),
+                "pool {} swaps take up to {} accounts, get_accounts_len says {}",
+                amm.key(),
+                most_metas,
+                amm.get_accounts_len()
+            );
+        }
+        self.check_unsupported_mint(&amm, mints[0])?;
+        self.check_clone(&amm, &mints)?;
+        Ok(amm)
+    }
+
+    /// Output never shrinks as the input grows, among the sizes the pool fills.
+    /// Sizes it cannot fill may fail or report `not_enough_liquidity`, but then
+    /// so must every larger size.
+    fn check_quotes(&self, amm: &dyn Amm, input_mint: Pubkey, output_mint: Pubkey) -> Result<()> {
+        let mut last: Option<(u64, u64)> = None;
+        let mut unfilled: Option<u64> = None;
+        for &in_amount in &self.sizes {
+            let quote = amm.quote(&QuoteParams {
+                input_mint,
+                in_amount,
+                output_mint,
+            });
+            let quote = match quote {
+                Ok(quote) if !quote.not_enough_liquidity => quote,
+                _ => {
+                    unfilled.get_or_insert(in_amount);
+                    continue;
+                }
+            };
+            if let Some(unfilled_in) = unfilled {
+                bail!(
+                    "pool {} fills {} of {} but not {}",
+                    amm.key(),
+                    in_amount,
+                    input_mint,
+                    unfilled_in
+                );
+            }
+            if let Some((last_in, last_out)) = last {
+                ensure!(
+                    quote.out_amount >= last_out,
+                    "pool {} pays {} for {} of {} but {} for {}",
+                    amm.key(),
+                    quote.out_amount,
+                    in_amount,
+                    input_mint,
+                    last_out,
+                    last_in
+                );
+            }
+            last = Some((in_amount, quote.out_amount));
+        }
+        ensure!(
+            last.is_some(),
+            "pool {} fills none of the sizes of {}",
+            amm.key(),
+            input_mint
+        );
+        Ok(())
+    }
+
+    /// Returns the number of account metas the swap takes.
+    fn check_swap_metas(
+        &self,
+        amm: &dyn Amm,
+        source_mint: Pubkey,
+        destination_mint: Pubkey,
+    ) -> Result<usize> {
+        let jupiter_program_id = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let metas = amm
+            .get_swap_and_account_metas(&SwapParams {
+                in_amount: self.sizes[0],
+                out_amount: 0,
+                source_mint,
+                destination_mint,
+                source_token_account: Pubkey::new_unique(),
+                destination_token_account: Pubkey::new_unique(),
+                token_transfer_authority: authority,
+                open_order_address: None,
+                quote_mint_to_referrer: None,
+                jupiter_program_id: &jupiter_program_id,
+                missing_dynamic_accounts_as_default: false,
+            })
+            .with_context(|| format!("pool {} swap from {}", amm.key(), source_mint))?
+            .account_metas;
+        ensure!(
+            metas
+                .iter()
+                .any(|meta| meta.pubkey == authority && meta.is_signer),
+            "pool {} swap from {} does not sign with the transfer authority",
+            amm.key(),
+            source_mint
+        );
+        Ok(metas.len())
+    }
+
+    fn check_unsupported_mint(&self, amm: &dyn Amm, traded_mint: Pubkey) -> Result<()> {
+        let other_mint = Pubkey::new_unique();
+        for &in_amount in &self.sizes {
+            let quote = amm.quote(&QuoteParams {
+                input_mint: other_mint,
+                in_amount,
+                output_mint: traded_mint,
+            });
+            ensure!(
+                quote.is_err(),
+                "pool {} quotes {} of a mint it does not trade",
+                amm.key(),
+                in_amount
+            );
+        }
+
+        let jupiter_program_id = Pubkey::new_unique();
+        let metas = amm.get_swap_and_account_metas(&SwapParams {
+            in_amount: self.sizes[0],
+            out_amount: 0,
+            source_mint: other_mint,
+            destination_mint: traded_mint,
+            source_token_account: Pubkey::new_unique(),
+            destination_token_account: Pubkey::new_unique(),
+            token_transfer_authority: Pubkey::new_unique(),
+            open_order_address: None,
+            quote_mint_to_referrer: None,
+            jupiter_program_id: &jupiter_program_id,
+            missing_dynamic_accounts_as_default: false,
+        });
+        ensure!(
+            metas.is_err(),
+            "pool {} builds a swap from a mint it does not trade",
+            amm.key()
+        );
+        Ok(())
+    }
+
+    fn check_clone(&self, amm: &dyn Amm, mints: &[Pubkey]) -> Result<()> {
+        let clone = amm.clone_amm();
+        ensure!(
+            clone.key() == amm.key()
+                && clone.get_reserve_mints() == mints
+                && clone.get_accounts_to_update() == amm.get_accounts_to_update(),
+            "clone of pool {} is another pool",
+            amm.key()
+        );
+        for (input_mint, output_mint) in [(mints[0], mints[1]), (mints[1], mints[0])] {
+            for &in_amount in &self.sizes {
+                let quote_params = QuoteParams {
+                    input_mint,
+                    in_amount,
+                    output_mint,
+                };
+                let (quote, cloned) = (amm.quote(&quote_params), clone.quote(&quote_params));
+                let same = match (&quote, &cloned) {
+                    (Ok(quote), Ok(cloned)) => same_quote(quote, cloned),
+                    (Err(_), Err(_)) => true,
+                    _ => false,
+                };
+                if !same {
+                    bail!(
+                        "clone of pool {} quotes {} of {} as {:?}, the original as {:?}",
+                        amm.key(),
+                        in_amount,
+                        input_mint,
+                        cloned,
+                        quote
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One `get_accounts_to_update` and `update` round, passing only the accounts
+/// asked for.
+fn update(amm: &mut dyn Amm, accounts: &HashMap<Pubkey, Account>) -> Result<()> {
+    let mut accounts_map = HashMap::new();
+    for key in amm.get_accounts_to_update() {
+        let account = accounts.get(&key).ok_or_else(|| {
+            anyhow!(
+                "pool {} asks for {}, missing from the fixture",
+                amm.key(),
+                key
+            )
+        })?;
+        accounts_map.insert(key, account.clone());
+    }
+    amm.update(&accounts_map)
+        .with_context(|| format!("pool {} update from the accounts it asked for", amm.key()))
+}
+
+fn same_quote(a: &Quote, b: &Quote) -> bool {
+    a.out_amount == b.out_amount
+        && a.fee_amount == b.fee_amount
+        && a.fee_mint == b.fee_mint
+        && a.not_enough_liquidity == b.not_enough_liquidity
+}
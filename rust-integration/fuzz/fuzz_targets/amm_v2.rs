@@ -0,0 +1,26 @@
+#![no_main]
+
+use jupiter_amm_interface::Amm;
+use libfuzzer_sys::fuzz_target;
+use obric_fuzz::{fixtures, AmmInput, MAX_ROUNDS};
+use obric_v2_sdk::obric_v2_amm::ObricV2Amm;
+use obric_v2_sdk::token::MintCache;
+
+fuzz_target!(|input: AmmInput| {
+    let (mut keyed_account, mut accounts) = fixtures::v2_pool();
+    for mutation in &input.pool {
+        mutation.apply(&mut keyed_account.account.data);
+    }
+    let Ok(amm) = ObricV2Amm::from_keyed_account(&keyed_account) else {
+        return;
+    };
+    // the global cache would carry mints over from earlier inputs
+    let mut amm = amm.with_mint_cache(MintCache::default());
+    for changes in input.rounds.iter().take(MAX_ROUNDS) {
+        for change in changes {
+            change.apply(&mut accounts);
+        }
+        let _ = amm.get_accounts_to_update();
+        let _ = amm.update(&accounts);
+    }
+});
@@ -0,0 +1,23 @@
+#![no_main]
+
+use jupiter_amm_interface::Amm;
+use libfuzzer_sys::fuzz_target;
+use obric_fuzz::{fixtures, AmmInput, MAX_ROUNDS};
+use obric_v3_sdk::obric_v3_amm::ObricV3Amm;
+
+fuzz_target!(|input: AmmInput| {
+    let (mut keyed_account, mut accounts) = fixtures::v3_pool();
+    for mutation in &input.pool {
+        mutation.apply(&mut keyed_account.account.data);
+    }
+    let Ok(mut amm) = ObricV3Amm::from_keyed_account(&keyed_account) else {
+        return;
+    };
+    for changes in input.rounds.iter().take(MAX_ROUNDS) {
+        for change in changes {
+            change.apply(&mut accounts);
+        }
+        let _ = amm.get_accounts_to_update();
+        let _ = amm.update(&accounts);
+    }
+});
@@ -0,0 +1,14 @@
+#![no_main]
+
+use anchor_lang::AccountDeserialize;
+use libfuzzer_sys::fuzz_target;
+use obric_fuzz::{fixtures, Data};
+use obric_solana_v3::state::{Obligation, Reserve};
+
+fuzz_target!(|data: Data| {
+    let data = data.bytes(&fixtures::larix_accounts());
+    if let Ok(reserve) = Reserve::try_deserialize_unchecked(&mut &data[..]) {
+        let _ = reserve.get_ctoken_exchange_rate();
+    }
+    let _ = Obligation::try_deserialize_unchecked(&mut &data[..]);
+});
@@ -0,0 +1,17 @@
+#![no_main]
+
+use anchor_lang::AccountDeserialize;
+use libfuzzer_sys::fuzz_target;
+use obric_fuzz::{fixtures, Data};
+
+fuzz_target!(|data: Data| {
+    let data = data.bytes(&fixtures::price_accounts());
+    if let Ok(feed) = obric_solana::state::PriceFeed::try_deserialize_unchecked(&mut &data[..]) {
+        let _ = feed.price_normalized();
+        let _ = obric_solana::state::PriceFeed::normalized_pair(&feed, &feed);
+    }
+    if let Ok(feed) = obric_solana_v3::state::PriceFeed::try_deserialize_unchecked(&mut &data[..]) {
+        let _ = feed.price_normalized();
+        let _ = obric_solana_v3::state::PriceFeed::normalized_pair(&feed, &feed);
+    }
+});
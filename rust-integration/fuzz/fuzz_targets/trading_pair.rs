@@ -0,0 +1,20 @@
+#![no_main]
+
+use anchor_lang::{AccountDeserialize, AccountSerialize};
+use libfuzzer_sys::fuzz_target;
+use obric_fuzz::{fixtures, Data};
+
+fuzz_target!(|data: Data| {
+    let data = data.bytes(&fixtures::trading_pairs());
+    // both versions share the discriminator, a pair decodes as whichever fits
+    if let Ok(pair) = obric_solana::state::SSTradingPair::try_deserialize(&mut &data[..]) {
+        let mut serialized = vec![];
+        pair.try_serialize(&mut serialized).unwrap();
+        assert_eq!(serialized, data[..serialized.len()]);
+    }
+    if let Ok(pair) = obric_solana_v3::state::SSTradingPair::try_deserialize(&mut &data[..]) {
+        let mut serialized = vec![];
+        pair.try_serialize(&mut serialized).unwrap();
+        assert_eq!(serialized, data[..serialized.len()]);
+    }
+});
@@ -0,0 +1,182 @@
+//! The synthetic accounts of `python/tests/fixtures`, written by
+//! `make_fixtures.py`, and the accounts around them the SDKs read; the Larix
+//! reserves are `Reserve::default()`. The decoders also start from the mainnet
+//! accounts recorded to `recorded/<target>/` by the SDKs'
+//! `test_record_fuzz_fixtures`.
+
+use anchor_lang::{AccountDeserialize, AccountSerialize};
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022;
+use jupiter_amm_interface::KeyedAccount;
+use larix_lending::state::obligation::Obligation;
+use larix_lending::state::reserve::Reserve;
+use larix_lending::state::PROGRAM_VERSION;
+use obric_sdk_common::fixtures::{clock_account, mint_account, token_account};
+use solana_sdk::account::Account;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::sysvar;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+const V2_POOL: &[u8] = include_bytes!("../../python/tests/fixtures/v2_pool.bin");
+const V3_POOL: &[u8] = include_bytes!("../../python/tests/fixtures/v3_pool.bin");
+const PRICE_X: &[u8] = include_bytes!("../../python/tests/fixtures/price_x.bin");
+const PRICE_Y: &[u8] = include_bytes!("../../python/tests/fixtures/price_y.bin");
+
+/// The key of the pool account, the fixtures use `[i; 32]` for theirs.
+pub const POOL_KEY: Pubkey = Pubkey::new_from_array([11; 32]);
+
+pub fn price_accounts() -> Vec<Vec<u8>> {
+    static RECORDED: OnceLock<Vec<Vec<u8>>> = OnceLock::new();
+    let mut accounts = vec![PRICE_X.to_vec(), PRICE_Y.to_vec()];
+    accounts.extend_from_slice(recorded(&RECORDED, "price_feed"));
+    accounts
+}
+
+pub fn trading_pairs() -> Vec<Vec<u8>> {
+    static RECORDED: OnceLock<Vec<Vec<u8>>> = OnceLock::new();
+    let mut accounts = vec![V2_POOL.to_vec(), V3_POOL.to_vec(), v3_pool_data()];
+    accounts.extend_from_slice(recorded(&RECORDED, "trading_pair"));
+    accounts
+}
+
+/// An empty reserve and obligation of the current version, then the recorded ones.
+pub fn larix_accounts() -> Vec<Vec<u8>> {
+    static RECORDED: OnceLock<Vec<Vec<u8>>> = OnceLock::new();
+    let mut reserve = vec![0; Reserve::LEN];
+    Reserve::pack(
+        Reserve {
+            version: PROGRAM_VERSION,
+            ..Reserve::default()
+        },
+        &mut reserve,
+    )
+    .unwrap();
+    let mut obligation = vec![0; Obligation::LEN];
+    Obligation::pack(
+        Obligation {
+            version: PROGRAM_VERSION,
+            ..Obligation::default()
+        },
+        &mut obligation,
+    )
+    .unwrap();
+    let mut accounts = vec![reserve, obligation];
+    accounts.extend_from_slice(recorded(&RECORDED, "larix"));
+    accounts
+}
+
+/// The accounts in `recorded/<target>/`, read once per run, in name order.
+fn recorded(cell: &'static OnceLock<Vec<Vec<u8>>>, target: &str) -> &'static [Vec<u8>] {
+    cell.get_or_init(|| {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("recorded")
+            .join(target);
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return vec![];
+        };
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .collect();
+        paths.sort();
+        paths
+            .iter()
+            .filter_map(|path| std::fs::read(path).ok())
+            .collect()
+    })
+}
+
+/// Owners an account can be handed to.
+pub fn owners() -> [Pubkey; 7] {
+    [
+        Pubkey::default(),
+        spl_token::ID,
+        spl_token_2022::ID,
+        obric_solana::state::price_feed::ID,
+        larix_lending::id(),
+        obric_v2_sdk::constants::PROGRAM_ID,
+        obric_v3_sdk::constants::PROGRAM_ID,
+    ]
+}
+
+/// The v2 fixture pool, balanced at 1000 X and 150000 Y, with its reserves,
+/// mints, prices and the clock.
+pub fn v2_pool() -> (KeyedAccount, HashMap<Pubkey, Account>) {
+    let state = obric_solana::state::SSTradingPair::try_deserialize(&mut &V2_POOL[..]).unwrap();
+    let mut accounts = HashMap::new();
+    accounts.insert(state.reserve_x, token_account(state.mint_x, 1_000_000_000));
+    accounts.insert(
+        state.reserve_y,
+        token_account(state.mint_y, 150_000_000_000),
+    );
+    accounts.insert(state.mint_x, mint_account(6));
+    accounts.insert(state.mint_y, mint_account(6));
+    accounts.insert(state.x_price_feed_id, price_account(PRICE_X));
+    accounts.insert(state.y_price_feed_id, price_account(PRICE_Y));
+    accounts.insert(sysvar::clock::ID, clock_account(0));
+    (
+        keyed_account(V2_POOL.to_vec(), obric_v2_sdk::constants::PROGRAM_ID),
+        accounts,
+    )
+}
+
+/// The v3 fixture pool with its prices, Larix reserves and the clock, and the
+/// pool account itself, which `update` reads back.
+pub fn v3_pool() -> (KeyedAccount, HashMap<Pubkey, Account>) {
+    use obric_solana_v3::consts::mint_to_larix_reserve;
+
+    let data = v3_pool_data();
+    let state = obric_solana_v3::state::SSTradingPair::try_deserialize(&mut &data[..]).unwrap();
+    let keyed_account = keyed_account(data, obric_v3_sdk::constants::PROGRAM_ID);
+    let mut accounts = HashMap::new();
+    accounts.insert(keyed_account.key, keyed_account.account.clone());
+    accounts.insert(state.x_price_feed_id, price_account(PRICE_X));
+    accounts.insert(state.y_price_feed_id, price_account(PRICE_Y));
+    accounts.insert(sysvar::clock::ID, clock_account(0));
+    let reserve = &larix_accounts()[0];
+    for mint in [state.mint_x, state.mint_y] {
+        let account = Account {
+            data: reserve.clone(),
+            owner: larix_lending::id(),
+            ..Account::default()
+        };
+        accounts.insert(mint_to_larix_reserve(&mint).unwrap(), account);
+    }
+    (keyed_account, accounts)
+}
+
+/// The v3 fixture trades placeholder mints. Only mints with a Larix reserve
+/// get past `update`, so this one trades SOL and USDC, which its decimals
+/// already match.
+fn v3_pool_data() -> Vec<u8> {
+    use obric_solana_v3::consts::mints;
+
+    let mut state =
+        obric_solana_v3::state::SSTradingPair::try_deserialize(&mut &V3_POOL[..]).unwrap();
+    state.mint_x = mints::sol::ID;
+    state.mint_y = mints::usdc::ID;
+    let mut data = vec![];
+    state.try_serialize(&mut data).unwrap();
+    data
+}
+
+fn keyed_account(data: Vec<u8>, owner: Pubkey) -> KeyedAccount {
+    KeyedAccount {
+        key: POOL_KEY,
+        account: Account {
+            data,
+            owner,
+            ..Account::default()
+        },
+        params: None,
+    }
+}
+
+fn price_account(data: &[u8]) -> Account {
+    Account {
+        data: data.to_vec(),
+        owner: obric_solana::state::price_feed::ID,
+        ..Account::default()
+    }
+}
@@ -0,0 +1,128 @@
+//! Inputs shared by the fuzz targets. Targets start from the valid accounts
+//! in `fixtures`, synthetic ones rather than recorded: the pools and prices
+//! `python/tests/make_fixtures.py` writes, default Larix reserves and
+//! obligations, and token accounts, mints and the clock built here. The
+//! fuzzer edits them, so runs explore around valid accounts as well as raw
+//! bytes. Edits are bounded by the input length: an input cannot grow account
+//! data by more than 255 bytes per edit.
+
+use arbitrary::Arbitrary;
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+
+pub mod fixtures;
+
+/// An edit to account data. Writes wrap around the data.
+#[derive(Arbitrary, Debug)]
+pub enum Mutation {
+    Write {
+        offset: u16,
+        bytes: Vec<u8>,
+    },
+    Truncate(u16),
+    /// Appends zeros
+    Extend(u8),
+}
+
+impl Mutation {
+    pub fn apply(&self, data: &mut Vec<u8>) {
+        match self {
+            Mutation::Write { offset, bytes } => {
+                if data.is_empty() {
+                    return;
+                }
+                let start = *offset as usize % data.len();
+                let end = data.len().min(start + bytes.len());
+                data[start..end].copy_from_slice(&bytes[..end - start]);
+            }
+            Mutation::Truncate(len) => data.truncate(*len as usize),
+            Mutation::Extend(len) => data.resize(data.len() + *len as usize, 0),
+        }
+    }
+}
+
+/// Data for a decoder: raw bytes, or one of its fixtures edited.
+#[derive(Arbitrary, Debug)]
+pub enum Data {
+    Raw(Vec<u8>),
+    Fixture { index: u8, mutations: Vec<Mutation> },
+}
+
+impl Data {
+    pub fn bytes(&self, fixtures: &[Vec<u8>]) -> Vec<u8> {
+        match self {
+            Data::Raw(bytes) => bytes.clone(),
+            Data::Fixture { index, mutations } => {
+                let mut data = fixtures[*index as usize % fixtures.len()].clone();
+                for mutation in mutations {
+                    mutation.apply(&mut data);
+                }
+                data
+            }
+        }
+    }
+}
+
+/// A change to a pool's account map. Accounts are picked by their position
+/// among the sorted keys, owners from `fixtures::owners`.
+#[derive(Arbitrary, Debug)]
+pub enum AccountChange {
+    Edit {
+        account: u8,
+        mutations: Vec<Mutation>,
+    },
+    Replace {
+        account: u8,
+        data: Vec<u8>,
+    },
+    Owner {
+        account: u8,
+        owner: u8,
+    },
+    Remove {
+        account: u8,
+    },
+}
+
+impl AccountChange {
+    pub fn apply(&self, accounts: &mut HashMap<Pubkey, Account>) {
+        let mut keys: Vec<Pubkey> = accounts.keys().copied().collect();
+        if keys.is_empty() {
+            return;
+        }
+        keys.sort();
+        let pick = |account: &u8| keys[*account as usize % keys.len()];
+        match self {
+            AccountChange::Edit { account, mutations } => {
+                let data = &mut accounts.get_mut(&pick(account)).unwrap().data;
+                for mutation in mutations {
+                    mutation.apply(data);
+                }
+            }
+            AccountChange::Replace { account, data } => {
+                accounts.get_mut(&pick(account)).unwrap().data = data.clone();
+            }
+            AccountChange::Owner { account, owner } => {
+                let owners = fixtures::owners();
+                accounts.get_mut(&pick(account)).unwrap().owner =
+                    owners[*owner as usize % owners.len()];
+            }
+            AccountChange::Remove { account } => {
+                accounts.remove(&pick(account));
+            }
+        }
+    }
+}
+
+/// A pool's lifecycle in the router: its account edited before
+/// `from_keyed_account`, then rounds of changes to the map each followed by
+/// `get_accounts_to_update` and `update`.
+#[derive(Arbitrary, Debug)]
+pub struct AmmInput {
+    pub pool: Vec<Mutation>,
+    pub rounds: Vec<Vec<AccountChange>>,
+}
+
+/// Rounds past this many add nothing a shorter input does not cover.
+pub const MAX_ROUNDS: usize = 4;
@@ -0,0 +1,66 @@
+use core::fmt;
+
+/// Why the curve math could not produce a result. The accounts crates map
+/// each to the `ObricError` of the same meaning.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MathError {
+    /// A value does not fit its type, or a division by zero
+    Overflow,
+    /// An oracle price of zero or below
+    NegativePrice,
+    /// An oracle price that cannot be scaled to the common exponent
+    PriceOutOfRange,
+    /// The quote needs more X than the pool can pay out
+    InsufficientActiveX,
+    /// The quote needs more Y than the pool can pay out
+    InsufficientActiveY,
+}
+
+impl fmt::Display for MathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            MathError::Overflow => "numeric overflow",
+            MathError::NegativePrice => "price is not positive",
+            MathError::PriceOutOfRange => "price cannot be normalized",
+            MathError::InsufficientActiveX => "insufficient active X",
+            MathError::InsufficientActiveY => "insufficient active Y",
+        };
+        f.write_str(message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MathError {}
+
+/// One of the two prices of a pair.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PriceSide {
+    X,
+    Y,
+}
+
+/// Why `normalize_price_pair` could not use a pair, and which price it could not use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PriceError {
+    pub side: PriceSide,
+    pub source: MathError,
+}
+
+impl From<PriceError> for MathError {
+    fn from(err: PriceError) -> Self {
+        err.source
+    }
+}
+
+impl fmt::Display for PriceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = match self.side {
+            PriceSide::X => "X",
+            PriceSide::Y => "Y",
+        };
+        write!(f, "price of {side}: {}", self.source)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PriceError {}
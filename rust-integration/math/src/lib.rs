@@ -0,0 +1,22 @@
+#![no_std]
+
+/*!
+The Obric curve math without Anchor or Solana: oracle price normalization, price multipliers,
+target computation, v2 and v3 quotes and their fee and rebate split.
+
+`obric-solana` and `obric-solana-v3` run their `SSTradingPair` methods on it and re-export its
+types, so on-chain swaps, the SDKs and anything built on this crate alone quote the same. The
+crate is `no_std`; the `std` feature implements `std::error::Error` for its errors.
+*/
+
+#[cfg(feature = "std")]
+extern crate std;
+
+pub mod error;
+pub mod price;
+pub mod quote;
+pub mod units;
+pub mod v2;
+pub mod v3;
+
+pub use error::{MathError, PriceError, PriceSide};
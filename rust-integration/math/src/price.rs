@@ -0,0 +1,84 @@
+use crate::error::{MathError, PriceError, PriceSide};
+use crate::units::{Mult, NormalizedPrice, TokenX, TokenY};
+
+/// Coarsest exponent prices are normalized to, the one of `price_normalized`.
+pub const NORMALIZED_EXPO: i32 = -3;
+/// The smaller of a pair of normalized prices is scaled up to at least this,
+/// three significant digits, when the feeds are precise enough.
+pub const MIN_NORMALIZED_PRICE: u64 = 1_000;
+
+/// Scales two Pyth prices, given as `(price, expo)`, to one common exponent
+/// and returns them with it. The exponent is `NORMALIZED_EXPO`, lowered until
+/// the smaller price reaches `MIN_NORMALIZED_PRICE` but never below the finer
+/// of the two feeds, so low-priced tokens do not round to 0. Only the ratio of
+/// the two prices reaches the curve, so the exponent does not change quotes.
+/// The error names the price that is not positive or cannot be scaled, X when
+/// both are.
+pub fn normalize_price_pair(
+    price_x: (i64, i32),
+    price_y: (i64, i32),
+) -> Result<(NormalizedPrice<TokenX>, NormalizedPrice<TokenY>, i32), PriceError> {
+    let scale = |(price, expo): (i64, i32), to: i32| -> Result<u64, MathError> {
+        // feeds can carry any exponent, i64 keeps the difference in range
+        let shift = u32::try_from((expo as i64 - to as i64).unsigned_abs())
+            .map_err(|_| MathError::PriceOutOfRange)?;
+        let factor = 10i128
+            .checked_pow(shift)
+            .ok_or(MathError::PriceOutOfRange)?;
+        let scaled = if expo >= to {
+            (price as i128)
+                .checked_mul(factor)
+                .ok_or(MathError::PriceOutOfRange)?
+        } else {
+            price as i128 / factor
+        };
+        u64::try_from(scaled).map_err(|_| match scaled < 0 {
+            true => MathError::NegativePrice,
+            false => MathError::PriceOutOfRange,
+        })
+    };
+    let finest = price_x.1.min(price_y.1).min(NORMALIZED_EXPO);
+    let mut expo = NORMALIZED_EXPO;
+    loop {
+        let x = scale(price_x, expo).map_err(|source| PriceError {
+            side: PriceSide::X,
+            source,
+        })?;
+        let y = scale(price_y, expo).map_err(|source| PriceError {
+            side: PriceSide::Y,
+            source,
+        })?;
+        if x.min(y) >= MIN_NORMALIZED_PRICE || expo <= finest {
+            let zero = match (x, y) {
+                (0, _) => Some(PriceSide::X),
+                (_, 0) => Some(PriceSide::Y),
+                _ => None,
+            };
+            if let Some(side) = zero {
+                return Err(PriceError {
+                    side,
+                    source: MathError::NegativePrice,
+                });
+            }
+            return Ok((NormalizedPrice::new(x), NormalizedPrice::new(y), expo));
+        }
+        expo -= 1;
+    }
+}
+
+/// `mult_x` and `mult_y` for a pair of normalized prices: the side with fewer
+/// decimals is scaled up by 10^d, d the difference in decimals.
+pub fn mults(
+    price_x: NormalizedPrice<TokenX>,
+    price_y: NormalizedPrice<TokenY>,
+    x_decimals: u8,
+    y_decimals: u8,
+) -> Result<(Mult<TokenX>, Mult<TokenY>), MathError> {
+    let mult_x = price_x
+        .to_mult(x_decimals, y_decimals)
+        .ok_or(MathError::Overflow)?;
+    let mult_y = price_y
+        .to_mult(y_decimals, x_decimals)
+        .ok_or(MathError::Overflow)?;
+    Ok((mult_x, mult_y))
+}
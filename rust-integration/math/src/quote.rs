@@ -0,0 +1,77 @@
+pub const MILLION: u64 = 1000000;
+
+/// How a quote splits between the user, the fee and its rebate, the protocol and the LPs.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QuoteBreakdown {
+    pub output_before_fee: u64,
+    pub fee_before_rebate: u64,
+    pub rebate: u64,
+    pub protocol_fee: u64,
+    pub lp_fee: u64,
+    pub output_after_fee: u64,
+    /// Charged fee (protocol + LP) in basis points of `output_before_fee`
+    pub fee_bps: u64,
+    /// Whether the trade moves the pool toward its target
+    pub rebalancing: bool,
+}
+
+pub(crate) fn fee_bps(fee: u64, output_before_fee: u64) -> u64 {
+    if output_before_fee == 0 {
+        0
+    } else {
+        (fee as u128 * 10_000 / output_before_fee as u128) as u64
+    }
+}
+
+/// Smallest input whose quote pays at least `out_amount`, with that quote.
+/// `quote` returns the quote for an input, `None` when the reserves cannot pay
+/// it, and `output` what it pays. Assumes the output grows with the input, so
+/// the inputs paying too little, those that fill and those the reserves cannot
+/// pay come in that order. Doubles from 1 while the quote pays too little; the
+/// inputs that fill can be fewer than one doubling, so a first input that
+/// cannot be paid is bisected down from before giving up. Then bisects for
+/// the smallest input that fills.
+pub fn exact_in<Q>(
+    out_amount: u64,
+    quote: impl Fn(u64) -> Option<Q>,
+    output: impl Fn(&Q) -> u64,
+) -> Option<(u64, Q)> {
+    let fills = |quote: &Q| output(quote) >= out_amount;
+    // `low` pays too little, 0 paying nothing
+    let (mut low, mut high) = (0, 1);
+    let unpaid = loop {
+        match quote(high) {
+            Some(quote) if fills(&quote) => break None,
+            Some(_) if high == u64::MAX => return None,
+            Some(_) => {
+                low = high;
+                high = high.saturating_mul(2);
+            }
+            None => break Some(high),
+        }
+    };
+    // an input between `low` and the first one the reserves cannot pay
+    if let Some(mut unpaid) = unpaid {
+        high = loop {
+            if low + 1 >= unpaid {
+                return None;
+            }
+            let mid = low + (unpaid - low) / 2;
+            match quote(mid) {
+                Some(quote) if fills(&quote) => break mid,
+                Some(_) => low = mid,
+                None => unpaid = mid,
+            }
+        };
+    }
+    // `high` fills
+    while low + 1 < high {
+        let mid = low + (high - low) / 2;
+        match quote(mid) {
+            Some(quote) if fills(&quote) => high = mid,
+            _ => low = mid,
+        }
+    }
+    quote(high).map(|quote| (high, quote))
+}
@@ -0,0 +1,221 @@
+use core::fmt;
+use core::marker::PhantomData;
+use num_integer::Roots;
+
+/*
+Units of the curve math, so X-units cannot meet Y-units and raw amounts cannot meet curve-space
+ones by accident. Each is a bare integer underneath; accounts keep their plain fields and convert
+at the edges (`SSTradingPair::mult_x`, `invariant`, ...).
+
+- `Amount<T>`: raw token amount of side `T`, in the mint's smallest unit
+- `NormalizedPrice<T>`: oracle price of side `T` at the pair's common exponent
+- `Mult<T>`: value of one raw unit of `T`, the price adjusted for decimals (`mult_x`, `mult_y`)
+- `Value`: an amount times its `Mult`, comparable across sides
+- `CurveAmount<T>`: a coordinate on curve-K, concentrated and shifted from the raw reserves
+- `Invariant`: `big_k`, the product of the two curve coordinates
+*/
+
+/// A side of a pair, `TokenX` or `TokenY`.
+pub trait Side: Copy + fmt::Debug + Default + PartialEq + Eq + PartialOrd + Ord {
+    type Other: Side<Other = Self>;
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TokenX;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TokenY;
+
+impl Side for TokenX {
+    type Other = TokenY;
+}
+
+impl Side for TokenY {
+    type Other = TokenX;
+}
+
+/// Raw amount of the mint on side `T`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Amount<T>(u64, PhantomData<T>);
+
+/// Oracle price of side `T`, scaled to the exponent `normalize_price_pair` picked for the pair.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NormalizedPrice<T>(u64, PhantomData<T>);
+
+/// Value of one raw unit of side `T`: its price, times 10^d when `T` has d fewer decimals than
+/// the other side.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Mult<T>(u64, PhantomData<T>);
+
+/// Coordinate of side `T` on curve-K.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CurveAmount<T>(u128, PhantomData<T>);
+
+/// An amount of either side valued at its `Mult`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Value(u128);
+
+/// Product of the X and Y coordinates on curve-K.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Invariant(u128);
+
+impl<T: Side> Amount<T> {
+    pub const ZERO: Self = Self(0, PhantomData);
+
+    pub const fn new(amount: u64) -> Self {
+        Self(amount, PhantomData)
+    }
+
+    pub const fn get(self) -> u64 {
+        self.0
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        Some(Self::new(self.0.checked_add(other.0)?))
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        Some(Self::new(self.0.checked_sub(other.0)?))
+    }
+
+    pub fn saturating_sub(self, other: Self) -> Self {
+        Self::new(self.0.saturating_sub(other.0))
+    }
+
+    pub fn value(self, mult: Mult<T>) -> Value {
+        Value(self.0 as u128 * mult.0 as u128)
+    }
+
+    /// The same amount as a distance along curve-K.
+    pub fn to_curve(self) -> CurveAmount<T> {
+        CurveAmount::new(self.0 as u128)
+    }
+
+    /// `concentration` times the amount on curve-K.
+    pub fn concentrated(self, concentration: u64) -> CurveAmount<T> {
+        CurveAmount::new(self.0 as u128 * concentration as u128)
+    }
+}
+
+impl<T: Side> NormalizedPrice<T> {
+    pub const fn new(price: u64) -> Self {
+        Self(price, PhantomData)
+    }
+
+    pub const fn get(self) -> u64 {
+        self.0
+    }
+
+    /// The price adjusted for the decimals of both sides. `None` when it does not fit in u64.
+    pub fn to_mult(self, decimals: u8, other_decimals: u8) -> Option<Mult<T>> {
+        let scale = 10u64.checked_pow(u32::from(other_decimals.saturating_sub(decimals)))?;
+        Some(Mult::new(self.0.checked_mul(scale)?))
+    }
+}
+
+impl<T: Side> Mult<T> {
+    pub const fn new(mult: u64) -> Self {
+        Self(mult, PhantomData)
+    }
+
+    pub const fn get(self) -> u64 {
+        self.0
+    }
+}
+
+impl<T: Side> CurveAmount<T> {
+    pub const fn new(amount: u128) -> Self {
+        Self(amount, PhantomData)
+    }
+
+    pub const fn get(self) -> u128 {
+        self.0
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        Some(Self::new(self.0.checked_add(other.0)?))
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        Some(Self::new(self.0.checked_sub(other.0)?))
+    }
+
+    pub fn saturating_sub(self, other: Self) -> Self {
+        Self::new(self.0.saturating_sub(other.0))
+    }
+
+    /// Back to a raw amount, `None` when it does not fit in u64.
+    pub fn to_amount(self) -> Option<Amount<T>> {
+        Some(Amount::new(u64::try_from(self.0).ok()?))
+    }
+
+    /// Undoes `Amount::concentrated`, rounding down.
+    pub fn deconcentrated(self, concentration: u64) -> Option<Self> {
+        Some(Self::new(self.0.checked_div(concentration as u128)?))
+    }
+
+    /// The coordinate of the other side holding the same value.
+    pub fn convert(
+        self,
+        mult: Mult<T>,
+        other_mult: Mult<T::Other>,
+    ) -> Option<CurveAmount<T::Other>> {
+        let value = self.0.checked_mul(mult.0 as u128)?;
+        Some(CurveAmount::new(value.checked_div(other_mult.0 as u128)?))
+    }
+
+    /// The invariant of the curve through this coordinate and `other`.
+    pub fn invariant(self, other: CurveAmount<T::Other>) -> Option<Invariant> {
+        Some(Invariant(self.0.checked_mul(other.0)?))
+    }
+}
+
+impl Value {
+    pub const fn get(self) -> u128 {
+        self.0
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        Some(Self(self.0.checked_add(other.0)?))
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        Some(Self(self.0.checked_sub(other.0)?))
+    }
+
+    /// The amount of side `T` worth this value, rounding down. `None` when
+    /// it does not fit in u64.
+    pub fn to_amount<T: Side>(self, mult: Mult<T>) -> Option<Amount<T>> {
+        let amount = self.0.checked_div(mult.0 as u128)?;
+        Some(Amount::new(u64::try_from(amount).ok()?))
+    }
+}
+
+impl Invariant {
+    pub const fn new(big_k: u128) -> Self {
+        Self(big_k)
+    }
+
+    pub const fn get(self) -> u128 {
+        self.0
+    }
+
+    /// The coordinate of the other side on the curve, rounding down.
+    pub fn solve<T: Side>(self, coordinate: CurveAmount<T>) -> Option<CurveAmount<T::Other>> {
+        Some(CurveAmount::new(self.0.checked_div(coordinate.0)?))
+    }
+
+    /// The coordinate of side `T` where both sides hold the same value:
+    /// `sqrt(big_k * other_mult / mult)`.
+    pub fn balanced<T: Side>(
+        self,
+        mult: Mult<T>,
+        other_mult: Mult<T::Other>,
+    ) -> Option<CurveAmount<T>> {
+        let squared = self
+            .0
+            .checked_mul(other_mult.0 as u128)?
+            .checked_div(mult.0 as u128)?;
+        Some(CurveAmount::new(squared.sqrt()))
+    }
+}
@@ -0,0 +1,296 @@
+use crate::error::MathError;
+use crate::quote::{exact_in, fee_bps, QuoteBreakdown, MILLION};
+use crate::units::{Amount, CurveAmount, Invariant, Mult, TokenX, TokenY};
+
+/*
+The v2 curve: reserves are held by the pool, `big_k` and `target_x` are set by the admin and the
+prices come from the oracle on every swap. Trades toward the target get part of the fee back.
+*/
+
+/// The fields of a v2 `SSTradingPair` the curve reads.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Curve {
+    pub big_k: Invariant,
+    pub target_x: Amount<TokenX>,
+    pub mult_x: Mult<TokenX>,
+    pub mult_y: Mult<TokenY>,
+    pub fee_millionth: u64,
+    pub rebate_percentage: u64,
+    pub protocol_fee_share_thousandth: u64,
+}
+
+/// Where the reserves sit on curve-K, from which every quote at those reserves starts.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CurvePoint {
+    pub current_x: Amount<TokenX>,
+    pub current_y: Amount<TokenY>,
+    pub target_x: Amount<TokenX>,
+    pub target_y: Amount<TokenY>,
+    pub current_x_k: CurveAmount<TokenX>,
+    pub current_y_k: CurveAmount<TokenY>,
+}
+
+impl Curve {
+    /// `target_x`, and the Y that leaves the value of the reserves unchanged.
+    pub fn target_xy(
+        &self,
+        current_x: Amount<TokenX>,
+        current_y: Amount<TokenY>,
+    ) -> Result<(Amount<TokenX>, Amount<TokenY>), MathError> {
+        let value_total = current_x
+            .value(self.mult_x)
+            .checked_add(current_y.value(self.mult_y))
+            .ok_or(MathError::Overflow)?;
+
+        let target_x = self.target_x;
+        let target_y = value_total
+            .checked_sub(target_x.value(self.mult_x))
+            .and_then(|target_y_value| target_y_value.to_amount(self.mult_y))
+            .ok_or(MathError::Overflow)?;
+        Ok((target_x, target_y))
+    }
+
+    /// Targets and the current point on curve-K for the given reserves. Only
+    /// changes with the reserves, prices and `big_k`, so quotes between two
+    /// updates can share it.
+    pub fn curve_point(
+        &self,
+        current_x: Amount<TokenX>,
+        current_y: Amount<TokenY>,
+    ) -> Result<CurvePoint, MathError> {
+        let (target_x, target_y) = self.target_xy(current_x, current_y)?;
+
+        // 0. get target_x on curve-K
+        //target_x_K = sqrt(big_k / p), where p = mult_x / mult_y
+        let target_x_k = self
+            .big_k
+            .balanced(self.mult_x, self.mult_y)
+            .ok_or(MathError::Overflow)?;
+
+        // 1. find current (x, y) on curve-K
+        let current_x_k = target_x_k
+            .checked_add(current_x.to_curve())
+            .and_then(|value| value.checked_sub(target_x.to_curve()))
+            .ok_or(MathError::Overflow)?;
+        let current_y_k = self.big_k.solve(current_x_k).ok_or(MathError::Overflow)?;
+
+        Ok(CurvePoint {
+            current_x,
+            current_y,
+            target_x,
+            target_y,
+            current_x_k,
+            current_y_k,
+        })
+    }
+
+    /// Exact-in quote of `input_x` at the given reserves. A quote the reserves
+    /// cannot pay is all zeros.
+    pub fn quote_x_to_y(
+        &self,
+        input_x: Amount<TokenX>,
+        current_x: Amount<TokenX>,
+        current_y: Amount<TokenY>,
+    ) -> Result<QuoteBreakdown, MathError> {
+        if input_x == Amount::ZERO {
+            return Ok(QuoteBreakdown::default());
+        }
+        self.quote_x_to_y_at(&self.curve_point(current_x, current_y)?, input_x)
+    }
+
+    /// `quote_x_to_y` from a `curve_point` computed beforehand.
+    pub fn quote_x_to_y_at(
+        &self,
+        point: &CurvePoint,
+        input_x: Amount<TokenX>,
+    ) -> Result<QuoteBreakdown, MathError> {
+        paid_or_zero(self.paid_x_to_y(point, input_x))
+    }
+
+    /// `quote_x_to_y_at`, failing with `InsufficientActiveY` where it quotes all zeros.
+    fn paid_x_to_y(
+        &self,
+        point: &CurvePoint,
+        input_x: Amount<TokenX>,
+    ) -> Result<QuoteBreakdown, MathError> {
+        if input_x == Amount::ZERO {
+            return Ok(QuoteBreakdown::default());
+        }
+        let CurvePoint {
+            current_x,
+            current_y,
+            target_x,
+            current_x_k,
+            current_y_k,
+            ..
+        } = *point;
+
+        // 2. find new (x, y) on curve-K
+        let new_x_k = current_x_k
+            .checked_add(input_x.to_curve())
+            .ok_or(MathError::Overflow)?;
+        let new_y_k = self.big_k.solve(new_x_k).ok_or(MathError::Overflow)?;
+
+        // more than u64 is more than the reserve
+        let output_before_fee_y = current_y_k
+            .saturating_sub(new_y_k)
+            .to_amount()
+            .unwrap_or(Amount::new(u64::MAX));
+        if output_before_fee_y >= current_y {
+            return Err(MathError::InsufficientActiveY);
+        }
+        // at most 100: the part of the input below target, in percent
+        let rebate_ratio = (input_x.min(target_x.saturating_sub(current_x)).get() as u128 * 100
+            / input_x.get() as u128) as u64;
+        self.split(
+            output_before_fee_y.get(),
+            rebate_ratio,
+            current_x < target_x,
+        )
+    }
+
+    /// Exact-in quote of `input_y` at the given reserves. A quote the reserves
+    /// cannot pay is all zeros.
+    pub fn quote_y_to_x(
+        &self,
+        input_y: Amount<TokenY>,
+        current_x: Amount<TokenX>,
+        current_y: Amount<TokenY>,
+    ) -> Result<QuoteBreakdown, MathError> {
+        if input_y == Amount::ZERO {
+            return Ok(QuoteBreakdown::default());
+        }
+        self.quote_y_to_x_at(&self.curve_point(current_x, current_y)?, input_y)
+    }
+
+    /// `quote_y_to_x` from a `curve_point` computed beforehand.
+    pub fn quote_y_to_x_at(
+        &self,
+        point: &CurvePoint,
+        input_y: Amount<TokenY>,
+    ) -> Result<QuoteBreakdown, MathError> {
+        paid_or_zero(self.paid_y_to_x(point, input_y))
+    }
+
+    /// `quote_y_to_x_at`, failing with `InsufficientActiveX` where it quotes all zeros.
+    fn paid_y_to_x(
+        &self,
+        point: &CurvePoint,
+        input_y: Amount<TokenY>,
+    ) -> Result<QuoteBreakdown, MathError> {
+        if input_y == Amount::ZERO {
+            return Ok(QuoteBreakdown::default());
+        }
+        let CurvePoint {
+            current_x,
+            current_y,
+            target_y,
+            current_x_k,
+            current_y_k,
+            ..
+        } = *point;
+
+        // 2. find new (x, y) on curve-K
+        let new_y_k = current_y_k
+            .checked_add(input_y.to_curve())
+            .ok_or(MathError::Overflow)?;
+        let new_x_k = self.big_k.solve(new_y_k).ok_or(MathError::Overflow)?;
+
+        // more than u64 is more than the reserve
+        let output_before_fee_x = current_x_k
+            .saturating_sub(new_x_k)
+            .to_amount()
+            .unwrap_or(Amount::new(u64::MAX));
+        if output_before_fee_x >= current_x {
+            return Err(MathError::InsufficientActiveX);
+        }
+        // at most 100: the part of the input below target, in percent
+        let rebate_ratio = (input_y.min(target_y.saturating_sub(current_y)).get() as u128 * 100
+            / input_y.get() as u128) as u64;
+        self.split(
+            output_before_fee_x.get(),
+            rebate_ratio,
+            current_y < target_y,
+        )
+    }
+
+    /// Smallest X input paying at least `output_y` after fees, with its quote.
+    /// `None` when no input does.
+    pub fn exact_out_x_to_y(
+        &self,
+        point: &CurvePoint,
+        output_y: u64,
+    ) -> Option<(Amount<TokenX>, QuoteBreakdown)> {
+        let quote = |input_x| self.paid_x_to_y(point, Amount::new(input_x)).ok();
+        let (input_x, quote) = exact_in(output_y.max(1), quote, |q| q.output_after_fee)?;
+        Some((Amount::new(input_x), quote))
+    }
+
+    /// Smallest Y input paying at least `output_x` after fees, with its quote.
+    /// `None` when no input does.
+    pub fn exact_out_y_to_x(
+        &self,
+        point: &CurvePoint,
+        output_x: u64,
+    ) -> Option<(Amount<TokenY>, QuoteBreakdown)> {
+        let quote = |input_y| self.paid_y_to_x(point, Amount::new(input_y)).ok();
+        let (input_y, quote) = exact_in(output_x.max(1), quote, |q| q.output_after_fee)?;
+        Some((Amount::new(input_y), quote))
+    }
+
+    /// Splits the fee on `output_before_fee`: `rebate_ratio` percent of the
+    /// input moves the pool toward target and gets `rebate_percentage` of its
+    /// fee back, the protocol takes its share of the rest.
+    fn split(
+        &self,
+        output_before_fee: u64,
+        rebate_ratio: u64,
+        rebalancing: bool,
+    ) -> Result<QuoteBreakdown, MathError> {
+        let fee_before_rebate = output_before_fee
+            .checked_mul(self.fee_millionth)
+            .ok_or(MathError::Overflow)?
+            / MILLION;
+        let rebate = fee_before_rebate
+            .checked_mul(rebate_ratio)
+            .ok_or(MathError::Overflow)?
+            / 100;
+        let rebate = rebate
+            .checked_mul(self.rebate_percentage)
+            .ok_or(MathError::Overflow)?
+            / 100;
+        let fee = fee_before_rebate
+            .checked_sub(rebate)
+            .ok_or(MathError::Overflow)?;
+        let output_after_fee = output_before_fee
+            .checked_sub(fee)
+            .ok_or(MathError::Overflow)?;
+
+        let protocol_fee = fee
+            .checked_mul(self.protocol_fee_share_thousandth)
+            .ok_or(MathError::Overflow)?
+            / 1000;
+        let lp_fee = fee.checked_sub(protocol_fee).ok_or(MathError::Overflow)?;
+
+        Ok(QuoteBreakdown {
+            output_before_fee,
+            fee_before_rebate,
+            rebate,
+            protocol_fee,
+            lp_fee,
+            output_after_fee,
+            fee_bps: fee_bps(fee, output_before_fee),
+            rebalancing,
+        })
+    }
+}
+
+/// The v2 program quotes what the reserves cannot pay as all zeros.
+fn paid_or_zero(quote: Result<QuoteBreakdown, MathError>) -> Result<QuoteBreakdown, MathError> {
+    match quote {
+        Err(MathError::InsufficientActiveX | MathError::InsufficientActiveY) => {
+            Ok(QuoteBreakdown::default())
+        }
+        quote => quote,
+    }
+}
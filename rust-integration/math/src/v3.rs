@@ -0,0 +1,238 @@
+use crate::error::MathError;
+use crate::quote::{exact_in, fee_bps, QuoteBreakdown, MILLION};
+use crate::units::{Amount, CurveAmount, Invariant, Mult, TokenX, TokenY};
+
+/*
+The v3 curve: reserves are lent out, so the pool is valued from its deposits and borrows. The
+target is the net value of the pool in Y, refreshed with the prices on every swap, and there is
+no rebate.
+
+When quoting, we assume X and Y are equally priced. We adjust our output amounts by the price of X
+only after the quote has been computed.
+
+here we do not compute current_x_K or target_x_K, instead we compute:
+- current_xp_K = current_x_K * mult_x / mult_y
+- target_xp_K = target_x_K * mult_x / mult_y = target_y_K
+
+At "target"/equilibrium:
+- target_y = deposited amount of Y
+- target_y_K = deposited amount of Y * concentration
+- targeted deposit_x = targeted borrow_x = 0
+- targeted deposit_y = target_y
+*/
+
+/// The fields of a v3 `SSTradingPair` the curve reads.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Curve {
+    pub concentration: u64,
+    pub target_y: Amount<TokenY>,
+    pub deposit_x: Amount<TokenX>,
+    pub borrow_x: Amount<TokenX>,
+    pub deposit_y: Amount<TokenY>,
+    pub borrow_y: Amount<TokenY>,
+    pub mult_x: Mult<TokenX>,
+    pub mult_y: Mult<TokenY>,
+    pub fee_millionth: u64,
+    pub protocol_fee_share_thousandth: u64,
+}
+
+/// The pool values every quote starts from, as returned by `curve_point`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CurvePoint {
+    pub big_k: Invariant,
+    pub current_x_k: CurveAmount<TokenX>,
+    pub current_y_k: CurveAmount<TokenY>,
+    pub available_x: Amount<TokenX>,
+    pub available_y: Amount<TokenY>,
+}
+
+impl Curve {
+    /// Net value of the deposits and borrows in units of Y. Values are taken
+    /// in u128, so only a target that does not fit in u64 fails.
+    pub fn compute_target_y(&self) -> Result<Amount<TokenY>, MathError> {
+        let value = |x: Amount<TokenX>, y: Amount<TokenY>| {
+            x.value(self.mult_x).checked_add(y.value(self.mult_y))
+        };
+        let deposit_value = value(self.deposit_x, self.deposit_y).ok_or(MathError::Overflow)?;
+        let borrow_value = value(self.borrow_x, self.borrow_y).ok_or(MathError::Overflow)?;
+        deposit_value
+            .checked_sub(borrow_value)
+            .and_then(|net_value| net_value.to_amount(self.mult_y))
+            .ok_or(MathError::Overflow)
+    }
+
+    /// `big_k` of the curve through the target for `target_y`.
+    pub fn invariant_at_target(&self, target_y: Amount<TokenY>) -> Result<Invariant, MathError> {
+        let target_y_k = target_y.concentrated(self.concentration);
+        target_y_k
+            .convert(self.mult_y, self.mult_x)
+            .and_then(|target_x_k| target_x_k.invariant(target_y_k))
+            .ok_or(MathError::Overflow)
+    }
+
+    /// `big_k`, the current point on curve-K and what the pool can pay out of
+    /// each side. u128 throughout: `concentration * target_y * mult_y` leaves
+    /// u64 on large pools.
+    pub fn curve_point(&self) -> Result<CurvePoint, MathError> {
+        let target_y_k = self.target_y.concentrated(self.concentration);
+        let target_x_k = target_y_k
+            .convert(self.mult_y, self.mult_x)
+            .ok_or(MathError::Overflow)?;
+        let current_y_k = target_y_k
+            .checked_add(self.deposit_y.to_curve())
+            .and_then(|value| value.checked_sub(self.target_y.to_curve()))
+            .ok_or(MathError::Overflow)?;
+        let current_x_k = target_x_k
+            .checked_add(self.deposit_x.to_curve())
+            .and_then(|value| value.checked_sub(self.borrow_x.to_curve()))
+            .ok_or(MathError::Overflow)?;
+        let big_k = current_x_k
+            .invariant(current_y_k)
+            .ok_or(MathError::Overflow)?;
+
+        let temp = target_x_k
+            .deconcentrated(self.concentration)
+            .and_then(|value| value.checked_add(self.deposit_x.to_curve()))
+            .ok_or(MathError::Overflow)?;
+        let available_x = temp
+            .saturating_sub(self.borrow_x.to_curve())
+            .to_amount()
+            .ok_or(MathError::Overflow)?;
+
+        Ok(CurvePoint {
+            big_k,
+            current_x_k,
+            current_y_k,
+            available_x,
+            available_y: self.deposit_y,
+        })
+    }
+
+    /// Exact-in quote of `input_x`. v3 pays no rebate, `fee_before_rebate` is
+    /// the charged fee.
+    pub fn quote_x_to_y(&self, input_x: Amount<TokenX>) -> Result<QuoteBreakdown, MathError> {
+        self.quote_x_to_y_at(&self.curve_point()?, input_x)
+    }
+
+    /// `quote_x_to_y` from a `curve_point` computed beforehand.
+    pub fn quote_x_to_y_at(
+        &self,
+        point: &CurvePoint,
+        input_x: Amount<TokenX>,
+    ) -> Result<QuoteBreakdown, MathError> {
+        let CurvePoint {
+            big_k,
+            current_x_k,
+            current_y_k,
+            available_y,
+            ..
+        } = *point;
+
+        // 2. find new (x, y) on curve-K
+        let new_x_k = current_x_k
+            .checked_add(input_x.to_curve())
+            .ok_or(MathError::Overflow)?;
+        let new_y_k = big_k.solve(new_x_k).ok_or(MathError::Overflow)?;
+
+        let output_before_fee_y = current_y_k
+            .checked_sub(new_y_k)
+            .and_then(CurveAmount::to_amount)
+            .ok_or(MathError::Overflow)?;
+        if output_before_fee_y >= available_y {
+            return Err(MathError::InsufficientActiveY);
+        }
+        self.split(output_before_fee_y.get(), self.deposit_x < self.borrow_x)
+    }
+
+    /// Exact-in quote of `input_y`. v3 pays no rebate, `fee_before_rebate` is
+    /// the charged fee.
+    pub fn quote_y_to_x(&self, input_y: Amount<TokenY>) -> Result<QuoteBreakdown, MathError> {
+        self.quote_y_to_x_at(&self.curve_point()?, input_y)
+    }
+
+    /// `quote_y_to_x` from a `curve_point` computed beforehand.
+    pub fn quote_y_to_x_at(
+        &self,
+        point: &CurvePoint,
+        input_y: Amount<TokenY>,
+    ) -> Result<QuoteBreakdown, MathError> {
+        let CurvePoint {
+            big_k,
+            current_x_k,
+            current_y_k,
+            available_x,
+            ..
+        } = *point;
+
+        // 2. find new (x, y) on curve-K
+        let new_y_k = current_y_k
+            .checked_add(input_y.to_curve())
+            .ok_or(MathError::Overflow)?;
+        let new_x_k = big_k.solve(new_y_k).ok_or(MathError::Overflow)?;
+
+        let output_before_fee_x = current_x_k
+            .checked_sub(new_x_k)
+            .and_then(CurveAmount::to_amount)
+            .ok_or(MathError::Overflow)?;
+        if output_before_fee_x >= available_x {
+            return Err(MathError::InsufficientActiveX);
+        }
+        self.split(output_before_fee_x.get(), self.deposit_y < self.target_y)
+    }
+
+    /// Smallest X input paying at least `output_y` after fees, with its quote.
+    /// `None` when no input does.
+    pub fn exact_out_x_to_y(
+        &self,
+        point: &CurvePoint,
+        output_y: u64,
+    ) -> Option<(Amount<TokenX>, QuoteBreakdown)> {
+        let quote = |input_x| self.quote_x_to_y_at(point, Amount::new(input_x)).ok();
+        let (input_x, quote) = exact_in(output_y.max(1), quote, |q| q.output_after_fee)?;
+        Some((Amount::new(input_x), quote))
+    }
+
+    /// Smallest Y input paying at least `output_x` after fees, with its quote.
+    /// `None` when no input does.
+    pub fn exact_out_y_to_x(
+        &self,
+        point: &CurvePoint,
+        output_x: u64,
+    ) -> Option<(Amount<TokenY>, QuoteBreakdown)> {
+        let quote = |input_y| self.quote_y_to_x_at(point, Amount::new(input_y)).ok();
+        let (input_y, quote) = exact_in(output_x.max(1), quote, |q| q.output_after_fee)?;
+        Some((Amount::new(input_y), quote))
+    }
+
+    /// Splits the fee on `output_before_fee` between the protocol and the LPs.
+    fn split(
+        &self,
+        output_before_fee: u64,
+        rebalancing: bool,
+    ) -> Result<QuoteBreakdown, MathError> {
+        let fee = output_before_fee
+            .checked_mul(self.fee_millionth)
+            .ok_or(MathError::Overflow)?
+            / MILLION;
+        let output_after_fee = output_before_fee
+            .checked_sub(fee)
+            .ok_or(MathError::Overflow)?;
+
+        let protocol_fee = fee
+            .checked_mul(self.protocol_fee_share_thousandth)
+            .ok_or(MathError::Overflow)?
+            / 1000;
+        let lp_fee = fee.checked_sub(protocol_fee).ok_or(MathError::Overflow)?;
+
+        Ok(QuoteBreakdown {
+            output_before_fee,
+            fee_before_rebate: fee,
+            rebate: 0,
+            protocol_fee,
+            lp_fee,
+            output_after_fee,
+            fee_bps: fee_bps(fee, output_before_fee),
+            rebalancing,
+        })
+    }
+}
@@ -0,0 +1,36 @@
+use obric_math::price::normalize_price_pair;
+use obric_math::{MathError, PriceError, PriceSide};
+
+/// The side and reason `normalize_price_pair` rejects the pair for.
+fn rejected(price_x: (i64, i32), price_y: (i64, i32)) -> (PriceSide, MathError) {
+    let PriceError { side, source } = normalize_price_pair(price_x, price_y).unwrap_err();
+    (side, source)
+}
+
+#[test]
+fn the_unusable_price_is_named() {
+    let usd = (1_00000000, -8);
+    assert_eq!(
+        rejected((0, -8), usd),
+        (PriceSide::X, MathError::NegativePrice)
+    );
+    assert_eq!(
+        rejected(usd, (-1, -8)),
+        (PriceSide::Y, MathError::NegativePrice)
+    );
+    assert_eq!(
+        rejected((i64::MAX, 2), usd),
+        (PriceSide::X, MathError::PriceOutOfRange)
+    );
+    assert_eq!(
+        rejected(usd, (1, i32::MAX)),
+        (PriceSide::Y, MathError::PriceOutOfRange)
+    );
+    // a price too small for the other's exponent is not the other's fault
+    assert_eq!(
+        rejected((1, -8), (i64::MAX, 2)),
+        (PriceSide::Y, MathError::PriceOutOfRange)
+    );
+    // X when both are
+    assert_eq!(rejected((0, -8), (0, -8)).0, PriceSide::X);
+}
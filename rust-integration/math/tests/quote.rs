@@ -0,0 +1,32 @@
+use obric_math::quote::exact_in;
+
+/// Pays 90% of the input up to a reserve limit of 1_110_112, fails past it.
+fn quote(in_amount: u64) -> Option<u64> {
+    (in_amount <= 1_110_112).then_some(in_amount * 9 / 10)
+}
+
+#[test]
+fn fills_between_two_doublings() {
+    // 2^20 pays too little and 2^21 is past the reserve
+    assert!(quote(1 << 20).unwrap() < 999_000);
+    assert_eq!(quote(1 << 21), None);
+    assert_eq!(
+        exact_in(999_000, quote, |out| *out),
+        Some((1_110_000, 999_000))
+    );
+    assert_eq!(
+        exact_in(999_100, quote, |out| *out),
+        Some((1_110_112, 999_100))
+    );
+    assert_eq!(exact_in(999_101, quote, |out| *out), None);
+}
+
+#[test]
+fn small_orders_fill_exactly() {
+    assert_eq!(exact_in(1, quote, |out| *out), Some((2, 1)));
+    assert_eq!(
+        exact_in(900_000, quote, |out| *out),
+        Some((1_000_000, 900_000))
+    );
+    assert_eq!(exact_in(0, quote, |out| *out), Some((1, 0)));
+}
@@ -0,0 +1,184 @@
+use num_integer::Roots;
+use obric_math::price::mults;
+use obric_math::quote::QuoteBreakdown;
+use obric_math::units::{Amount, Invariant, NormalizedPrice};
+use obric_math::v2::Curve;
+
+/// The v2 quote as `SSTradingPair` computed it before it moved here, on bare integers.
+fn legacy_quote(
+    curve: &Curve,
+    input: u64,
+    current: (u64, u64),
+    x_to_y: bool,
+) -> Option<QuoteBreakdown> {
+    let (mult_x, mult_y) = (curve.mult_x.get() as u128, curve.mult_y.get() as u128);
+    let (current_x, current_y) = current;
+    let big_k = curve.big_k.get();
+    let target_x = curve.target_x.get();
+    let value_total = current_x as u128 * mult_x + current_y as u128 * mult_y;
+    let target_y = (value_total.checked_sub(target_x as u128 * mult_x)? / mult_y) as u64;
+    let target_x_k = (big_k * mult_y / mult_x).sqrt();
+    let current_x_k = (target_x_k + current_x as u128).checked_sub(target_x as u128)?;
+    let current_y_k = big_k / current_x_k;
+
+    let (input_target, input_current, output_current, output_before_fee) = if x_to_y {
+        let new_y_k = big_k / (current_x_k + input as u128);
+        (
+            target_x,
+            current_x,
+            current_y,
+            (current_y_k - new_y_k) as u64,
+        )
+    } else {
+        let new_x_k = big_k / (current_y_k + input as u128);
+        (
+            target_y,
+            current_y,
+            current_x,
+            (current_x_k - new_x_k) as u64,
+        )
+    };
+    if output_before_fee >= output_current {
+        return Some(QuoteBreakdown::default());
+    }
+    let fee_before_rebate = output_before_fee * curve.fee_millionth / 1_000_000;
+    let rebate_ratio = std::cmp::min(
+        input,
+        input_target - std::cmp::min(input_target, input_current),
+    ) * 100
+        / input;
+    let rebate = fee_before_rebate * rebate_ratio / 100 * curve.rebate_percentage / 100;
+    let fee = fee_before_rebate - rebate;
+    let protocol_fee = fee * curve.protocol_fee_share_thousandth / 1000;
+    Some(QuoteBreakdown {
+        output_before_fee,
+        fee_before_rebate,
+        rebate,
+        protocol_fee,
+        lp_fee: fee - protocol_fee,
+        output_after_fee: output_before_fee - fee,
+        fee_bps: (fee as u128 * 10_000 / output_before_fee as u128) as u64,
+        rebalancing: input_current < input_target,
+    })
+}
+
+/// A $150 / $1 pool with 6 decimals on both sides and 1000 X at target.
+fn curve(concentration: u128) -> Curve {
+    let (mult_x, mult_y) = mults(
+        NormalizedPrice::new(150_000),
+        NormalizedPrice::new(1_000),
+        6,
+        6,
+    )
+    .unwrap();
+    let target_x_k = 1_000_000_000u128 * concentration;
+    Curve {
+        big_k: Invariant::new(target_x_k * target_x_k * 150_000 / 1_000),
+        target_x: Amount::new(1_000_000_000),
+        mult_x,
+        mult_y,
+        fee_millionth: 1_000,
+        rebate_percentage: 50,
+        protocol_fee_share_thousandth: 200,
+    }
+}
+
+#[test]
+fn quotes_match_the_legacy_math() {
+    for concentration in [1, 10, 100] {
+        let curve = curve(concentration);
+        for current in [
+            (1_000_000_000, 150_000_000_000),
+            (600_000_000, 210_000_000_000),
+            (1_400_000_000, 90_000_000_000),
+        ] {
+            let (current_x, current_y) = (Amount::new(current.0), Amount::new(current.1));
+            for input in [
+                1,
+                999,
+                1_000_000,
+                100_000_000,
+                10_000_000_000,
+                1_000_000_000_000,
+            ] {
+                assert_eq!(
+                    curve
+                        .quote_x_to_y(Amount::new(input), current_x, current_y)
+                        .ok(),
+                    legacy_quote(&curve, input, current, true),
+                    "x to y: concentration {concentration}, reserves {current:?}, input {input}"
+                );
+                assert_eq!(
+                    curve
+                        .quote_y_to_x(Amount::new(input), current_x, current_y)
+                        .ok(),
+                    legacy_quote(&curve, input, current, false),
+                    "y to x: concentration {concentration}, reserves {current:?}, input {input}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn exact_out_is_the_smallest_input_that_fills() {
+    let curve = curve(100);
+    let point = curve
+        .curve_point(Amount::new(1_000_000_000), Amount::new(150_000_000_000))
+        .unwrap();
+    for output_y in [1, 1_000_000, 15_000_000_000, 145_000_000_000] {
+        let (input_x, quote) = curve.exact_out_x_to_y(&point, output_y).unwrap();
+        assert!(quote.output_after_fee >= output_y);
+        let short = curve
+            .quote_x_to_y_at(&point, Amount::new(input_x.get() - 1))
+            .unwrap();
+        assert!(short.output_after_fee < output_y);
+    }
+    // the inputs paying 145000 USDC end where the reserve does, short of the
+    // next power of two: doubling alone never lands on one
+    assert!((0..64).all(|shift| {
+        let quote = curve.quote_x_to_y_at(&point, Amount::new(1 << shift));
+        quote.unwrap().output_after_fee < 145_000_000_000
+    }));
+    // more than the reserve is never paid
+    assert_eq!(curve.exact_out_y_to_x(&point, 1_000_000_000), None);
+}
+
+#[test]
+fn degenerate_pools_fail_instead_of_panicking() {
+    let mut curve = curve(100);
+    curve.mult_x = obric_math::units::Mult::new(0);
+    assert!(curve
+        .curve_point(Amount::new(1_000_000_000), Amount::new(150_000_000_000))
+        .is_err());
+    let curve = Curve::default();
+    assert!(curve.curve_point(Amount::new(1), Amount::new(1)).is_err());
+}
+
+#[test]
+fn fees_past_the_output_fail_instead_of_panicking() {
+    use obric_math::MathError;
+
+    let (current_x, current_y) = (Amount::new(600_000_000), Amount::new(210_000_000_000));
+    let input = Amount::new(100_000_000);
+    // a rebate of more than the fee
+    let mut generous = curve(100);
+    generous.rebate_percentage = 1_000;
+    assert_eq!(
+        generous.quote_x_to_y(input, current_x, current_y),
+        Err(MathError::Overflow)
+    );
+    // a fee of more than the output
+    let mut greedy = curve(100);
+    greedy.rebate_percentage = 0;
+    greedy.fee_millionth = 2_000_000;
+    assert_eq!(
+        greedy.quote_x_to_y(input, current_x, current_y),
+        Err(MathError::Overflow)
+    );
+    greedy.fee_millionth = u64::MAX;
+    assert_eq!(
+        greedy.quote_y_to_x(Amount::new(input.get()), current_x, current_y),
+        Err(MathError::Overflow)
+    );
+}
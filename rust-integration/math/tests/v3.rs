@@ -0,0 +1,150 @@
+use obric_math::price::mults;
+use obric_math::quote::QuoteBreakdown;
+use obric_math::units::{Amount, NormalizedPrice};
+use obric_math::v3::Curve;
+use obric_math::MathError;
+
+/// The v3 quote as `SSTradingPair` computed it before it moved here, on bare integers.
+fn legacy_quote(curve: &Curve, input: u64, x_to_y: bool) -> Result<QuoteBreakdown, MathError> {
+    let (mult_x, mult_y) = (curve.mult_x.get() as u128, curve.mult_y.get() as u128);
+    let (deposit_x, borrow_x) = (curve.deposit_x.get() as u128, curve.borrow_x.get() as u128);
+    let (deposit_y, target_y) = (curve.deposit_y.get(), curve.target_y.get());
+    let concentration = curve.concentration as u128;
+
+    let target_y_k = target_y as u128 * concentration;
+    let target_x_k = target_y_k * mult_y / mult_x;
+    let current_y_k = target_y_k + deposit_y as u128 - target_y as u128;
+    let current_x_k = target_x_k + deposit_x - borrow_x;
+    let big_k = current_x_k * current_y_k;
+    let available_x = (target_x_k / concentration + deposit_x).saturating_sub(borrow_x) as u64;
+
+    let output_before_fee = if x_to_y {
+        let new_y_k = big_k / (current_x_k + input as u128);
+        let output = (current_y_k - new_y_k) as u64;
+        if output >= deposit_y {
+            return Err(MathError::InsufficientActiveY);
+        }
+        output
+    } else {
+        let new_x_k = big_k / (current_y_k + input as u128);
+        let output = (current_x_k - new_x_k) as u64;
+        if output >= available_x {
+            return Err(MathError::InsufficientActiveX);
+        }
+        output
+    };
+    let fee = output_before_fee * curve.fee_millionth / 1_000_000;
+    let protocol_fee = fee * curve.protocol_fee_share_thousandth / 1000;
+    Ok(QuoteBreakdown {
+        output_before_fee,
+        fee_before_rebate: fee,
+        rebate: 0,
+        protocol_fee,
+        lp_fee: fee - protocol_fee,
+        output_after_fee: output_before_fee - fee,
+        fee_bps: match output_before_fee {
+            0 => 0,
+            _ => (fee as u128 * 10_000 / output_before_fee as u128) as u64,
+        },
+        rebalancing: if x_to_y {
+            deposit_x < borrow_x
+        } else {
+            deposit_y < target_y
+        },
+    })
+}
+
+/// A $150 / $1 pool with 9 and 6 decimals, lent out as given.
+fn curve(concentration: u64, deposit_x: u64, borrow_x: u64, deposit_y: u64) -> Curve {
+    let (mult_x, mult_y) = mults(
+        NormalizedPrice::new(150_000),
+        NormalizedPrice::new(1_000),
+        9,
+        6,
+    )
+    .unwrap();
+    let mut curve = Curve {
+        concentration,
+        deposit_x: Amount::new(deposit_x),
+        borrow_x: Amount::new(borrow_x),
+        deposit_y: Amount::new(deposit_y),
+        mult_x,
+        mult_y,
+        fee_millionth: 2_500,
+        protocol_fee_share_thousandth: 200,
+        ..Curve::default()
+    };
+    curve.target_y = curve.compute_target_y().unwrap();
+    curve
+}
+
+#[test]
+fn quotes_match_the_legacy_math() {
+    for concentration in [1, 10, 100] {
+        for (deposit_x, borrow_x, deposit_y) in [
+            (0, 0, 150_000_000_000),
+            (1_000_000_000_000, 0, 0),
+            (0, 400_000_000_000, 210_000_000_000),
+            (300_000_000_000, 100_000_000_000, 90_000_000_000),
+        ] {
+            let curve = curve(concentration, deposit_x, borrow_x, deposit_y);
+            for input in [
+                1,
+                999,
+                1_000_000,
+                100_000_000,
+                10_000_000_000,
+                1_000_000_000_000,
+            ] {
+                assert_eq!(
+                    curve.quote_x_to_y(Amount::new(input)),
+                    legacy_quote(&curve, input, true),
+                    "x to y: concentration {concentration}, pool {curve:?}, input {input}"
+                );
+                assert_eq!(
+                    curve.quote_y_to_x(Amount::new(input)),
+                    legacy_quote(&curve, input, false),
+                    "y to x: concentration {concentration}, pool {curve:?}, input {input}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn the_invariant_at_target_is_balanced() {
+    let curve = curve(50, 0, 0, 150_000_000_000);
+    let point = curve.curve_point().unwrap();
+    assert_eq!(
+        curve.invariant_at_target(curve.target_y).unwrap(),
+        point.big_k
+    );
+}
+
+#[test]
+fn exact_out_is_the_smallest_input_that_fills() {
+    let curve = curve(100, 0, 0, 150_000_000_000);
+    let point = curve.curve_point().unwrap();
+    for output_y in [1, 1_000_000, 15_000_000_000, 145_000_000_000] {
+        let (input_x, quote) = curve.exact_out_x_to_y(&point, output_y).unwrap();
+        assert!(quote.output_after_fee >= output_y);
+        let short = curve.quote_x_to_y_at(&point, Amount::new(input_x.get() - 1));
+        assert!(short.map_or(true, |short| short.output_after_fee < output_y));
+    }
+    // the inputs paying 145000 USDC end where the reserve does, short of the
+    // next power of two: doubling alone never lands on one
+    assert!((0..64).all(|shift| {
+        let quote = curve.quote_x_to_y_at(&point, Amount::new(1 << shift));
+        quote.map_or(true, |quote| quote.output_after_fee < 145_000_000_000)
+    }));
+    assert_eq!(curve.exact_out_x_to_y(&point, 150_000_000_000), None);
+}
+
+#[test]
+fn overflows_are_errors() {
+    let mut curve = curve(100, 0, 0, 150_000_000_000);
+    curve.concentration = u64::MAX;
+    curve.target_y = Amount::new(u64::MAX);
+    assert_eq!(curve.curve_point(), Err(MathError::Overflow));
+    assert_eq!(curve.quote_x_to_y(Amount::new(1)), Err(MathError::Overflow));
+}
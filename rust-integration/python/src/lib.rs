@@ -0,0 +1,530 @@
+//! Python bindings decoding Obric V2 and V3 pools from recorded account bytes
+//! and quoting them with the same code as `ObricV2Amm` and `ObricV3Amm`.
+//!
+//! ```python
+//! import obric
+//! pair = obric.TradingPairV2.decode(pool_bytes)
+//! price_x, price_y, _ = obric.normalize_price_pair(
+//!     obric.decode_price(price_x_bytes), obric.decode_price(price_y_bytes)
+//! )
+//! pair.update_price(price_x, price_y, x_decimals, y_decimals)
+//! pair.update_mints(mint_x_bytes, mint_x_owner, mint_y_bytes, mint_y_owner, epoch)
+//! quote = pair.quote_x_to_y(1_000_000, reserve_x, reserve_y)
+//! ```
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountDeserialize;
+use obric_math::units::{Amount, NormalizedPrice};
+use obric_sdk_common::token::MintInfo;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::str::FromStr;
+
+fn value_error(err: impl ToString) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+/// How a quote splits between the user, the fee and its rebate, the protocol and the LPs.
+#[pyclass(name = "QuoteBreakdown", get_all, frozen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PyQuoteBreakdown {
+    pub output_before_fee: u64,
+    pub fee_before_rebate: u64,
+    pub rebate: u64,
+    pub protocol_fee: u64,
+    pub lp_fee: u64,
+    pub output_after_fee: u64,
+    pub fee_bps: u64,
+    pub rebalancing: bool,
+    /// What the user receives: `output_after_fee` net of the output mint's
+    /// transfer fee, as `Quote::out_amount`
+    pub out_amount: u64,
+}
+
+impl From<obric_math::quote::QuoteBreakdown> for PyQuoteBreakdown {
+    fn from(quote: obric_math::quote::QuoteBreakdown) -> Self {
+        Self {
+            output_before_fee: quote.output_before_fee,
+            fee_before_rebate: quote.fee_before_rebate,
+            rebate: quote.rebate,
+            protocol_fee: quote.protocol_fee,
+            lp_fee: quote.lp_fee,
+            output_after_fee: quote.output_after_fee,
+            fee_bps: quote.fee_bps,
+            rebalancing: quote.rebalancing,
+            out_amount: quote.output_after_fee,
+        }
+    }
+}
+
+#[pymethods]
+impl PyQuoteBreakdown {
+    fn __repr__(&self) -> String {
+        format!("{self:?}")
+    }
+
+    fn __eq__(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+/// `(price, expo)` of a Pyth price account, as `PriceFeed` reads it.
+#[pyfunction]
+fn decode_price(data: &[u8]) -> PyResult<(i64, i32)> {
+    let feed =
+        obric_solana::state::PriceFeed::try_deserialize(&mut &data[..]).map_err(value_error)?;
+    let price = feed.get_price_unchecked();
+    Ok((price.price, price.expo))
+}
+
+/// Two `(price, expo)` scaled to their common exponent, as
+/// `(price_x, price_y, expo)`, for `update_price`.
+#[pyfunction]
+fn normalize_price_pair(price_x: (i64, i32), price_y: (i64, i32)) -> PyResult<(u64, u64, i32)> {
+    let (price_x, price_y, expo) =
+        obric_math::price::normalize_price_pair(price_x, price_y).map_err(value_error)?;
+    Ok((price_x.get(), price_y.get(), expo))
+}
+
+/// A v2 `SSTradingPair`. Reserves are passed to each quote, the mints'
+/// transfer fees apply once `update_mints` has read them.
+#[pyclass(name = "TradingPairV2")]
+#[derive(Clone)]
+pub struct TradingPairV2 {
+    state: obric_solana::state::SSTradingPair,
+    mints: Option<(MintInfo, MintInfo)>,
+    epoch: u64,
+}
+
+impl TradingPairV2 {
+    /// Transfer fee on moving `amount` of X, or of Y, at `epoch`.
+    fn transfer_fee(&self, amount: u64, of_x: bool) -> u64 {
+        self.mints.map_or(0, |(mint_x, mint_y)| {
+            let mint = if of_x { mint_x } else { mint_y };
+            mint.transfer_fee(self.epoch, amount)
+        })
+    }
+
+    /// Quotes `in_amount` net of its transfer fee, and nets `out_amount` of
+    /// the output's. `ObricV2Amm` does not load Token-2022 pools, which the
+    /// program cannot swap, so this is the only place they are priced.
+    fn quote(
+        &self,
+        in_amount: u64,
+        is_x_to_y: bool,
+        quote: impl FnOnce(u64) -> anchor_lang::Result<obric_math::quote::QuoteBreakdown>,
+    ) -> PyResult<PyQuoteBreakdown> {
+        let net_in = in_amount - self.transfer_fee(in_amount, is_x_to_y);
+        let mut quote: PyQuoteBreakdown = quote(net_in).map_err(value_error)?.into();
+        quote.out_amount -= self.transfer_fee(quote.output_after_fee, !is_x_to_y);
+        Ok(quote)
+    }
+}
+
+#[pymethods]
+impl TradingPairV2 {
+    /// Decodes the data of the pool account, discriminator included.
+    #[staticmethod]
+    fn decode(data: &[u8]) -> PyResult<Self> {
+        let state = obric_solana::state::SSTradingPair::try_deserialize(&mut &data[..])
+            .map_err(value_error)?;
+        Ok(Self {
+            state,
+            mints: None,
+            epoch: 0,
+        })
+    }
+
+    /// Every field as JSON, pubkeys in base58.
+    fn to_json(&self) -> PyResult<String> {
+        serde_json::to_string(&self.state).map_err(value_error)
+    }
+
+    #[getter]
+    fn mint_x(&self) -> String {
+        self.state.mint_x.to_string()
+    }
+
+    #[getter]
+    fn mint_y(&self) -> String {
+        self.state.mint_y.to_string()
+    }
+
+    #[getter]
+    fn reserve_x(&self) -> String {
+        self.state.reserve_x.to_string()
+    }
+
+    #[getter]
+    fn reserve_y(&self) -> String {
+        self.state.reserve_y.to_string()
+    }
+
+    #[getter]
+    fn x_price_feed_id(&self) -> String {
+        self.state.x_price_feed_id.to_string()
+    }
+
+    #[getter]
+    fn y_price_feed_id(&self) -> String {
+        self.state.y_price_feed_id.to_string()
+    }
+
+    #[getter]
+    fn big_k(&self) -> u128 {
+        self.state.big_k
+    }
+
+    #[getter]
+    fn target_x(&self) -> u64 {
+        self.state.target_x
+    }
+
+    #[getter]
+    fn mult_x(&self) -> u64 {
+        self.state.mult_x
+    }
+
+    #[getter]
+    fn mult_y(&self) -> u64 {
+        self.state.mult_y
+    }
+
+    #[getter]
+    fn fee_millionth(&self) -> u64 {
+        self.state.fee_millionth
+    }
+
+    #[getter]
+    fn rebate_percentage(&self) -> u64 {
+        self.state.rebate_percentage
+    }
+
+    #[getter]
+    fn protocol_fee_share_thousandth(&self) -> u64 {
+        self.state.protocol_fee_share_thousandth
+    }
+
+    /// Sets `mult_x` and `mult_y` from normalized prices and the mints' decimals.
+    fn update_price(
+        &mut self,
+        price_x: u64,
+        price_y: u64,
+        x_decimals: u8,
+        y_decimals: u8,
+    ) -> PyResult<()> {
+        let (price_x, price_y) = (NormalizedPrice::new(price_x), NormalizedPrice::new(price_y));
+        self.state
+            .update_price(price_x, price_y, x_decimals, y_decimals)
+            .map_err(value_error)
+    }
+
+    /// `(target_x, target_y)` at the given reserves.
+    fn get_target_xy(&self, current_x: u64, current_y: u64) -> PyResult<(u64, u64)> {
+        let (target_x, target_y) = self
+            .0
+            .get_target_xy(Amount::new(current_x), Amount::new(current_y))
+            .map_err(value_error)?;
+        Ok((target_x.get(), target_y.get()))
+    }
+
+    /// Reads the mints' transfer fees from the data of their accounts and the
+    /// token program owning them. `epoch` picks the fee of each mint.
+    fn update_mints(
+        &mut self,
+        mint_x: &[u8],
+        mint_x_owner: &str,
+        mint_y: &[u8],
+        mint_y_owner: &str,
+        epoch: u64,
+    ) -> PyResult<()> {
+        let mint = |data: &[u8], owner: &str| -> PyResult<MintInfo> {
+            let owner = Pubkey::from_str(owner).map_err(value_error)?;
+            MintInfo::unpack(data, &owner).map_err(value_error)
+        };
+        self.mints = Some((mint(mint_x, mint_x_owner)?, mint(mint_y, mint_y_owner)?));
+        self.epoch = epoch;
+        Ok(())
+    }
+
+    /// Exact-in quote of `input_x` at the given reserves, all zeros when the
+    /// reserves cannot pay it. The input is taken net of its transfer fee.
+    fn quote_x_to_y(
+        &self,
+        input_x: u64,
+        current_x: u64,
+        current_y: u64,
+    ) -> PyResult<PyQuoteBreakdown> {
+        self.quote(input_x, true, |input_x| {
+            self.state.quote_x_to_y_breakdown(
+                Amount::new(input_x),
+                Amount::new(current_x),
+                Amount::new(current_y),
+            )
+        })
+    }
+
+    /// Exact-in quote of `input_y` at the given reserves, all zeros when the
+    /// reserves cannot pay it. The input is taken net of its transfer fee.
+    fn quote_y_to_x(
+        &self,
+        input_y: u64,
+        current_x: u64,
+        current_y: u64,
+    ) -> PyResult<PyQuoteBreakdown> {
+        self.quote(input_y, false, |input_y| {
+            self.state.quote_y_to_x_breakdown(
+                Amount::new(input_y),
+                Amount::new(current_x),
+                Amount::new(current_y),
+            )
+        })
+    }
+}
+
+/// A v3 `SSTradingPair`. Quotes read the deposits and borrows it records, so
+/// `update_price`, `compute_target_y` and `update_target_y` come first, in the
+/// order `ObricV3Amm::update` runs them.
+#[pyclass(name = "TradingPairV3")]
+#[derive(Clone)]
+pub struct TradingPairV3(obric_solana_v3::state::SSTradingPair);
+
+#[pymethods]
+impl TradingPairV3 {
+    /// Decodes the data of the pool account, discriminator included.
+    #[staticmethod]
+    fn decode(data: &[u8]) -> PyResult<Self> {
+        let state = obric_solana_v3::state::SSTradingPair::try_deserialize(&mut &data[..])
+            .map_err(value_error)?;
+        Ok(Self(state))
+    }
+
+    /// Every field as JSON, pubkeys in base58.
+    fn to_json(&self) -> PyResult<String> {
+        serde_json::to_string(&self.0).map_err(value_error)
+    }
+
+    #[getter]
+    fn mint_x(&self) -> String {
+        self.0.mint_x.to_string()
+    }
+
+    #[getter]
+    fn mint_y(&self) -> String {
+        self.0.mint_y.to_string()
+    }
+
+    #[getter]
+    fn x_price_feed_id(&self) -> String {
+        self.0.x_price_feed_id.to_string()
+    }
+
+    #[getter]
+    fn y_price_feed_id(&self) -> String {
+        self.0.y_price_feed_id.to_string()
+    }
+
+    #[getter]
+    fn deposit_x(&self) -> u64 {
+        self.0.deposit_x
+    }
+
+    #[getter]
+    fn borrow_x(&self) -> u64 {
+        self.0.borrow_x
+    }
+
+    #[getter]
+    fn deposit_y(&self) -> u64 {
+        self.0.deposit_y
+    }
+
+    #[getter]
+    fn borrow_y(&self) -> u64 {
+        self.0.borrow_y
+    }
+
+    #[getter]
+    fn target_y(&self) -> u64 {
+        self.0.target_y
+    }
+
+    #[getter]
+    fn concentration(&self) -> u64 {
+        self.0.concentration
+    }
+
+    #[getter]
+    fn big_k(&self) -> u128 {
+        self.0.big_k
+    }
+
+    #[getter]
+    fn mult_x(&self) -> u64 {
+        self.0.mult_x
+    }
+
+    #[getter]
+    fn mult_y(&self) -> u64 {
+        self.0.mult_y
+    }
+
+    #[getter]
+    fn decimals_x(&self) -> u8 {
+        self.0.decimals_x
+    }
+
+    #[getter]
+    fn decimals_y(&self) -> u8 {
+        self.0.decimals_y
+    }
+
+    #[getter]
+    fn fee_millionth(&self) -> u64 {
+        self.0.fee_millionth
+    }
+
+    #[getter]
+    fn protocol_fee_share_thousandth(&self) -> u64 {
+        self.0.protocol_fee_share_thousandth
+    }
+
+    /// Sets `mult_x` and `mult_y` from normalized prices and the decimals
+    /// stored in the pair.
+    fn update_price(&mut self, price_x: u64, price_y: u64) -> PyResult<()> {
+        let (price_x, price_y) = (NormalizedPrice::new(price_x), NormalizedPrice::new(price_y));
+        self.0.update_price(price_x, price_y).map_err(value_error)
+    }
+
+    /// Net value of the deposits and borrows in units of Y.
+    fn compute_target_y(&self) -> PyResult<u64> {
+        let target_y = self.0.compute_target_y().map_err(value_error)?;
+        Ok(target_y.get())
+    }
+
+    /// Sets `target_y` and the `big_k` of the curve through it.
+    fn update_target_y(&mut self, target_y: u64) -> PyResult<()> {
+        self.0
+            .update_target_y(Amount::new(target_y))
+            .map_err(value_error)
+    }
+
+    /// `(big_k, current_x_k, current_y_k, available_x, available_y)` that
+    /// every quote starts from.
+    fn get_pool_values_for_quoting(&self) -> PyResult<(u128, u128, u128, u64, u64)> {
+        let (big_k, current_x_k, current_y_k, available_x, available_y) =
+            self.0.get_pool_values_for_quoting().map_err(value_error)?;
+        Ok((
+            big_k.get(),
+            current_x_k.get(),
+            current_y_k.get(),
+            available_x.get(),
+            available_y.get(),
+        ))
+    }
+
+    /// Exact-in quote of `input_x`. Raises when the pool cannot pay it.
+    fn quote_x_to_y(&self, input_x: u64) -> PyResult<PyQuoteBreakdown> {
+        let quote = self
+            .0
+            .quote_x_to_y_breakdown(Amount::new(input_x))
+            .map_err(value_error)?;
+        Ok(quote.into())
+    }
+
+    /// Exact-in quote of `input_y`. Raises when the pool cannot pay it.
+    fn quote_y_to_x(&self, input_y: u64) -> PyResult<PyQuoteBreakdown> {
+        let quote = self
+            .0
+            .quote_y_to_x_breakdown(Amount::new(input_y))
+            .map_err(value_error)?;
+        Ok(quote.into())
+    }
+}
+
+/// A Larix lending reserve, which v3 pools deposit into and borrow from.
+/// Decimals (`*_wads`) are integers scaled by 10^18.
+#[pyclass(name = "LarixReserve")]
+#[derive(Clone)]
+pub struct LarixReserve(obric_solana_v3::state::Reserve);
+
+#[pymethods]
+impl LarixReserve {
+    /// Decodes the data of a reserve account of the Larix program.
+    #[staticmethod]
+    fn decode(data: &[u8]) -> PyResult<Self> {
+        let reserve = obric_solana_v3::state::Reserve::try_deserialize(&mut &data[..])
+            .map_err(value_error)?;
+        Ok(Self(reserve))
+    }
+
+    #[getter]
+    fn lending_market(&self) -> String {
+        self.0.lending_market.to_string()
+    }
+
+    #[getter]
+    fn liquidity_mint(&self) -> String {
+        self.0.liquidity.mint_pubkey.to_string()
+    }
+
+    #[getter]
+    fn liquidity_supply(&self) -> String {
+        self.0.liquidity.supply_pubkey.to_string()
+    }
+
+    #[getter]
+    fn available_amount(&self) -> u64 {
+        self.0.liquidity.available_amount
+    }
+
+    #[getter]
+    fn borrowed_amount_wads(&self) -> PyResult<u128> {
+        self.0
+            .liquidity
+            .borrowed_amount_wads
+            .to_scaled_val()
+            .map_err(value_error)
+    }
+
+    #[getter]
+    fn owner_unclaimed_wads(&self) -> PyResult<u128> {
+        self.0
+            .liquidity
+            .owner_unclaimed
+            .to_scaled_val()
+            .map_err(value_error)
+    }
+
+    #[getter]
+    fn collateral_mint(&self) -> String {
+        self.0.collateral.mint_pubkey.to_string()
+    }
+
+    #[getter]
+    fn collateral_supply(&self) -> String {
+        self.0.collateral.supply_pubkey.to_string()
+    }
+
+    #[getter]
+    fn mint_total_supply(&self) -> u64 {
+        self.0.collateral.mint_total_supply
+    }
+
+    /// Liquidity per collateral token, scaled by 10^18.
+    fn ctoken_exchange_rate_wads(&self) -> PyResult<u128> {
+        let rate = self.0.get_ctoken_exchange_rate().map_err(value_error)?;
+        rate.to_scaled_val().map_err(value_error)
+    }
+}
+
+#[pymodule]
+fn obric(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(decode_price, m)?)?;
+    m.add_function(wrap_pyfunction!(normalize_price_pair, m)?)?;
+    m.add_class::<PyQuoteBreakdown>()?;
+    m.add_class::<TradingPairV2>()?;
+    m.add_class::<TradingPairV3>()?;
+    m.add_class::<LarixReserve>()?;
+    Ok(())
+}
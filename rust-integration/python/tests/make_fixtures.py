@@ -0,0 +1,106 @@
+"""Writes the account fixtures under `fixtures/`. They are checked in; rerun
+this only to change them, and update the expected values in the tests.
+
+- v2_pool.bin: v2 SSTradingPair of $150 X against $1 Y, 6 decimals on both
+  sides, balanced at 1000 X and 150000 Y with a concentration of 100
+- v3_pool.bin: v3 SSTradingPair of the same prices with 9 and 6 decimals,
+  150000 Y deposited, concentration 100, before any update
+- price_x.bin, price_y.bin: Pythnet price accounts of $150 and $1, expo -8
+- fee_mint.bin: Token-2022 mint with 6 decimals charging a 1% transfer fee,
+  uncapped, from epoch 0
+
+larix_reserve.bin is packed by the Larix crate rather than by hand:
+`cargo test -p obric-v3-sdk test_python_fixtures` writes it when it is missing,
+or again with OBRIC_WRITE_FIXTURES=1.
+"""
+
+import hashlib
+import struct
+from pathlib import Path
+
+FIXTURES = Path(__file__).parent / "fixtures"
+
+
+def key(i):
+    return bytes([i]) * 32
+
+
+def discriminator(name):
+    return hashlib.sha256(f"account:{name}".encode()).digest()[:8]
+
+
+def u64(*values):
+    return b"".join(struct.pack("<Q", v) for v in values)
+
+
+def v2_pool():
+    data = discriminator("SSTradingPair")
+    data += struct.pack("<?", True)
+    data += key(1) + key(2)  # price feeds
+    data += key(3) + key(4)  # reserves
+    data += key(5) + key(6)  # protocol fee accounts
+    data += struct.pack("<B", 255)
+    data += key(7) + key(8)  # mints
+    target_x_k = 100 * 1_000_000_000
+    data += u64(100)  # concentration
+    data += (target_x_k * target_x_k * 150).to_bytes(16, "little")  # big_k
+    data += u64(1_000_000_000, 0)  # target_x, cumulative_volume
+    data += u64(0, 0, 1_000, 50, 200)  # mults, fee, rebate, protocol share
+    data += u64(*[0] * 8) + u64(*[0] * 8)  # volume records
+    data += u64(*[0] * 24)
+    assert len(data) == 666
+    return data
+
+
+def v3_pool():
+    data = discriminator("SSTradingPair")
+    data += struct.pack("<?", True)
+    data += key(1) + key(2)  # price feeds
+    data += key(3) + key(4)  # reserves
+    data += key(9) + key(10)  # ctoken reserves
+    data += key(5) + key(6)  # protocol fee accounts
+    data += struct.pack("<B", 255)
+    data += key(7) + key(8)  # mints
+    # deposit_x, borrow_x, deposit_y, borrow_y, target_y, concentration
+    data += u64(0, 0, 150_000_000_000, 0, 0, 100)
+    data += (0).to_bytes(16, "little")  # big_k
+    data += u64(0, 0, 0, 2_500, 0, 200)  # volume, mults, fee, rebate, protocol share
+    data += struct.pack("<BB", 9, 6)
+    data += u64(*[0] * 8) + bytes(6) + u64(*[0] * 8)
+    data += u64(*[0] * 23)
+    assert len(data) == 762
+    return data
+
+
+def price_account(price, expo=-8):
+    # pyth-sdk-solana's PythnetPriceAccount: a 240 byte header, 128
+    # components of 96 bytes and a 48 byte cumulative price
+    data = bytearray(240 + 128 * 96 + 48)
+    struct.pack_into("<IIII", data, 0, 0xA1B2C3D4, 2, 3, len(data))  # magic, ver, atype, size
+    struct.pack_into("<i", data, 20, expo)
+    struct.pack_into("<q", data, 184, price)  # prev_price
+    struct.pack_into("<qQI", data, 208, price, 0, 1)  # agg price, conf, status trading
+    return bytes(data)
+
+
+def transfer_fee_mint(decimals, epoch, basis_points):
+    # spl-token-2022: the base mint padded to the 165 bytes of a token
+    # account, the account type, then the TransferFeeConfig extension as TLV
+    data = bytearray(165)
+    struct.pack_into("<QBB", data, 36, 1_000_000, decimals, True)  # supply, decimals, initialized
+    data += struct.pack("<B", 1)  # AccountType::Mint
+    data += struct.pack("<HH", 1, 108)  # ExtensionType::TransferFeeConfig, length
+    data += bytes(32 + 32) + u64(0)  # authorities, withheld amount
+    data += u64(0, 0) + struct.pack("<H", 0)  # older fee: epoch, maximum, basis points
+    data += u64(epoch, 2**64 - 1) + struct.pack("<H", basis_points)  # newer fee
+    assert len(data) == 278
+    return bytes(data)
+
+
+if __name__ == "__main__":
+    FIXTURES.mkdir(exist_ok=True)
+    (FIXTURES / "v2_pool.bin").write_bytes(v2_pool())
+    (FIXTURES / "v3_pool.bin").write_bytes(v3_pool())
+    (FIXTURES / "price_x.bin").write_bytes(price_account(150_00000000))
+    (FIXTURES / "price_y.bin").write_bytes(price_account(1_00000000))
+    (FIXTURES / "fee_mint.bin").write_bytes(transfer_fee_mint(6, 0, 100))
@@ -0,0 +1,241 @@
+"""Runs against the checked-in fixtures, see make_fixtures.py. Build the module
+first with `maturin develop` from the `python` directory."""
+
+import struct
+from math import isqrt
+from pathlib import Path
+
+import pytest
+
+import obric
+
+FIXTURES = Path(__file__).parent / "fixtures"
+SIZES = [1, 999, 1_000_000, 100_000_000, 10_000_000_000, 1_000_000_000_000]
+TOKEN = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+TOKEN_2022 = "TokenzQdBNbLqP5VEhdkAS6EPFLziuF3kuDmpE8Fm3bd"
+WAD = 10**18
+
+
+def fixture(name):
+    return (FIXTURES / name).read_bytes()
+
+
+def b58(data):
+    alphabet = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz"
+    n, out = int.from_bytes(data, "big"), ""
+    while n:
+        n, rem = divmod(n, 58)
+        out = alphabet[rem] + out
+    return "1" * (len(data) - len(data.lstrip(b"\0"))) + out
+
+
+def spl_mint(decimals):
+    data = bytearray(82)
+    struct.pack_into("<QBB", data, 36, 1_000_000, decimals, True)  # supply, decimals, initialized
+    return bytes(data)
+
+
+def prices():
+    return obric.normalize_price_pair(
+        obric.decode_price(fixture("price_x.bin")), obric.decode_price(fixture("price_y.bin"))
+    )
+
+
+def split(output, fee_millionth, rebate_ratio, rebate_percentage, protocol_share, rebalancing):
+    fee_before_rebate = output * fee_millionth // 1_000_000
+    rebate = fee_before_rebate * rebate_ratio // 100 * rebate_percentage // 100
+    fee = fee_before_rebate - rebate
+    protocol_fee = fee * protocol_share // 1000
+    return dict(
+        output_before_fee=output,
+        fee_before_rebate=fee_before_rebate,
+        rebate=rebate,
+        protocol_fee=protocol_fee,
+        lp_fee=fee - protocol_fee,
+        output_after_fee=output - fee,
+        fee_bps=fee * 10_000 // output if output else 0,
+        rebalancing=rebalancing,
+    )
+
+
+def v2_reference(pair, input, current_x, current_y, x_to_y):
+    """The v2 curve by hand, as a quant would write it."""
+    big_k, mult_x, mult_y, target_x = pair.big_k, pair.mult_x, pair.mult_y, pair.target_x
+    target_y = (current_x * mult_x + current_y * mult_y - target_x * mult_x) // mult_y
+    current_x_k = isqrt(big_k * mult_y // mult_x) + current_x - target_x
+    current_y_k = big_k // current_x_k
+    if x_to_y:
+        output = current_y_k - big_k // (current_x_k + input)
+        target, current, reserve = target_x, current_x, current_y
+    else:
+        output = current_x_k - big_k // (current_y_k + input)
+        target, current, reserve = target_y, current_y, current_x
+    if output >= reserve:
+        return split(0, 0, 0, 0, 0, False)
+    rebate_ratio = min(input, target - min(target, current)) * 100 // input
+    return split(
+        output,
+        pair.fee_millionth,
+        rebate_ratio,
+        pair.rebate_percentage,
+        pair.protocol_fee_share_thousandth,
+        current < target,
+    )
+
+
+def v3_reference(pair, input, x_to_y):
+    """The v3 curve by hand. None when the pool cannot pay."""
+    target_y_k = pair.target_y * pair.concentration
+    target_x_k = target_y_k * pair.mult_y // pair.mult_x
+    current_y_k = target_y_k + pair.deposit_y - pair.target_y
+    current_x_k = target_x_k + pair.deposit_x - pair.borrow_x
+    big_k = current_x_k * current_y_k
+    if x_to_y:
+        output = current_y_k - big_k // (current_x_k + input)
+        available = pair.deposit_y
+        rebalancing = pair.deposit_x < pair.borrow_x
+    else:
+        output = current_x_k - big_k // (current_y_k + input)
+        available = max(target_x_k // pair.concentration + pair.deposit_x - pair.borrow_x, 0)
+        rebalancing = pair.deposit_y < pair.target_y
+    if output >= available:
+        return None
+    return split(output, pair.fee_millionth, 0, 0, pair.protocol_fee_share_thousandth, rebalancing)
+
+
+def as_dict(quote):
+    return {name: getattr(quote, name) for name in split(0, 0, 0, 0, 0, False)}
+
+
+def test_prices():
+    assert obric.decode_price(fixture("price_x.bin")) == (150_00000000, -8)
+    assert prices() == (150_000, 1_000, -3)
+    # a memecoin keeps three significant digits
+    assert obric.normalize_price_pair((2_500, -8), (1_00000000, -8)) == (2_500, 100_000_000, -8)
+    with pytest.raises(ValueError):
+        obric.normalize_price_pair((-1, -8), (1_00000000, -8))
+    with pytest.raises(ValueError):
+        obric.decode_price(fixture("v2_pool.bin"))
+
+
+def test_v2_decode():
+    pair = obric.TradingPairV2.decode(fixture("v2_pool.bin"))
+    assert pair.mint_x == b58(bytes([7]) * 32)
+    assert pair.reserve_y == b58(bytes([4]) * 32)
+    assert pair.big_k == 150 * 10**22
+    assert (pair.fee_millionth, pair.rebate_percentage) == (1_000, 50)
+    assert '"target_x":1000000000' in pair.to_json()
+    with pytest.raises(ValueError):
+        obric.TradingPairV2.decode(fixture("v2_pool.bin")[:100])
+
+
+def test_v2_quotes():
+    pair = obric.TradingPairV2.decode(fixture("v2_pool.bin"))
+    price_x, price_y, _ = prices()
+    pair.update_price(price_x, price_y, 6, 6)
+    assert (pair.mult_x, pair.mult_y) == (150_000, 1_000)
+
+    # 1 X at $150, with a 0.1% fee of which the protocol takes a fifth
+    quote = pair.quote_x_to_y(1_000_000, 1_000_000_000, 150_000_000_000)
+    assert quote.output_after_fee == 149_848_503
+    assert (quote.protocol_fee, quote.lp_fee) == (29_999, 119_999)
+    assert pair.get_target_xy(1_000_000_000, 150_000_000_000) == (1_000_000_000, 150_000_000_000)
+
+    # no transfer fee before update_mints
+    assert quote.out_amount == quote.output_after_fee
+
+    for current_x, current_y in [
+        (1_000_000_000, 150_000_000_000),
+        (600_000_000, 210_000_000_000),
+        (1_400_000_000, 90_000_000_000),
+    ]:
+        for size in SIZES:
+            quote = pair.quote_x_to_y(size, current_x, current_y)
+            assert as_dict(quote) == v2_reference(pair, size, current_x, current_y, True)
+            quote = pair.quote_y_to_x(size, current_x, current_y)
+            assert as_dict(quote) == v2_reference(pair, size, current_x, current_y, False)
+
+
+def test_v2_transfer_fees():
+    pair = obric.TradingPairV2.decode(fixture("v2_pool.bin"))
+    price_x, price_y, _ = prices()
+    pair.update_price(price_x, price_y, 6, 6)
+    # 1% on X, none on Y
+    pair.update_mints(fixture("fee_mint.bin"), TOKEN_2022, spl_mint(6), TOKEN, 0)
+    current = (1_000_000_000, 150_000_000_000)
+
+    # the pool receives 990000 X
+    quote = pair.quote_x_to_y(1_000_000, *current)
+    assert as_dict(quote) == v2_reference(pair, 990_000, *current, True)
+    assert quote.out_amount == quote.output_after_fee == 148_350_032
+
+    # the user receives 1% less X than the pool pays, rounded up
+    quote = pair.quote_y_to_x(150_000_000, *current)
+    assert as_dict(quote) == v2_reference(pair, 150_000_000, *current, False)
+    assert (quote.output_after_fee, quote.out_amount) == (998_992, 989_002)
+
+    with pytest.raises(ValueError):
+        pair.update_mints(fixture("fee_mint.bin"), TOKEN, spl_mint(6), TOKEN, 0)
+    with pytest.raises(ValueError):
+        pair.update_mints(spl_mint(6), "not a pubkey", spl_mint(6), TOKEN, 0)
+
+
+def test_v3_quotes():
+    pair = obric.TradingPairV3.decode(fixture("v3_pool.bin"))
+    assert (pair.decimals_x, pair.decimals_y) == (9, 6)
+    price_x, price_y, _ = prices()
+    pair.update_price(price_x, price_y)
+    assert (pair.mult_x, pair.mult_y) == (150_000, 1_000_000)
+    target_y = pair.compute_target_y()
+    assert target_y == 150_000_000_000
+    pair.update_target_y(target_y)
+    assert pair.big_k == 15 * 10**26
+
+    big_k, current_x_k, current_y_k, available_x, available_y = pair.get_pool_values_for_quoting()
+    assert (big_k, current_x_k, current_y_k) == (pair.big_k, 10**14, 15 * 10**12)
+    assert (available_x, available_y) == (10**12, 150_000_000_000)
+
+    # 1 SOL at $150 and $150 of SOL, with a 0.25% fee
+    assert pair.quote_x_to_y(10**9).output_after_fee == 149_623_505
+    assert pair.quote_y_to_x(150_000_000).out_amount == 997_490_026
+
+    for size in SIZES:
+        for quote, x_to_y in [(pair.quote_x_to_y, True), (pair.quote_y_to_x, False)]:
+            expected = v3_reference(pair, size, x_to_y)
+            if expected is None:
+                with pytest.raises(ValueError, match="InsufficientActive"):
+                    quote(size)
+            else:
+                assert as_dict(quote(size)) == expected
+
+
+@pytest.mark.skipif(
+    not (FIXTURES / "larix_reserve.bin").exists(),
+    reason="written by `cargo test -p obric-v3-sdk test_python_fixtures`",
+)
+def test_larix_reserve():
+    reserve = obric.LarixReserve.decode(fixture("larix_reserve.bin"))
+    assert reserve.lending_market == b58(bytes([21]) * 32)
+    assert (reserve.liquidity_mint, reserve.liquidity_supply) == (
+        b58(bytes([22]) * 32),
+        b58(bytes([23]) * 32),
+    )
+    assert (reserve.collateral_mint, reserve.collateral_supply) == (
+        b58(bytes([24]) * 32),
+        b58(bytes([25]) * 32),
+    )
+    # 1000 SOL available, 500 borrowed and 1 of unclaimed fees for 1400 cSOL
+    assert reserve.available_amount == 1_000_000_000_000
+    assert reserve.borrowed_amount_wads == 500_000_000_000 * WAD
+    assert reserve.owner_unclaimed_wads == 1_000_000_000 * WAD
+    assert reserve.mint_total_supply == 1_400_000_000_000
+    total_supply = (
+        reserve.available_amount * WAD + reserve.borrowed_amount_wads - reserve.owner_unclaimed_wads
+    )
+    rate = reserve.ctoken_exchange_rate_wads()
+    assert rate == total_supply // reserve.mint_total_supply == 1_070_714_285_714_285_714
+
+
+def test_larix_reserve_rejects_other_accounts():
+    with pytest.raises(ValueError):
+        obric.LarixReserve.decode(fixture("v3_pool.bin"))
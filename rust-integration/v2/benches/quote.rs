@@ -0,0 +1,101 @@
+use anchor_lang::AccountSerialize;
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use jupiter_amm_interface::{Amm, KeyedAccount, QuoteParams};
+use obric_sdk_common::fixtures::{clock_account, mint_account, price_account, token_account};
+use obric_solana::state::{Amount, SSTradingPair};
+use obric_v2_sdk::constants::PROGRAM_ID;
+use obric_v2_sdk::obric_v2_amm::ObricV2Amm;
+use obric_v2_sdk::token::MintCache;
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+
+/*
+Quotes per second on an offline $150 / $1 pool with 6 decimals on both sides:
+- `state`: the accounts crate quote, which places the reserves on the curve (integer `sqrt` of
+  `big_k * mult_y / mult_x`) on every call, as `quote` did before the curve point was kept
+- `amm`: `Amm::quote` with a new amount each time, from the curve point computed in `update`
+*/
+
+fn updated_pool() -> ObricV2Amm {
+    let target_x_k = 1_000_000_000u128 * 100;
+    let state = SSTradingPair {
+        is_initialized: true,
+        x_price_feed_id: Pubkey::new_unique(),
+        y_price_feed_id: Pubkey::new_unique(),
+        reserve_x: Pubkey::new_unique(),
+        reserve_y: Pubkey::new_unique(),
+        mint_x: Pubkey::new_unique(),
+        mint_y: Pubkey::new_unique(),
+        concentration: 100,
+        target_x: 1_000_000_000,
+        // mult_x / mult_y as `update` sets them
+        big_k: target_x_k * target_x_k * 150_000 / 1_000,
+        fee_millionth: 1_000,
+        rebate_percentage: 50,
+        protocol_fee_share_thousandth: 200,
+        ..SSTradingPair::default()
+    };
+    let accounts_map = HashMap::from([
+        (state.mint_x, mint_account(6)),
+        (state.mint_y, mint_account(6)),
+        (state.reserve_x, token_account(state.mint_x, 900_000_000)),
+        (
+            state.reserve_y,
+            token_account(state.mint_y, 165_000_000_000),
+        ),
+        (state.x_price_feed_id, price_account(150_00000000)),
+        (state.y_price_feed_id, price_account(1_00000000)),
+        (solana_sdk::sysvar::clock::ID, clock_account(0)),
+    ]);
+
+    let mut data = vec![];
+    state.try_serialize(&mut data).unwrap();
+    let keyed_account = KeyedAccount {
+        key: Pubkey::new_unique(),
+        account: Account {
+            data,
+            owner: PROGRAM_ID,
+            ..Account::default()
+        },
+        params: None,
+    };
+    let mut amm = ObricV2Amm::from_keyed_account(&keyed_account)
+        .unwrap()
+        .with_mint_cache(MintCache::default());
+    amm.update(&accounts_map).unwrap();
+    amm
+}
+
+fn quote(c: &mut Criterion) {
+    let amm = updated_pool();
+    let (current_x, current_y) = amm.current_reserves();
+    let (current_x, current_y) = (Amount::new(current_x), Amount::new(current_y));
+    let params = |in_amount| QuoteParams {
+        input_mint: amm.state.mint_x,
+        in_amount,
+        output_mint: amm.state.mint_y,
+    };
+
+    let mut group = c.benchmark_group("quote");
+    group.throughput(Throughput::Elements(1));
+    let mut in_amount = 1_000_000;
+    group.bench_function("state", |b| {
+        b.iter(|| {
+            in_amount += 1;
+            amm.state
+                .quote_x_to_y_breakdown(Amount::new(black_box(in_amount)), current_x, current_y)
+                .unwrap()
+        })
+    });
+    group.bench_function("amm", |b| {
+        b.iter(|| {
+            in_amount += 1;
+            amm.quote(&params(black_box(in_amount))).unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, quote);
+criterion_main!(benches);
@@ -0,0 +1,33 @@
+use crate::obric_v2_amm::ObricV2Amm;
+pub use obric_sdk_common::analytics::{
+    estimate_fees, AnalyticsSource, PoolAnalytics, VolumeBucket,
+};
+
+impl AnalyticsSource for ObricV2Amm {
+    fn cumulative_volume(&self) -> u64 {
+        self.state.cumulative_volume
+    }
+
+    fn volume_records(&self) -> (&[u64], &[i64]) {
+        (&self.state.volume_record, &self.state.volume_time_record)
+    }
+
+    fn fee_millionth(&self) -> u64 {
+        self.state.fee_millionth
+    }
+
+    fn protocol_fee_share_thousandth(&self) -> u64 {
+        self.state.protocol_fee_share_thousandth
+    }
+
+    /// The reserves
+    fn pool_value(&self) -> u64 {
+        let state = &self.state;
+        if state.mult_y == 0 {
+            return 0;
+        }
+        let (current_x, current_y) = self.current_reserves();
+        let x_value = current_x as u128 * state.mult_x as u128 / state.mult_y as u128;
+        u64::try_from(x_value + current_y as u128).unwrap_or(u64::MAX)
+    }
+}
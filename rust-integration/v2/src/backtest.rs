@@ -0,0 +1,123 @@
+use anyhow::Result;
+use obric_sdk_common::backtest::{check_series, events, Event};
+pub use obric_sdk_common::backtest::{parse_prices_csv, parse_trades_csv, PricePoint, Trade};
+use obric_solana::state::{Amount, SSTradingPair};
+
+/*
+Offline replay of an oracle price series and a trade flow through the v2 curve, see
+`obric_sdk_common::backtest` for the inputs. Every value is reported in raw units of Y at the
+oracle price of the moment.
+*/
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BacktestReport {
+    pub trades_executed: u64,
+    /// Trades the pool could not fill
+    pub trades_skipped: u64,
+    pub initial_value: u64,
+    pub final_value: u64,
+    /// Initial reserves valued at the final prices
+    pub hold_value: u64,
+    pub pnl_vs_hold: i128,
+    pub lp_fee_income: u64,
+    pub protocol_fee_income: u64,
+    pub rebate_paid: u64,
+    pub final_x: u64,
+    pub final_y: u64,
+    /// `current_x - target_x` at the end, and its largest absolute value along the way
+    pub final_drift_x: i128,
+    pub max_drift_x: u128,
+}
+
+#[derive(Clone)]
+pub struct Backtest {
+    pub state: SSTradingPair,
+    pub x_decimals: u8,
+    pub y_decimals: u8,
+    pub current_x: u64,
+    pub current_y: u64,
+}
+
+impl Backtest {
+    fn value(&self, x: u64, y: u64) -> u64 {
+        (x as u128 * self.state.mult_x as u128 / self.state.mult_y as u128) as u64 + y
+    }
+
+    fn x_in_y(&self, x: u64) -> u64 {
+        self.value(x, 0)
+    }
+
+    fn drift_x(&self) -> i128 {
+        self.current_x as i128 - self.state.target_x as i128
+    }
+
+    /// Replays `prices` and `trades`, both sorted by timestamp. The first price
+    /// must not come after the first trade.
+    pub fn run(&mut self, prices: &[PricePoint], trades: &[Trade]) -> Result<BacktestReport> {
+        check_series(prices, trades)?;
+
+        let mut report = BacktestReport::default();
+        let (initial_x, initial_y) = (self.current_x, self.current_y);
+        for event in events(prices, trades) {
+            match event {
+                Event::Price(index, price) => {
+                    let (price_x, price_y) = price.normalized()?;
+                    self.state
+                        .update_price(price_x, price_y, self.x_decimals, self.y_decimals)?;
+                    if index == 0 {
+                        report.initial_value = self.value(initial_x, initial_y);
+                    }
+                }
+                Event::Trade(trade) => {
+                    self.swap(trade, &mut report)?;
+                    report.max_drift_x = report.max_drift_x.max(self.drift_x().unsigned_abs());
+                }
+            }
+        }
+
+        report.final_value = self.value(self.current_x, self.current_y);
+        report.hold_value = self.value(initial_x, initial_y);
+        report.pnl_vs_hold = report.final_value as i128 - report.hold_value as i128;
+        report.final_x = self.current_x;
+        report.final_y = self.current_y;
+        report.final_drift_x = self.drift_x();
+        Ok(report)
+    }
+
+    fn swap(&mut self, trade: &Trade, report: &mut BacktestReport) -> Result<()> {
+        let (current_x, current_y) = (Amount::new(self.current_x), Amount::new(self.current_y));
+        let breakdown = if trade.is_x_to_y {
+            self.state
+                .quote_x_to_y_breakdown(Amount::new(trade.in_amount), current_x, current_y)?
+        } else {
+            self.state
+                .quote_y_to_x_breakdown(Amount::new(trade.in_amount), current_x, current_y)?
+        };
+        if breakdown.output_after_fee == 0 {
+            report.trades_skipped += 1;
+            return Ok(());
+        }
+
+        // the LP fee stays in the reserve, the protocol fee leaves it with the output
+        let reserve_out = breakdown.output_after_fee + breakdown.protocol_fee;
+        let (lp_fee, protocol_fee, rebate) = if trade.is_x_to_y {
+            self.current_x += trade.in_amount;
+            self.current_y -= reserve_out;
+            (breakdown.lp_fee, breakdown.protocol_fee, breakdown.rebate)
+        } else {
+            self.current_y += trade.in_amount;
+            self.current_x -= reserve_out;
+            (
+                self.x_in_y(breakdown.lp_fee),
+                self.x_in_y(breakdown.protocol_fee),
+                self.x_in_y(breakdown.rebate),
+            )
+        };
+        report.trades_executed += 1;
+        report.lp_fee_income += lp_fee;
+        report.protocol_fee_income += protocol_fee;
+        report.rebate_paid += rebate;
+        Ok(())
+    }
+}
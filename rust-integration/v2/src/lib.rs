@@ -1,5 +1,12 @@
+pub mod analytics;
+pub mod backtest;
 pub mod constants;
+pub use obric_sdk_common::error;
 pub mod obric_v2_amm;
+pub mod simulation;
+pub mod snapshot;
+pub mod sweep;
+pub mod token;
 
 #[cfg(test)]
 pub mod test_harness;
@@ -2,25 +2,247 @@ use crate::constants::{PROGRAM_ID};
 use anchor_lang::AccountDeserialize;
 use anchor_spl::token::spl_token::solana_program::instruction::AccountMeta;
 use anchor_spl::token::spl_token::solana_program::pubkey::Pubkey;
-use anchor_spl::token::{Mint, TokenAccount};
 use anyhow::Result;
 use jupiter_amm_interface::{
     Amm, KeyedAccount, Quote, QuoteParams, Swap, SwapAndAccountMetas, SwapParams,
 };
 use obric_solana::state::PriceFeed;
 use obric_solana::state::SSTradingPair;
-use solana_sdk::account::Account;
+use obric_solana::state::CurvePoint;
+use obric_solana::state::Amount;
+pub use obric_solana::state::QuoteBreakdown;
+use solana_sdk::account::{from_account, Account};
+use solana_sdk::clock::Clock;
+use solana_sdk::sysvar;
 use std::collections::HashMap;
-use obric_solana::errors::AmmError;
+use obric_math::MathError;
+use crate::error::ObricSdkError;
+use crate::token::{unpack_token_amount, MintCache, MintInfo};
 
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct ObricV2Amm {
     key: Pubkey,
     pub state: SSTradingPair,
     current_x: u64,
     current_y: u64,
-    pub x_decimals: u8,
-    pub y_decimals: u8,
+    /// `None` until the mint has been loaded by `update`
+    pub x_mint_info: Option<MintInfo>,
+    pub y_mint_info: Option<MintInfo>,
+    mint_cache: MintCache,
+    /// `None` takes prices of any age
+    max_price_age: Option<u64>,
+    epoch: u64,
+    /// Epoch the mint infos were read at, they are read again in a later one
+    mints_epoch: u64,
+    /// Computed by `update`, used while the state it was computed at is unchanged
+    curve_point: Option<(StateVersion, CurvePoint)>,
+}
+
+/// Everything a quote depends on besides its input. Quotes compare it rather
+/// than a counter, so changes made directly to `state` are picked up too.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct StateVersion {
+    current_x: u64,
+    current_y: u64,
+    mult_x: u64,
+    mult_y: u64,
+    big_k: u128,
+    target_x: u64,
+    fee_millionth: u64,
+    rebate_percentage: u64,
+    protocol_fee_share_thousandth: u64,
+}
+
+impl ObricV2Amm {
+    /// Shares `mint_cache` instead of the process-wide `MintCache::global()`.
+    pub fn with_mint_cache(mut self, mint_cache: MintCache) -> Self {
+        self.mint_cache = mint_cache;
+        self
+    }
+
+    /// Fails `update` with `StaleOracle` on prices published more than
+    /// `seconds` from the clock, `MAX_PRICE_AGE` is a reasonable limit.
+    pub fn with_max_price_age(mut self, seconds: u64) -> Self {
+        self.max_price_age = Some(seconds);
+        self
+    }
+
+    /// Splits the quote for `quote_params` into fee, rebate, protocol and LP shares.
+    pub fn quote_breakdown(
+        &self,
+        quote_params: &QuoteParams,
+    ) -> std::result::Result<QuoteBreakdown, ObricSdkError> {
+        if self.x_mint_info.is_none() || self.y_mint_info.is_none() {
+            return Err(self.not_loaded());
+        }
+        let in_amount = quote_params.in_amount;
+        let is_x_to_y = if quote_params.input_mint.eq(&self.state.mint_x) {
+            true
+        } else if quote_params.input_mint.eq(&self.state.mint_y) {
+            false
+        } else {
+            return Err(ObricSdkError::UnsupportedMint {
+                pool: self.key,
+                mint: quote_params.input_mint,
+            });
+        };
+        if in_amount == 0 {
+            return Ok(QuoteBreakdown::default());
+        }
+        let version = self.state_version();
+        let curve = self.state.curve();
+        let point = match self.curve_point {
+            Some((point_version, point)) if point_version == version => Ok(point),
+            _ => curve.curve_point(Amount::new(self.current_x), Amount::new(self.current_y)),
+        };
+        point
+            .and_then(|point| match is_x_to_y {
+                true => curve.quote_x_to_y_at(&point, Amount::new(in_amount)),
+                false => curve.quote_y_to_x_at(&point, Amount::new(in_amount)),
+            })
+            .map_err(|source| {
+                ObricSdkError::from_quote(self.key, quote_params.input_mint, in_amount, source)
+            })
+    }
+
+    fn not_loaded(&self) -> ObricSdkError {
+        ObricSdkError::PoolNotLoaded {
+            pool: self.key,
+            what: "its mints and reserves",
+        }
+    }
+
+    fn account<'a>(
+        &self,
+        accounts_map: &'a HashMap<Pubkey, Account>,
+        account: &Pubkey,
+    ) -> std::result::Result<&'a Account, ObricSdkError> {
+        accounts_map
+            .get(account)
+            .ok_or(ObricSdkError::AccountNotFound {
+                pool: self.key,
+                account: *account,
+            })
+    }
+
+    fn deserialization_error<E: Into<Box<dyn std::error::Error + Send + Sync>>>(
+        &self,
+        account: Pubkey,
+        kind: &'static str,
+    ) -> impl FnOnce(E) -> ObricSdkError {
+        let pool = self.key;
+        move |source| ObricSdkError::Deserialization {
+            pool,
+            account,
+            kind,
+            source: source.into(),
+        }
+    }
+
+    /// Metadata of `mint`, and whether it was read in the current epoch. Mints are
+    /// read again once the epoch moves on, from the cache or `accounts_map`; until
+    /// `get_accounts_to_update` has asked for them, the earlier epoch's is kept.
+    fn load_mint(
+        &self,
+        mint: &Pubkey,
+        current: Option<MintInfo>,
+        accounts_map: &HashMap<Pubkey, Account>,
+    ) -> std::result::Result<(MintInfo, bool), ObricSdkError> {
+        if let Some(info) = current.filter(|_| self.mints_epoch == self.epoch) {
+            return Ok((info, true));
+        }
+        let cached = self.mint_cache.get(mint);
+        if let Some((read_at, info)) = cached {
+            if read_at == self.epoch {
+                return Ok((info, true));
+            }
+        }
+        if !accounts_map.contains_key(mint) {
+            if let Some(info) = current.or(cached.map(|(_, info)| info)) {
+                return Ok((info, false));
+            }
+        }
+        let account = self.account(accounts_map, mint)?;
+        let info = MintInfo::unpack(&account.data, &account.owner)
+            .map_err(self.deserialization_error(*mint, "mint"))?;
+        self.mint_cache.insert(*mint, self.epoch, info);
+        Ok((info, true))
+    }
+
+    /// The price account of `oracle`, if its price was published within
+    /// `max_price_age` of `now`.
+    fn price_feed(
+        &self,
+        accounts_map: &HashMap<Pubkey, Account>,
+        oracle: &Pubkey,
+        now: i64,
+    ) -> std::result::Result<PriceFeed, ObricSdkError> {
+        let data = &mut &self.account(accounts_map, oracle)?.data[..];
+        let feed = PriceFeed::try_deserialize(data)
+            .map_err(self.deserialization_error(*oracle, "price account"))?;
+        if let Some(max_age) = self.max_price_age {
+            let publish_time = feed.get_price_unchecked().publish_time;
+            ObricSdkError::check_price_age(self.key, *oracle, publish_time, now, max_age)?;
+        }
+        Ok(feed)
+    }
+
+    fn state_version(&self) -> StateVersion {
+        StateVersion {
+            current_x: self.current_x,
+            current_y: self.current_y,
+            mult_x: self.state.mult_x,
+            mult_y: self.state.mult_y,
+            big_k: self.state.big_k,
+            target_x: self.state.target_x,
+            fee_millionth: self.state.fee_millionth,
+            rebate_percentage: self.state.rebate_percentage,
+            protocol_fee_share_thousandth: self.state.protocol_fee_share_thousandth,
+        }
+    }
+
+    /// The curve point computed by the last `update`, `None` once the pool has
+    /// changed since or when the curve could not place it.
+    pub fn curve_point(&self) -> Option<CurvePoint> {
+        self.curve_point
+            .filter(|(version, _)| version.eq(&self.state_version()))
+            .map(|(_, point)| point)
+    }
+
+    /// Reserve balances as of the last `update`.
+    pub fn current_reserves(&self) -> (u64, u64) {
+        (self.current_x, self.current_y)
+    }
+
+    pub fn x_decimals(&self) -> Option<u8> {
+        self.x_mint_info.map(|info| info.decimals)
+    }
+
+    pub fn y_decimals(&self) -> Option<u8> {
+        self.y_mint_info.map(|info| info.decimals)
+    }
+
+    /// Metadata of `mint` as of the last `update`, `None` for a mint the pool does not trade.
+    pub fn mint_info(&self, mint: &Pubkey) -> Option<MintInfo> {
+        if mint.eq(&self.state.mint_x) {
+            self.x_mint_info
+        } else if mint.eq(&self.state.mint_y) {
+            self.y_mint_info
+        } else {
+            None
+        }
+    }
+
+    /// Epoch of the clock read by the last `update`, mints are read again once it moves on.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn token_program(&self, mint: &Pubkey) -> Pubkey {
+        self.mint_info(mint)
+            .map_or(anchor_spl::token::spl_token::id(), |info| info.token_program)
+    }
 }
 
 impl Amm for ObricV2Amm {
@@ -37,25 +259,29 @@ impl Amm for ObricV2Amm {
     }
 
     fn get_accounts_to_update(&self) -> Vec<Pubkey> {
-        if self.x_decimals == 0 && self.y_decimals == 0 {
-            return [
-                self.state.reserve_x,
-                self.state.reserve_y,
-                self.state.x_price_feed_id,
-                self.state.y_price_feed_id,
-                self.state.mint_x,
-                self.state.mint_y,
-            ]
-            .to_vec();
-        } else {
-            return [
-                self.state.reserve_x,
-                self.state.reserve_y,
-                self.state.x_price_feed_id,
-                self.state.y_price_feed_id,
-            ]
-            .to_vec();
+        let mut accounts = vec![
+            self.state.reserve_x,
+            self.state.reserve_y,
+            self.state.x_price_feed_id,
+            self.state.y_price_feed_id,
+        ];
+        for (mint, info) in [
+            (self.state.mint_x, self.x_mint_info),
+            (self.state.mint_y, self.y_mint_info),
+        ] {
+            // a pool that has not updated yet takes the cached mint of any epoch
+            let read = info.is_some() && self.mints_epoch == self.epoch;
+            let cached = self
+                .mint_cache
+                .get(&mint)
+                .is_some_and(|(read_at, _)| read_at >= self.epoch);
+            if !read && !cached {
+                accounts.push(mint);
+            }
         }
+        // the epoch dates the mints, the timestamp the prices
+        accounts.push(sysvar::clock::ID);
+        accounts
     }
 
     fn has_dynamic_accounts(&self) -> bool {
@@ -63,46 +289,70 @@ impl Amm for ObricV2Amm {
     }
 
     fn update(&mut self, accounts_map: &HashMap<Pubkey, Account>) -> Result<()> {
-        let reserve_x_data = &mut &accounts_map.get(&self.state.reserve_x).ok_or(AmmError::AccountNotFound)?.data[..];
-        let reserve_y_data = &mut &accounts_map.get(&self.state.reserve_x).ok_or(AmmError::AccountNotFound)?.data[..];
-        let reserve_x_token_account = &TokenAccount::try_deserialize(reserve_x_data)?;
-        let reserve_y_token_account = &TokenAccount::try_deserialize(reserve_y_data)?;
-        self.current_x = reserve_x_token_account.amount;
-        self.current_y = reserve_y_token_account.amount;
-
-        if self.x_decimals == 0 && self.y_decimals == 0 {
-            let mint_x_data = &mut &accounts_map.get(&self.state.mint_x).ok_or(AmmError::AccountNotFound)?.data[..];
-            let min_x = &Mint::try_deserialize(mint_x_data)?;
-
-            let mint_y_data = &mut &accounts_map.get(&self.state.mint_y).ok_or(AmmError::AccountNotFound)?.data[..];
-            let min_y = &Mint::try_deserialize(mint_y_data)?;
-
-            self.x_decimals = min_x.decimals;
-            self.y_decimals = min_y.decimals;
+        let (reserve_x, reserve_y) = (self.state.reserve_x, self.state.reserve_y);
+        let (account_x, account_y) = (
+            self.account(accounts_map, &reserve_x)?,
+            self.account(accounts_map, &reserve_y)?,
+        );
+        self.current_x = unpack_token_amount(&account_x.data, &account_x.owner)
+            .map_err(self.deserialization_error(reserve_x, "token account"))?;
+        self.current_y = unpack_token_amount(&account_y.data, &account_y.owner)
+            .map_err(self.deserialization_error(reserve_y, "token account"))?;
+
+        let clock: Clock = from_account(self.account(accounts_map, &sysvar::clock::ID)?)
+            .ok_or_else(|| ObricSdkError::Deserialization {
+                pool: self.key,
+                account: sysvar::clock::ID,
+                kind: "clock",
+                source: "not a clock sysvar".into(),
+            })?;
+        self.epoch = clock.epoch;
+
+        let (mint_x, x_read) = self.load_mint(&self.state.mint_x, self.x_mint_info, accounts_map)?;
+        let (mint_y, y_read) = self.load_mint(&self.state.mint_y, self.y_mint_info, accounts_map)?;
+        // the program's `Swap` takes `Program<Token>` and SPL Token accounts, so a
+        // pool with a Token-2022 mint is never loaded rather than quoted unswappable
+        for (mint, info) in [(self.state.mint_x, mint_x), (self.state.mint_y, mint_y)] {
+            if info.token_program != anchor_spl::token::spl_token::ID {
+                return Err(ObricSdkError::UnsupportedTokenProgram {
+                    pool: self.key,
+                    mint,
+                    token_program: info.token_program,
+                }
+                .into());
+            }
+        }
+        self.x_mint_info = Some(mint_x);
+        self.y_mint_info = Some(mint_y);
+        if x_read && y_read {
+            self.mints_epoch = self.epoch;
         }
 
-        let price_x_data = &mut &accounts_map.get(&self.state.x_price_feed_id).ok_or(AmmError::AccountNotFound)?.data[..];
-        let price_y_data = &mut &accounts_map.get(&self.state.y_price_feed_id).ok_or(AmmError::AccountNotFound)?.data[..];
-        let price_x_fee = &PriceFeed::try_deserialize(price_x_data)?;
-        let price_y_fee = &PriceFeed::try_deserialize(price_y_data)?;
-        let price_x = price_x_fee.price_normalized()?.price as u64;
-        let price_y = price_y_fee.price_normalized()?.price as u64;
+        let (oracle_x, oracle_y) = (self.state.x_price_feed_id, self.state.y_price_feed_id);
+        let price_x = self.price_feed(accounts_map, &oracle_x, clock.unix_timestamp)?;
+        let price_y = self.price_feed(accounts_map, &oracle_y, clock.unix_timestamp)?;
+        let (price_x, price_y, _) = PriceFeed::normalized_pair(&price_x, &price_y)
+            .map_err(|err| ObricSdkError::from_price(self.key, oracle_x, oracle_y, err))?;
+        // only fails when the decimals scale a price past u64
         self.state
-            .update_price(price_x, price_y, self.x_decimals, self.y_decimals)?;
+            .update_price(price_x, price_y, mint_x.decimals, mint_y.decimals)
+            .map_err(|_| ObricSdkError::Math {
+                pool: self.key,
+                source: MathError::Overflow,
+            })?;
+        // a pool the curve cannot place fails its quotes instead
+        self.curve_point = self
+            .state
+            .curve_point(Amount::new(self.current_x), Amount::new(self.current_y))
+            .ok()
+            .map(|point| (self.state_version(), point));
         Ok(())
     }
 
     fn quote(&self, quote_params: &QuoteParams) -> Result<Quote> {
-        let (output_after_fee, protocol_fee, lp_fee) =
-            if quote_params.input_mint.eq(&self.state.mint_x) {
-                self.state
-                    .quote_x_to_y(quote_params.in_amount, self.current_x, self.current_y)?
-            } else if quote_params.input_mint.eq(&self.state.mint_y) {
-                self.state
-                    .quote_y_to_x(quote_params.in_amount, self.current_x, self.current_y)?
-            } else {
-                (0u64, 0u64, 0u64)
-            };
+        let breakdown = self.quote_breakdown(quote_params)?;
+        let output_after_fee = breakdown.output_after_fee;
+        let (protocol_fee, lp_fee) = (breakdown.protocol_fee, breakdown.lp_fee);
         if output_after_fee == 0 {
             Ok(Quote {
                 not_enough_liquidity: true,
@@ -119,38 +369,7 @@ impl Amm for ObricV2Amm {
     }
 
     fn clone_amm(&self) -> Box<dyn Amm + Send + Sync> {
-        let state = &self.state;
-        Box::new(Self {
-            key: self.key,
-            state: SSTradingPair {
-                is_initialized: state.is_initialized,
-                x_price_feed_id: state.x_price_feed_id,
-                y_price_feed_id: state.y_price_feed_id,
-                reserve_x: state.reserve_x,
-                reserve_y: state.reserve_y,
-                protocol_fee_x: state.protocol_fee_x,
-                protocol_fee_y: state.protocol_fee_y,
-                bump: state.bump,
-                mint_x: state.mint_x,
-                mint_y: state.mint_y,
-                concentration: state.concentration,
-                big_k: state.big_k,
-                target_x: state.target_x,
-                cumulative_volume: state.cumulative_volume,
-                mult_x: state.mult_x,
-                mult_y: state.mult_y,
-                fee_millionth: state.fee_millionth,
-                rebate_percentage: state.rebate_percentage,
-                protocol_fee_share_thousandth: state.protocol_fee_share_thousandth,
-                volume_record: state.volume_record,
-                volume_time_record: state.volume_time_record,
-                padding: state.padding,
-            },
-            current_x: self.current_x,
-            current_y: self.current_y,
-            x_decimals: self.x_decimals,
-            y_decimals: self.y_decimals,
-        })
+        Box::new(self.clone())
     }
 
     fn from_keyed_account(keyed_account: &KeyedAccount) -> Result<Self>
@@ -158,14 +377,25 @@ impl Amm for ObricV2Amm {
         Self: Sized,
     {
         let data = &mut &keyed_account.account.data.clone()[0..];
-        let ss_trading_pair = SSTradingPair::try_deserialize(data)?;
+        let ss_trading_pair =
+            SSTradingPair::try_deserialize(data).map_err(|source| ObricSdkError::Deserialization {
+                pool: keyed_account.key,
+                account: keyed_account.key,
+                kind: "v2 SSTradingPair",
+                source: source.into(),
+            })?;
         Ok(Self {
             key: keyed_account.key,
             state: ss_trading_pair,
             current_x: 0u64,
             current_y: 0u64,
-            x_decimals: 0u8,
-            y_decimals: 0u8,
+            x_mint_info: None,
+            y_mint_info: None,
+            mint_cache: MintCache::global(),
+            max_price_age: None,
+            epoch: 0u64,
+            mints_epoch: 0u64,
+            curve_point: None,
         })
     }
 
@@ -181,13 +411,22 @@ impl Amm for ObricV2Amm {
                     swap_params.destination_token_account,
                     self.state.protocol_fee_y,
                 )
-            } else {
+            } else if swap_params.source_mint.eq(&self.state.mint_y) {
                 (
                     swap_params.destination_token_account,
                     swap_params.source_token_account,
                     self.state.protocol_fee_x,
                 )
+            } else {
+                return Err(ObricSdkError::UnsupportedMint {
+                    pool: self.key,
+                    mint: swap_params.source_mint,
+                }
+                .into());
             };
+        if self.x_mint_info.is_none() || self.y_mint_info.is_none() {
+            return Err(self.not_loaded().into());
+        }
 
         Ok(SwapAndAccountMetas {
             swap: Swap::Saber,
@@ -203,7 +442,7 @@ impl Amm for ObricV2Amm {
                 AccountMeta::new_readonly(self.state.x_price_feed_id, false),
                 AccountMeta::new_readonly(self.state.y_price_feed_id, false),
                 AccountMeta::new_readonly(swap_params.token_transfer_authority, true),
-                AccountMeta::new_readonly(anchor_spl::token::spl_token::id(), false),
+                AccountMeta::new_readonly(anchor_spl::token::spl_token::ID, false),
             ],
         })
     }
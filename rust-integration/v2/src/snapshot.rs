@@ -0,0 +1,110 @@
+use crate::analytics::PoolAnalytics;
+use crate::error::ObricSdkError;
+use crate::obric_v2_amm::ObricV2Amm;
+use anchor_lang::AccountDeserialize;
+use jupiter_amm_interface::Amm;
+use obric_solana::state::{Amount, PriceFeed, SSTradingPair};
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+
+/// Decoded pool state together with the values the curve derives from it, as
+/// of one `update`. With the `serde` feature it serializes to JSON with
+/// pubkeys in base58, so snapshots can be stored and diffed.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PoolSnapshot {
+    #[cfg_attr(feature = "serde", serde(with = "obric_solana::serde_pubkey"))]
+    pub pool: Pubkey,
+    pub state: SSTradingPair,
+    /// Oracle prices as fed to `update_price`, `price_x * 10^price_expo` dollars
+    pub price_x: u64,
+    pub price_y: u64,
+    pub price_expo: i32,
+    pub x_decimals: u8,
+    pub y_decimals: u8,
+    pub current_x: u64,
+    pub current_y: u64,
+    pub target_x: u64,
+    pub target_y: u64,
+    pub big_k: u128,
+    /// Y per X in UI units at the current point of the curve, before fees
+    pub marginal_price: f64,
+    /// Reserve balances. A swap's output before fees must stay below the
+    /// reserve it is paid from, and the curve can cap it lower still
+    pub available_x: u64,
+    pub available_y: u64,
+    pub analytics: PoolAnalytics,
+}
+
+impl PoolSnapshot {
+    /// `accounts_map` holds the accounts last passed to `amm.update`, `now` is
+    /// the unix time the volume analytics are measured up to.
+    pub fn new(
+        amm: &ObricV2Amm,
+        accounts_map: &HashMap<Pubkey, Account>,
+        now: i64,
+    ) -> Result<Self, ObricSdkError> {
+        let (pool, state) = (amm.key(), &amm.state);
+        let (Some(x_decimals), Some(y_decimals)) = (amm.x_decimals(), amm.y_decimals()) else {
+            return Err(ObricSdkError::PoolNotLoaded {
+                pool,
+                what: "its mints and reserves",
+            });
+        };
+        let price_feed = |oracle: Pubkey| {
+            let account = accounts_map
+                .get(&oracle)
+                .ok_or(ObricSdkError::AccountNotFound {
+                    pool,
+                    account: oracle,
+                })?;
+            PriceFeed::try_deserialize(&mut &account.data[..]).map_err(|source| {
+                ObricSdkError::Deserialization {
+                    pool,
+                    account: oracle,
+                    kind: "price account",
+                    source: source.into(),
+                }
+            })
+        };
+        let (oracle_x, oracle_y) = (state.x_price_feed_id, state.y_price_feed_id);
+        let (price_x, price_y, price_expo) =
+            PriceFeed::normalized_pair(&price_feed(oracle_x)?, &price_feed(oracle_y)?)
+                .map_err(|err| ObricSdkError::from_price(pool, oracle_x, oracle_y, err))?;
+
+        let (current_x, current_y) = amm.current_reserves();
+        let (target_x, target_y) = state
+            .curve()
+            .target_xy(Amount::new(current_x), Amount::new(current_y))
+            .map_err(|source| ObricSdkError::Math { pool, source })?;
+        let (target_x, target_y) = (target_x.get(), target_y.get());
+
+        // same walk as `quote_x_to_y`, in floating point
+        let big_k = state.big_k as f64;
+        let target_x_k = (big_k * state.mult_y as f64 / state.mult_x as f64).sqrt();
+        let current_x_k = target_x_k - target_x as f64 + current_x as f64;
+        let current_y_k = big_k / current_x_k;
+        let marginal_price =
+            current_y_k / current_x_k * 10f64.powi(i32::from(x_decimals) - i32::from(y_decimals));
+
+        Ok(Self {
+            pool,
+            state: state.clone(),
+            price_x: price_x.get(),
+            price_y: price_y.get(),
+            price_expo,
+            x_decimals,
+            y_decimals,
+            current_x,
+            current_y,
+            target_x,
+            target_y,
+            big_k: state.big_k,
+            marginal_price,
+            available_x: current_x,
+            available_y: current_y,
+            analytics: PoolAnalytics::new(amm, now),
+        })
+    }
+}
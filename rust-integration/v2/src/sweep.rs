@@ -0,0 +1,76 @@
+use crate::backtest::{Backtest, PricePoint, Trade};
+use anyhow::{anyhow, Result};
+use obric_sdk_common::sweep::slippage_bps;
+pub use obric_sdk_common::sweep::{format_table, synthetic_trades, SweepRanges, SweepResult};
+use obric_solana::state::Amount;
+
+/*
+Parameter sweep over the backtest: every combination of the ranges is replayed against the same
+prices and trades. `big_k` is rebuilt from `target_x` and the first price for each concentration,
+the way a pool is initialized.
+*/
+
+/// Replays `prices` and `trades` from `base` for every combination of `ranges`.
+/// `sizes_x` and `sizes_y` are the standard trade sizes slippage is reported at.
+pub fn sweep(
+    base: &Backtest,
+    prices: &[PricePoint],
+    trades: &[Trade],
+    ranges: &SweepRanges,
+    sizes_x: &[u64],
+    sizes_y: &[u64],
+) -> Result<Vec<SweepResult>> {
+    let (price_x, price_y) = prices
+        .first()
+        .ok_or(anyhow!("empty price series"))?
+        .normalized()?;
+    let mut results = vec![];
+    for &concentration in &ranges.concentration {
+        for &fee_millionth in &ranges.fee_millionth {
+            for &rebate_percentage in &ranges.rebate_percentage {
+                for &protocol_fee_share_thousandth in &ranges.protocol_fee_share_thousandth {
+                    let mut backtest = base.clone();
+                    let state = &mut backtest.state;
+                    state.concentration = concentration;
+                    state.fee_millionth = fee_millionth;
+                    state.rebate_percentage = rebate_percentage;
+                    state.protocol_fee_share_thousandth = protocol_fee_share_thousandth;
+                    state.update_price(price_x, price_y, base.x_decimals, base.y_decimals)?;
+                    let target_x_k = state.target_x as u128 * concentration as u128;
+                    state.big_k =
+                        target_x_k * target_x_k * state.mult_x as u128 / state.mult_y as u128;
+
+                    let (mult_x, mult_y) = (state.mult_x as u128, state.mult_y as u128);
+                    let current_x = Amount::new(backtest.current_x);
+                    let current_y = Amount::new(backtest.current_y);
+                    let mut slippage_x_to_y_bps = vec![];
+                    for &size in sizes_x {
+                        let input_x = Amount::new(size);
+                        let (out, _, _) = state.quote_x_to_y(input_x, current_x, current_y)?;
+                        slippage_x_to_y_bps.push(slippage_bps(out, size as u128 * mult_x / mult_y));
+                    }
+                    let mut slippage_y_to_x_bps = vec![];
+                    for &size in sizes_y {
+                        let input_y = Amount::new(size);
+                        let (out, _, _) = state.quote_y_to_x(input_y, current_x, current_y)?;
+                        slippage_y_to_x_bps.push(slippage_bps(out, size as u128 * mult_y / mult_x));
+                    }
+
+                    let report = backtest.run(prices, trades)?;
+                    results.push(SweepResult {
+                        concentration,
+                        fee_millionth,
+                        rebate_percentage,
+                        protocol_fee_share_thousandth,
+                        slippage_x_to_y_bps,
+                        slippage_y_to_x_bps,
+                        lp_fee_income: report.lp_fee_income,
+                        pnl_vs_hold: report.pnl_vs_hold,
+                        max_drift_x: report.max_drift_x,
+                    });
+                }
+            }
+        }
+    }
+    Ok(results)
+}
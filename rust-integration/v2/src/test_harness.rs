@@ -2,7 +2,9 @@ use crate::constants::PROGRAM_ID;
 use crate::obric_v2_amm::ObricV2Amm;
 use anyhow::Result;
 use jupiter_amm_interface::{Amm, KeyedAccount, QuoteParams};
+use obric_sdk_common::fixtures::{clock_account, mint_account, price_account, token_account};
 use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
 use std::collections::HashMap;
 use std::env;
 
@@ -23,7 +25,7 @@ impl AmmTestHarness {
         let accounts = self.client.get_program_accounts(&PROGRAM_ID).unwrap();
         let keyed_accounts = &mut vec![];
         for (key, account) in accounts {
-            if account.data.len() == 666usize {
+            if account.data.len() == obric_solana::state::SSTradingPair::LEN {
                 keyed_accounts.push(KeyedAccount {
                     key,
                     account,
@@ -65,7 +67,7 @@ fn test_quote() {
         let amm = &mut ObricV2Amm::from_keyed_account(&keyed_account).unwrap();
         test_harness.update_amm(amm);
         println!("Pool: {}, {}", amm.state.mint_x, amm.state.mint_y);
-        let in_amount = pow(10, usize::from(amm.x_decimals));
+        let in_amount = pow(10, usize::from(amm.x_decimals().unwrap()));
         let quote = amm
             .quote(&QuoteParams {
             input_mint: amm.state.mint_x,
@@ -84,7 +86,7 @@ fn test_quote() {
             quote.out_amount, quote.fee_amount
         );
 
-        let in_amount = pow(10, usize::from(amm.y_decimals)); // 10 SOL
+        let in_amount = pow(10, usize::from(amm.y_decimals().unwrap())); // 10 SOL
         let quote = amm
             .quote(&QuoteParams {
             input_mint: amm.state.mint_y,
@@ -104,3 +106,1053 @@ fn test_quote() {
         );
     }
 }
+
+#[test]
+fn test_quote_consistency() {
+    use crate::simulation::{QuoteConsistencyChecker, RpcSimulator};
+    use num::pow;
+    use std::str::FromStr;
+
+    // a wallet holding both mints of every pool on the SOLANA_RPC cluster
+    let Ok(user) = env::var("OBRIC_SIM_USER") else {
+        return;
+    };
+    let test_harness = AmmTestHarness::new();
+    let checker = QuoteConsistencyChecker {
+        simulator: RpcSimulator {
+            client: &test_harness.client,
+            user: Pubkey::from_str(&user).unwrap(),
+        },
+        tolerance_bps: 10,
+    };
+
+    for keyed_account in test_harness.get_all_keyed_account().unwrap() {
+        let amm = &mut ObricV2Amm::from_keyed_account(&keyed_account).unwrap();
+        test_harness.update_amm(amm);
+        let unit: u64 = pow(10, usize::from(std::cmp::min(amm.x_decimals().unwrap(), amm.y_decimals().unwrap())));
+        let sizes = [std::cmp::max(unit / 100, 1), unit, unit * 100];
+
+        for simulated in checker.check(amm, &sizes).unwrap() {
+            println!(
+                "Pool: {}, in {} of {}: quoted {}, executed {}",
+                simulated.pool,
+                simulated.in_amount,
+                simulated.input_mint,
+                simulated.quoted_out,
+                simulated.executed_out
+            );
+        }
+    }
+}
+
+/// Records every mainnet pool and its price feeds for the fuzz targets, with
+/// `OBRIC_RECORD_FUZZ=1` and `SOLANA_RPC` set.
+#[test]
+fn test_record_fuzz_fixtures() {
+    use obric_sdk_common::fixtures::record_fuzz_fixture;
+
+    if env::var("OBRIC_RECORD_FUZZ").is_err() {
+        return;
+    }
+    let test_harness = AmmTestHarness::new();
+    for keyed_account in test_harness.get_all_keyed_account().unwrap() {
+        let amm = ObricV2Amm::from_keyed_account(&keyed_account).unwrap();
+        let pool = &keyed_account.account.data;
+        record_fuzz_fixture("trading_pair", &keyed_account.key, pool);
+        for feed in [amm.state.x_price_feed_id, amm.state.y_price_feed_id] {
+            let data = test_harness.client.get_account_data(&feed).unwrap();
+            record_fuzz_fixture("price_feed", &feed, &data);
+        }
+    }
+}
+
+/// An offline pool quoting $150 X against $1 Y, with every account it reads.
+fn offline_pool(
+    x_decimals: u8,
+    y_decimals: u8,
+) -> (KeyedAccount, HashMap<Pubkey, solana_sdk::account::Account>) {
+    use anchor_lang::AccountSerialize;
+    use obric_solana::state::SSTradingPair;
+
+    let state = SSTradingPair {
+        is_initialized: true,
+        x_price_feed_id: Pubkey::new_unique(),
+        y_price_feed_id: Pubkey::new_unique(),
+        reserve_x: Pubkey::new_unique(),
+        reserve_y: Pubkey::new_unique(),
+        mint_x: Pubkey::new_unique(),
+        mint_y: Pubkey::new_unique(),
+        ..SSTradingPair::default()
+    };
+    let mut accounts_map = HashMap::new();
+    accounts_map.insert(state.mint_x, mint_account(x_decimals));
+    accounts_map.insert(state.mint_y, mint_account(y_decimals));
+    accounts_map.insert(state.reserve_x, token_account(state.mint_x, 1_000));
+    accounts_map.insert(state.reserve_y, token_account(state.mint_y, 150_000));
+    accounts_map.insert(state.x_price_feed_id, price_account(150_00000000));
+    accounts_map.insert(state.y_price_feed_id, price_account(1_00000000));
+    accounts_map.insert(solana_sdk::sysvar::clock::ID, clock_account(0));
+
+    let mut data = vec![];
+    state.try_serialize(&mut data).unwrap();
+    let keyed_account = KeyedAccount {
+        key: Pubkey::new_unique(),
+        account: solana_sdk::account::Account {
+            data,
+            owner: PROGRAM_ID,
+            ..solana_sdk::account::Account::default()
+        },
+        params: None,
+    };
+    (keyed_account, accounts_map)
+}
+
+#[test]
+fn test_update_price_with_zero_decimals() {
+    use crate::token::MintCache;
+
+    let (keyed_account, accounts_map) = offline_pool(0, 0);
+    let amm = &mut ObricV2Amm::from_keyed_account(&keyed_account)
+        .unwrap()
+        .with_mint_cache(MintCache::default());
+    assert_eq!(amm.x_decimals(), None);
+
+    amm.update(&accounts_map).unwrap();
+    assert_eq!((amm.x_decimals(), amm.y_decimals()), (Some(0), Some(0)));
+    assert_eq!((amm.state.mult_x, amm.state.mult_y), (150_000, 1_000));
+
+    // loaded 0-decimal mints are not refetched
+    let accounts_to_update = amm.get_accounts_to_update();
+    assert!(!accounts_to_update.contains(&amm.state.mint_x));
+    assert!(!accounts_to_update.contains(&amm.state.mint_y));
+}
+
+#[test]
+fn test_update_price_with_mixed_decimals() {
+    use crate::token::MintCache;
+
+    for (x_decimals, y_decimals, mult_x, mult_y) in [
+        (0u8, 6u8, 150_000_000_000u64, 1_000u64),
+        (9, 0, 150_000, 1_000_000_000_000),
+    ] {
+        let (keyed_account, accounts_map) = offline_pool(x_decimals, y_decimals);
+        let amm = &mut ObricV2Amm::from_keyed_account(&keyed_account)
+            .unwrap()
+            .with_mint_cache(MintCache::default());
+
+        amm.update(&accounts_map).unwrap();
+        assert_eq!(
+            (amm.x_decimals(), amm.y_decimals()),
+            (Some(x_decimals), Some(y_decimals))
+        );
+        assert_eq!((amm.state.mult_x, amm.state.mult_y), (mult_x, mult_y));
+        assert!(!amm
+            .get_accounts_to_update()
+            .iter()
+            .any(|key| key.eq(&amm.state.mint_x) || key.eq(&amm.state.mint_y)));
+    }
+}
+
+#[test]
+fn test_update_reads_each_reserve() {
+    use crate::token::MintCache;
+
+    let (keyed_account, mut accounts_map) = offline_pool(6, 6);
+    let amm = &mut ObricV2Amm::from_keyed_account(&keyed_account)
+        .unwrap()
+        .with_mint_cache(MintCache::default());
+    amm.update(&accounts_map).unwrap();
+    assert_eq!(amm.current_reserves(), (1_000, 150_000));
+
+    // only Y moves
+    accounts_map.insert(amm.state.reserve_y, token_account(amm.state.mint_y, 90_000));
+    amm.update(&accounts_map).unwrap();
+    assert_eq!(amm.current_reserves(), (1_000, 90_000));
+}
+
+#[test]
+fn test_mint_cache_is_shared_across_pools() {
+    use crate::token::MintCache;
+
+    let (keyed_account, mut accounts_map) = offline_pool(0, 6);
+    let mint_cache = MintCache::default();
+    let amm = &mut ObricV2Amm::from_keyed_account(&keyed_account)
+        .unwrap()
+        .with_mint_cache(mint_cache.clone());
+    amm.update(&accounts_map).unwrap();
+    assert_eq!(mint_cache.get(&amm.state.mint_y).unwrap().1.supply, 1_000_000);
+
+    // a second pool over the same mints never asks for them
+    let other = &mut ObricV2Amm::from_keyed_account(&keyed_account)
+        .unwrap()
+        .with_mint_cache(mint_cache);
+    let accounts_to_update = other.get_accounts_to_update();
+    assert!(!accounts_to_update.contains(&other.state.mint_x));
+    assert!(!accounts_to_update.contains(&other.state.mint_y));
+    accounts_map.remove(&other.state.mint_x);
+    accounts_map.remove(&other.state.mint_y);
+    other.update(&accounts_map).unwrap();
+    assert_eq!((other.x_decimals(), other.y_decimals()), (Some(0), Some(6)));
+}
+
+#[test]
+fn test_mints_are_read_again_each_epoch() {
+    use crate::token::MintCache;
+    use anchor_spl::token::spl_token;
+    use obric_sdk_common::fixtures::packed_account;
+    use solana_sdk::sysvar;
+
+    let (keyed_account, mut accounts_map) = offline_pool(6, 6);
+    let mint_cache = MintCache::default();
+    let amm = &mut ObricV2Amm::from_keyed_account(&keyed_account)
+        .unwrap()
+        .with_mint_cache(mint_cache.clone());
+    amm.update(&accounts_map).unwrap();
+    let (mint_x, mint_y) = (amm.state.mint_x, amm.state.mint_y);
+    assert!(!amm.get_accounts_to_update().contains(&mint_x));
+
+    // a changed mint is not read again within the epoch
+    let minted = spl_token::state::Mint {
+        supply: 2_000_000,
+        decimals: 6,
+        is_initialized: true,
+        ..spl_token::state::Mint::default()
+    };
+    accounts_map.insert(mint_x, packed_account(minted, spl_token::ID));
+    amm.update(&accounts_map).unwrap();
+    assert_eq!(amm.mint_info(&mint_x).unwrap().supply, 1_000_000);
+
+    // but is once the epoch moves on
+    accounts_map.insert(sysvar::clock::ID, clock_account(1));
+    amm.update(&accounts_map).unwrap();
+    assert_eq!(amm.mint_info(&mint_x).unwrap().supply, 2_000_000);
+    assert_eq!(mint_cache.get(&mint_x).unwrap().0, 1);
+
+    // a mint left out keeps the last epoch's until it is asked for
+    let mint_y_account = accounts_map.remove(&mint_y).unwrap();
+    accounts_map.insert(sysvar::clock::ID, clock_account(2));
+    amm.update(&accounts_map).unwrap();
+    assert_eq!(amm.y_decimals(), Some(6));
+    let accounts_to_update = amm.get_accounts_to_update();
+    assert!(!accounts_to_update.contains(&mint_x));
+    assert!(accounts_to_update.contains(&mint_y));
+    accounts_map.insert(mint_y, mint_y_account);
+    amm.update(&accounts_map).unwrap();
+    assert!(!amm.get_accounts_to_update().contains(&mint_y));
+
+    // an invalidated mint is read again by the next pool
+    mint_cache.invalidate(&mint_x);
+    let other = ObricV2Amm::from_keyed_account(&keyed_account)
+        .unwrap()
+        .with_mint_cache(mint_cache.clone());
+    let accounts_to_update = other.get_accounts_to_update();
+    assert!(accounts_to_update.contains(&mint_x));
+    assert!(!accounts_to_update.contains(&mint_y));
+    mint_cache.clear();
+    assert!(other.get_accounts_to_update().contains(&mint_y));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_pool_snapshot_json() {
+    use crate::error::ObricSdkError;
+    use crate::snapshot::PoolSnapshot;
+    use crate::token::MintCache;
+
+    let (keyed_account, accounts_map) = offline_pool(0, 6);
+    let amm = &mut ObricV2Amm::from_keyed_account(&keyed_account)
+        .unwrap()
+        .with_mint_cache(MintCache::default());
+    amm.update(&accounts_map).unwrap();
+    // target_x on the curve at the oracle price
+    let target_x_k = 1_000u128 * 100;
+    amm.state.concentration = 100;
+    amm.state.target_x = 1_000;
+    amm.state.big_k = target_x_k * target_x_k * amm.state.mult_x as u128 / amm.state.mult_y as u128;
+
+    let mut missing = accounts_map.clone();
+    missing.remove(&amm.state.x_price_feed_id);
+    assert!(matches!(
+        PoolSnapshot::new(amm, &missing, 0),
+        Err(ObricSdkError::AccountNotFound { pool, account })
+            if pool == keyed_account.key && account == amm.state.x_price_feed_id
+    ));
+
+    let snapshot = PoolSnapshot::new(amm, &accounts_map, 0).unwrap();
+    assert_eq!((snapshot.price_x, snapshot.price_y), (150_000, 1_000));
+    assert!((snapshot.marginal_price - 150.0).abs() < 1e-6);
+
+    let json = serde_json::to_value(&snapshot).unwrap();
+    assert_eq!(json["pool"], keyed_account.key.to_string());
+    assert_eq!(json["state"]["mint_x"], amm.state.mint_x.to_string());
+
+    let decoded: PoolSnapshot = serde_json::from_value(json).unwrap();
+    assert_eq!(decoded.pool, snapshot.pool);
+    assert_eq!(decoded.state.mint_y, amm.state.mint_y);
+    assert_eq!(decoded.big_k, snapshot.big_k);
+    assert_eq!(
+        (decoded.target_x, decoded.target_y),
+        (snapshot.target_x, snapshot.target_y)
+    );
+}
+
+#[test]
+fn test_clone_round_trip() {
+    use anchor_lang::AccountSerialize;
+    use crate::token::MintCache;
+
+    let (keyed_account, accounts_map) = offline_pool(6, 9);
+    let amm = &mut ObricV2Amm::from_keyed_account(&keyed_account)
+        .unwrap()
+        .with_mint_cache(MintCache::default());
+    amm.update(&accounts_map).unwrap();
+    assert_eq!(amm.clone(), *amm);
+
+    let mut data = vec![];
+    amm.state.try_serialize(&mut data).unwrap();
+    let mut reloaded = ObricV2Amm::from_keyed_account(&KeyedAccount {
+        account: solana_sdk::account::Account {
+            data,
+            ..keyed_account.account.clone()
+        },
+        ..keyed_account
+    })
+    .unwrap()
+    .with_mint_cache(MintCache::default());
+    assert_eq!(reloaded.state, amm.state.clone());
+    // the caches differ, the pools do not
+    reloaded.update(&accounts_map).unwrap();
+    assert_eq!(reloaded, *amm);
+}
+
+#[test]
+fn test_volume_analytics() {
+    use crate::analytics::{PoolAnalytics, VolumeBucket};
+    use crate::token::MintCache;
+
+    let (keyed_account, accounts_map) = offline_pool(0, 6);
+    let amm = &mut ObricV2Amm::from_keyed_account(&keyed_account)
+        .unwrap()
+        .with_mint_cache(MintCache::default());
+    amm.update(&accounts_map).unwrap();
+    amm.state.fee_millionth = 1_000;
+    amm.state.protocol_fee_share_thousandth = 200;
+    amm.state.cumulative_volume = 1_000_000_000;
+    // ring buffer wrapped after the third slot, the last slot never written
+    let day = 24 * 3600;
+    amm.state.volume_time_record = [3 * day, 4 * day, 5 * day, 0, 0, day, 2 * day, 0];
+    amm.state.volume_record = [30_000, 40_000, 50_000, 0, 0, 10_000, 20_000, 0];
+
+    let analytics = PoolAnalytics::new(amm, 6 * day);
+    assert_eq!(analytics.buckets.len(), 5);
+    assert_eq!(
+        analytics.buckets[0],
+        VolumeBucket {
+            start_time: day,
+            volume: 10_000
+        }
+    );
+    assert_eq!(analytics.window_seconds, 5 * day);
+    assert_eq!(analytics.window_volume, 150_000);
+    assert_eq!((analytics.fee, analytics.protocol_fee, analytics.lp_fee), (150, 30, 120));
+    // 1_000 X (0 decimals) at 150_000_000 raw Y (6 decimals) each, plus 150_000 raw Y
+    assert_eq!(analytics.pool_value, 150_000_150_000);
+    let fee_apr = 150.0 / 150_000_150_000.0 * 365.0 / 5.0;
+    assert!((analytics.fee_apr - fee_apr).abs() < 1e-9);
+    assert!((analytics.lp_fee_apr - fee_apr * 0.8).abs() < 1e-9);
+}
+
+#[test]
+fn test_quote_breakdown() {
+    use crate::token::MintCache;
+
+    let (keyed_account, mut accounts_map) = offline_pool(6, 6);
+    let amm = &mut ObricV2Amm::from_keyed_account(&keyed_account)
+        .unwrap()
+        .with_mint_cache(MintCache::default());
+    // X below its target of 1_000 X, Y above
+    accounts_map.insert(amm.state.reserve_x, token_account(amm.state.mint_x, 800_000_000));
+    accounts_map.insert(amm.state.reserve_y, token_account(amm.state.mint_y, 150_000_000_000));
+    amm.update(&accounts_map).unwrap();
+    let target_x_k = 1_000_000_000u128 * 100;
+    amm.state.concentration = 100;
+    amm.state.target_x = 1_000_000_000;
+    amm.state.big_k = target_x_k * target_x_k * amm.state.mult_x as u128 / amm.state.mult_y as u128;
+    amm.state.fee_millionth = 1_000;
+    amm.state.rebate_percentage = 50;
+    amm.state.protocol_fee_share_thousandth = 200;
+
+    let x_to_y = QuoteParams {
+        input_mint: amm.state.mint_x,
+        in_amount: 10_000_000,
+        output_mint: amm.state.mint_y,
+    };
+    let breakdown = amm.quote_breakdown(&x_to_y).unwrap();
+    assert!(breakdown.rebalancing);
+    // the whole input moves X toward target, so the full rebate_percentage is paid back
+    assert_eq!(breakdown.rebate, breakdown.fee_before_rebate * 50 / 100);
+    assert_eq!(
+        breakdown.protocol_fee + breakdown.lp_fee,
+        breakdown.fee_before_rebate - breakdown.rebate
+    );
+    assert_eq!(
+        breakdown.output_after_fee + breakdown.protocol_fee + breakdown.lp_fee,
+        breakdown.output_before_fee
+    );
+    // half of the 10 bps fee, give or take the rounding
+    assert!((4..=5).contains(&breakdown.fee_bps));
+    let quote = amm.quote(&x_to_y).unwrap();
+    assert_eq!(quote.out_amount, breakdown.output_after_fee);
+    assert_eq!(quote.fee_amount, breakdown.protocol_fee + breakdown.lp_fee);
+
+    let breakdown = amm
+        .quote_breakdown(&QuoteParams {
+            input_mint: amm.state.mint_y,
+            in_amount: 1_500_000_000,
+            output_mint: amm.state.mint_x,
+        })
+        .unwrap();
+    assert!(!breakdown.rebalancing);
+    assert_eq!(breakdown.rebate, 0);
+    assert!(breakdown.fee_before_rebate > 0);
+    assert!((9..=10).contains(&breakdown.fee_bps));
+}
+
+#[test]
+fn test_backtest_round_trip_at_flat_prices() {
+    use crate::backtest::{parse_prices_csv, parse_trades_csv, Backtest};
+    use obric_solana::state::SSTradingPair;
+
+    let prices = parse_prices_csv(
+        "timestamp,price_x,price_y,expo\n\
+         0,15000000000,100000000,-8\n\
+         60,15000000000,100000000,-8\n",
+    )
+    .unwrap();
+    let trades = parse_trades_csv(
+        "timestamp,side,in_amount\n\
+         10,x_to_y,10000000\n\
+         20,y_to_x,1500000000\n\
+         30,y_to_x,1000000000000000\n",
+    )
+    .unwrap();
+    let (price_x, price_y) = prices[0].normalized().unwrap();
+    assert_eq!((price_x.get(), price_y.get()), (150_000, 1_000));
+
+    let target_x_k = 1_000_000_000u128 * 100;
+    let mut backtest = Backtest {
+        state: SSTradingPair {
+            concentration: 100,
+            target_x: 1_000_000_000,
+            // mult_x / mult_y at the prices above
+            big_k: target_x_k * target_x_k * 150_000 / 1_000,
+            fee_millionth: 1_000,
+            rebate_percentage: 50,
+            protocol_fee_share_thousandth: 200,
+            ..SSTradingPair::default()
+        },
+        x_decimals: 6,
+        y_decimals: 6,
+        current_x: 800_000_000,
+        current_y: 150_000_000_000,
+    };
+    let report = backtest.run(&prices, &trades).unwrap();
+
+    // the last trade asks for more X than the pool holds
+    assert_eq!((report.trades_executed, report.trades_skipped), (2, 1));
+    assert!(report.lp_fee_income > 0);
+    assert!(report.protocol_fee_income > 0);
+    // the first trade moves X toward its target
+    assert!(report.rebate_paid > 0);
+    assert_eq!(report.initial_value, report.hold_value);
+    assert!(report.pnl_vs_hold > 0);
+    assert_eq!(report.final_drift_x, report.final_x as i128 - 1_000_000_000);
+}
+
+#[test]
+fn test_parameter_sweep() {
+    use crate::backtest::{parse_prices_csv, Backtest};
+    use crate::sweep::{format_table, sweep, synthetic_trades, SweepRanges};
+    use obric_solana::state::SSTradingPair;
+
+    let prices = parse_prices_csv(
+        "0,15000000000,100000000,-8\n\
+         300,15300000000,100000000,-8\n\
+         600,14700000000,100000000,-8\n",
+    )
+    .unwrap();
+    let trades = synthetic_trades(50, 7, 1, 12, 10_000_000, 1_500_000_000);
+    assert_eq!(trades, synthetic_trades(50, 7, 1, 12, 10_000_000, 1_500_000_000));
+    assert!(trades.iter().any(|trade| trade.is_x_to_y));
+    assert!(trades.iter().any(|trade| !trade.is_x_to_y));
+
+    // big_k is rebuilt by the sweep
+    let base = Backtest {
+        state: SSTradingPair {
+            target_x: 1_000_000_000,
+            ..SSTradingPair::default()
+        },
+        x_decimals: 6,
+        y_decimals: 6,
+        current_x: 1_000_000_000,
+        current_y: 150_000_000_000,
+    };
+    let ranges = SweepRanges {
+        concentration: vec![50, 100],
+        fee_millionth: vec![500, 1_000],
+        rebate_percentage: vec![0, 50],
+        protocol_fee_share_thousandth: vec![200],
+    };
+    let results = sweep(
+        &base,
+        &prices,
+        &trades,
+        &ranges,
+        &[1_000_000, 100_000_000],
+        &[150_000_000, 15_000_000_000],
+    )
+    .unwrap();
+    assert_eq!(results.len(), 8);
+    // concentration first, then fee, then rebate
+    let (low_concentration, high_concentration) = (&results[2], &results[6]);
+    assert_eq!((low_concentration.concentration, low_concentration.fee_millionth), (50, 1_000));
+    assert_eq!((high_concentration.concentration, high_concentration.fee_millionth), (100, 1_000));
+    assert!(high_concentration.slippage_x_to_y_bps[1] <= low_concentration.slippage_x_to_y_bps[1]);
+    assert!(high_concentration.slippage_y_to_x_bps[1] <= low_concentration.slippage_y_to_x_bps[1]);
+    // a larger fee costs more on the same curve
+    assert!(results[0].slippage_x_to_y_bps[0] < results[2].slippage_x_to_y_bps[0]);
+    for result in &results {
+        assert!(result.slippage_x_to_y_bps[0] <= result.slippage_x_to_y_bps[1]);
+        assert!(result.lp_fee_income > 0);
+    }
+
+    let table = format_table(&results);
+    assert_eq!(table.lines().count(), 9);
+    assert!(table.starts_with("concentration fee_millionth"));
+}
+
+/// A balanced 6/6 decimals pool of `mint_x` at $150 against `mint_y` at $1.
+fn routed_pool(mint_x: Pubkey, mint_y: Pubkey, concentration: u64) -> ObricV2Amm {
+    use crate::token::MintCache;
+
+    let (keyed_account, mut accounts_map) = offline_pool(6, 6);
+    let mut amm = ObricV2Amm::from_keyed_account(&keyed_account)
+        .unwrap()
+        .with_mint_cache(MintCache::default());
+    amm.state.mint_x = mint_x;
+    amm.state.mint_y = mint_y;
+    accounts_map.insert(mint_x, mint_account(6));
+    accounts_map.insert(mint_y, mint_account(6));
+    accounts_map.insert(amm.state.reserve_x, token_account(mint_x, 1_000_000_000));
+    accounts_map.insert(amm.state.reserve_y, token_account(mint_y, 150_000_000_000));
+    amm.update(&accounts_map).unwrap();
+
+    let target_x_k = 1_000_000_000u128 * concentration as u128;
+    amm.state.concentration = concentration;
+    amm.state.target_x = 1_000_000_000;
+    amm.state.big_k = target_x_k * target_x_k * amm.state.mult_x as u128 / amm.state.mult_y as u128;
+    amm.state.fee_millionth = 1_000;
+    amm.state.protocol_fee_share_thousandth = 200;
+    amm
+}
+
+#[test]
+fn test_router() {
+    use obric_sdk_common::router::Router;
+
+    let (mint_a, mint_b, usdc) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let deep = routed_pool(mint_a, usdc, 100);
+    let shallow = routed_pool(mint_a, usdc, 50);
+    let other = routed_pool(mint_b, usdc, 100);
+    let router = Router::new(vec![&deep, &shallow, &other]);
+
+    let route = router.best_single(mint_a, usdc, 10_000_000).unwrap();
+    assert_eq!(route.hops.len(), 1);
+    assert_eq!(route.hops[0][0].pool, deep.key());
+
+    // splitting moves the deeper pool less far down its curve
+    let single = router.best_single(mint_a, usdc, 200_000_000).unwrap();
+    let split = router.best_split(mint_a, usdc, 200_000_000).unwrap();
+    assert_eq!(split.hops[0].len(), 2);
+    assert_eq!(split.in_amount(), 200_000_000);
+    assert!(split.out_amount() > single.out_amount());
+    let deep_leg = split.legs().find(|leg| leg.pool == deep.key()).unwrap();
+    let shallow_leg = split.legs().find(|leg| leg.pool == shallow.key()).unwrap();
+    assert!(deep_leg.in_amount > shallow_leg.in_amount);
+    assert_eq!(router.best_route(mint_a, usdc, 200_000_000).unwrap(), split);
+
+    // more A than both pools hold USDC for: the split of what they can take
+    assert!(router.best_single(mint_a, usdc, 10_000_000_000).is_none());
+    let partial = router.best_split(mint_a, usdc, 10_000_000_000).unwrap();
+    assert_eq!(partial.hops[0].len(), 2);
+    assert!(partial.in_amount() > 0 && partial.in_amount() < 10_000_000_000);
+    assert_eq!(router.best_route(mint_a, usdc, 10_000_000_000).unwrap(), partial);
+
+    // A and B only meet through USDC
+    assert!(router.best_single(mint_a, mint_b, 10_000_000).is_none());
+    let route = router.best_route(mint_a, mint_b, 10_000_000).unwrap();
+    assert_eq!(route.hops.len(), 2);
+    let (first, second) = (&route.hops[0][0], &route.hops[1][0]);
+    assert_eq!((first.pool, first.output_mint), (deep.key(), usdc));
+    assert_eq!((second.pool, second.input_mint), (other.key(), usdc));
+    assert_eq!(second.in_amount, first.out_amount);
+    // two fees and the curves, but close to one for one
+    assert!(route.out_amount() < 10_000_000 && route.out_amount() > 9_900_000);
+
+    let user = Pubkey::new_unique();
+    let token_accounts = HashMap::from([(mint_a, Pubkey::new_unique()), (usdc, Pubkey::new_unique())]);
+    let metas = router
+        .account_metas(&split, user, |mint| token_accounts[mint], 50)
+        .unwrap();
+    assert_eq!(metas.len(), 2);
+    for (leg, metas) in split.legs().zip(&metas) {
+        assert!(metas.iter().any(|meta| meta.pubkey == leg.pool));
+        assert!(metas.iter().any(|meta| meta.pubkey == user && meta.is_signer));
+    }
+}
+
+#[test]
+fn test_arbitrage() {
+    use obric_sdk_common::arbitrage::{detect, find_against_reference, find_cycles, ReferencePrice};
+
+    let (mint_a, usdc) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let balanced = routed_pool(mint_a, usdc, 100);
+    let other_balanced = routed_pool(mint_a, usdc, 50);
+    // 200 A above a target of 800: A is cheaper here than at the oracle
+    let mut skewed = routed_pool(mint_a, usdc, 100);
+    let target_x_k = 800_000_000u128 * 100;
+    skewed.state.target_x = 800_000_000;
+    skewed.state.big_k = target_x_k * target_x_k * skewed.state.mult_x as u128 / skewed.state.mult_y as u128;
+    let reference = ReferencePrice {
+        mint_x: mint_a,
+        mint_y: usdc,
+        mult_x: balanced.state.mult_x,
+        mult_y: balanced.state.mult_y,
+    };
+
+    // at target, the fees close every cycle
+    assert!(find_cycles(&[&balanced, &other_balanced]).is_empty());
+    assert!(find_against_reference(&[&balanced], &reference).is_empty());
+
+    let opportunities = find_cycles(&[&balanced, &skewed]);
+    assert!(!opportunities.is_empty());
+    let best = &opportunities[0];
+    // sell A to the balanced pool, buy it back cheaper from the skewed one
+    assert_eq!((best.legs[0].pool, best.input_mint()), (balanced.key(), mint_a));
+    assert_eq!(best.legs[1].pool, skewed.key());
+    assert_eq!(best.legs[1].in_amount, best.legs[0].out_amount);
+    assert_eq!(best.return_amount - best.in_amount, best.profit);
+    assert!(best.profit > 0);
+
+    // no nearby size does better
+    let cycle_profit = |in_amount: u64| {
+        let quote = |amm: &ObricV2Amm, input_mint, output_mint, in_amount| {
+            amm.quote(&QuoteParams {
+                input_mint,
+                in_amount,
+                output_mint,
+            })
+            .unwrap()
+            .out_amount
+        };
+        let out = quote(&balanced, mint_a, usdc, in_amount);
+        quote(&skewed, usdc, mint_a, out) as i128 - in_amount as i128
+    };
+    assert_eq!(cycle_profit(best.in_amount), best.profit as i128);
+    assert!(cycle_profit(best.in_amount * 9 / 10) <= best.profit as i128);
+    assert!(cycle_profit(best.in_amount * 11 / 10) <= best.profit as i128);
+
+    let opportunities = find_against_reference(&[&skewed], &reference);
+    assert_eq!(opportunities.len(), 1);
+    assert_eq!(opportunities[0].input_mint(), usdc);
+    assert!(opportunities[0].profit_bps() > 0);
+
+    // profits in A and in USDC are ranked apart
+    let all = detect(&[&balanced, &skewed], &[reference]);
+    assert!(all.iter().any(|opportunity| opportunity.input_mint() == mint_a));
+    assert!(all.iter().any(|opportunity| opportunity.input_mint() == usdc));
+    let groups = all.windows(2).filter(|pair| pair[0].input_mint() != pair[1].input_mint());
+    assert_eq!(groups.count(), 1);
+    assert!(all
+        .windows(2)
+        .filter(|pair| pair[0].input_mint() == pair[1].input_mint())
+        .all(|pair| pair[0].profit >= pair[1].profit));
+}
+
+#[test]
+fn test_quotes_follow_state() {
+    use obric_solana::state::Amount;
+
+    let (mint_a, usdc) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut amm = routed_pool(mint_a, usdc, 100);
+    let params = QuoteParams {
+        input_mint: mint_a,
+        in_amount: 10_000_000,
+        output_mint: usdc,
+    };
+    let (current_x, current_y) = amm.current_reserves();
+    let (current_x, current_y) = (Amount::new(current_x), Amount::new(current_y));
+    let quoted = amm
+        .state
+        .quote_x_to_y_breakdown(Amount::new(params.in_amount), current_x, current_y)
+        .unwrap();
+    assert_eq!(amm.quote_breakdown(&params).unwrap(), quoted);
+
+    // changes to the state miss the curve point of the last update
+    amm.state.fee_millionth = 2_000;
+    let higher_fee = amm.quote_breakdown(&params).unwrap();
+    assert_eq!(
+        higher_fee,
+        amm.state
+            .quote_x_to_y_breakdown(Amount::new(params.in_amount), current_x, current_y)
+            .unwrap()
+    );
+    assert!(higher_fee.output_after_fee < quoted.output_after_fee);
+    amm.state.target_x = 900_000_000;
+    assert_ne!(amm.quote_breakdown(&params).unwrap(), higher_fee);
+
+    // clones quote the same
+    let clone = amm.clone();
+    assert_eq!(clone, amm);
+    assert_eq!(clone.quote_breakdown(&params).unwrap(), amm.quote_breakdown(&params).unwrap());
+}
+
+#[test]
+fn test_curve_point_is_kept_until_the_state_changes() {
+    use crate::token::MintCache;
+    use obric_solana::state::Amount;
+
+    let (keyed_account, accounts_map) = offline_pool(6, 6);
+    let mut amm = ObricV2Amm::from_keyed_account(&keyed_account)
+        .unwrap()
+        .with_mint_cache(MintCache::default());
+    assert_eq!(amm.curve_point(), None);
+    amm.update(&accounts_map).unwrap();
+    let target_x_k = 1_000u128 * 100;
+    amm.state.concentration = 100;
+    amm.state.target_x = 1_000;
+    amm.state.big_k = target_x_k * target_x_k * amm.state.mult_x as u128 / amm.state.mult_y as u128;
+    amm.state.fee_millionth = 1_000;
+    assert_eq!(amm.curve_point(), None);
+    amm.update(&accounts_map).unwrap();
+    let (current_x, current_y) = amm.current_reserves();
+    let (current_x, current_y) = (Amount::new(current_x), Amount::new(current_y));
+    let point = amm.state.curve_point(current_x, current_y).unwrap();
+    assert_eq!(amm.curve_point(), Some(point));
+
+    // quotes after a change recompute it
+    amm.state.fee_millionth = 2_000;
+    assert_eq!(amm.curve_point(), None);
+    let params = QuoteParams {
+        input_mint: amm.state.mint_x,
+        in_amount: 10,
+        output_mint: amm.state.mint_y,
+    };
+    assert_eq!(
+        amm.quote_breakdown(&params).unwrap(),
+        amm.state
+            .quote_x_to_y_breakdown(Amount::new(params.in_amount), current_x, current_y)
+            .unwrap()
+    );
+    amm.update(&accounts_map).unwrap();
+    assert_eq!(amm.curve_point(), Some(point));
+}
+
+#[test]
+fn test_quote_bonk_priced_pool() {
+    use crate::token::MintCache;
+
+    // X at $0.00002 with 5 decimals, Y at $1 with 6 decimals
+    let (keyed_account, mut accounts_map) = offline_pool(5, 6);
+    let amm = &mut ObricV2Amm::from_keyed_account(&keyed_account)
+        .unwrap()
+        .with_mint_cache(MintCache::default());
+    accounts_map.insert(amm.state.x_price_feed_id, price_account(2_000));
+    accounts_map.insert(amm.state.reserve_x, token_account(amm.state.mint_x, 100_000_000_000_000));
+    accounts_map.insert(amm.state.reserve_y, token_account(amm.state.mint_y, 20_000_000_000));
+    amm.update(&accounts_map).unwrap();
+    // normalized at 10^-8 rather than rounded to 0 at 10^-3
+    assert_eq!((amm.state.mult_x, amm.state.mult_y), (20_000, 100_000_000));
+
+    let target_x_k = 100_000_000_000_000u128 * 100;
+    amm.state.concentration = 100;
+    amm.state.target_x = 100_000_000_000_000;
+    amm.state.big_k = target_x_k * target_x_k * amm.state.mult_x as u128 / amm.state.mult_y as u128;
+    amm.state.fee_millionth = 1_000;
+
+    // 1M X for $20, less the 10 bps fee and a little slippage
+    let quote = amm
+        .quote(&QuoteParams {
+            input_mint: amm.state.mint_x,
+            in_amount: 100_000_000_000,
+            output_mint: amm.state.mint_y,
+        })
+        .unwrap();
+    assert!(quote.out_amount > 19_950_000 && quote.out_amount < 20_000_000);
+    let quote = amm
+        .quote(&QuoteParams {
+            input_mint: amm.state.mint_y,
+            in_amount: 20_000_000,
+            output_mint: amm.state.mint_x,
+        })
+        .unwrap();
+    assert!(quote.out_amount > 99_750_000_000 && quote.out_amount < 100_000_000_000);
+}
+
+#[test]
+fn test_token_2022_pools_are_not_loaded() {
+    use crate::error::ObricSdkError;
+    use crate::token::MintCache;
+    use anchor_spl::token_2022::spl_token_2022;
+    use obric_sdk_common::fixtures::{token_2022_account, transfer_fee_mint_account};
+    use solana_sdk::sysvar;
+
+    // X is a Token-2022 mint charging 1%, which the program's swap cannot move
+    let (keyed_account, mut accounts_map) = offline_pool(6, 6);
+    let mint_cache = MintCache::default();
+    let amm = &mut ObricV2Amm::from_keyed_account(&keyed_account)
+        .unwrap()
+        .with_mint_cache(mint_cache.clone());
+    let (mint_x, mint_y) = (amm.state.mint_x, amm.state.mint_y);
+    accounts_map.insert(mint_x, transfer_fee_mint_account(6, 0, 100));
+    accounts_map.insert(
+        amm.state.reserve_x,
+        token_2022_account(mint_x, 1_000_000_000),
+    );
+    let error = amm.update(&accounts_map).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<ObricSdkError>(),
+        Some(ObricSdkError::UnsupportedTokenProgram { mint, token_program, .. })
+            if *mint == mint_x && *token_program == spl_token_2022::ID
+    ));
+    // so it quotes nothing, instead of quotes its swap would fail
+    let error = amm
+        .quote(&QuoteParams {
+            input_mint: mint_x,
+            in_amount: 1_000_000,
+            output_mint: mint_y,
+        })
+        .unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<ObricSdkError>(),
+        Some(ObricSdkError::PoolNotLoaded { .. })
+    ));
+    // the mint is still decoded and shared with other pools
+    assert_eq!(
+        mint_cache.get(&mint_x).unwrap().1.token_program,
+        spl_token_2022::ID
+    );
+
+    // the clock is required
+    accounts_map.insert(mint_x, mint_account(6));
+    accounts_map.remove(&sysvar::clock::ID);
+    let error = amm.update(&accounts_map).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<ObricSdkError>(),
+        Some(ObricSdkError::AccountNotFound { account, .. }) if *account == sysvar::clock::ID
+    ));
+}
+
+#[test]
+fn test_price_age_is_only_checked_with_a_limit() {
+    use crate::error::{ObricSdkError, MAX_PRICE_AGE};
+    use crate::token::MintCache;
+    use obric_sdk_common::fixtures::price_account_at;
+
+    let (keyed_account, mut accounts_map) = offline_pool(6, 6);
+    let amm = ObricV2Amm::from_keyed_account(&keyed_account)
+        .unwrap()
+        .with_mint_cache(MintCache::default());
+    let oracle = amm.state.y_price_feed_id;
+    accounts_map.insert(oracle, price_account_at(1_00000000, MAX_PRICE_AGE as i64 + 1));
+
+    // the programs take a price of any age, so does the SDK by default
+    let mut unchecked = amm.clone();
+    unchecked.update(&accounts_map).unwrap();
+
+    let mut checked = amm.with_max_price_age(MAX_PRICE_AGE);
+    let error = checked.update(&accounts_map).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<ObricSdkError>(),
+        Some(ObricSdkError::StaleOracle { oracle: stale, .. }) if *stale == oracle
+    ));
+}
+
+#[test]
+fn test_amm_conformance() {
+    use anchor_lang::{AccountDeserialize, AccountSerialize};
+    use obric_amm_conformance::Conformance;
+    use obric_solana::state::SSTradingPair;
+
+    // balanced at 1000 X and 150000 Y with a concentration of 100
+    let (mut keyed_account, mut accounts_map) = offline_pool(6, 6);
+    let mut state = SSTradingPair::try_deserialize(&mut &keyed_account.account.data[..]).unwrap();
+    let target_x_k = 1_000_000_000u128 * 100;
+    state.concentration = 100;
+    state.target_x = 1_000_000_000;
+    state.big_k = target_x_k * target_x_k * 150;
+    state.fee_millionth = 1_000;
+    state.rebate_percentage = 50;
+    state.protocol_fee_share_thousandth = 200;
+    keyed_account.account.data.clear();
+    state.try_serialize(&mut keyed_account.account.data).unwrap();
+    accounts_map.insert(state.reserve_x, token_account(state.mint_x, 1_000_000_000));
+    accounts_map.insert(state.reserve_y, token_account(state.mint_y, 150_000_000_000));
+
+    let amm: ObricV2Amm = Conformance::default()
+        .check(&keyed_account, &accounts_map)
+        .unwrap();
+    assert_eq!(amm.current_reserves(), (1_000_000_000, 150_000_000_000));
+    assert_eq!((amm.state.mult_x, amm.state.mult_y), (150_000, 1_000));
+}
+
+#[test]
+fn test_typed_errors() {
+    use crate::error::{ObricSdkError, MAX_PRICE_AGE};
+    use obric_sdk_common::fixtures::price_account_at;
+    use crate::token::MintCache;
+
+    let (keyed_account, mut accounts_map) = offline_pool(6, 6);
+    let amm = &mut ObricV2Amm::from_keyed_account(&keyed_account)
+        .unwrap()
+        .with_mint_cache(MintCache::default())
+        .with_max_price_age(MAX_PRICE_AGE);
+    let x_to_y = QuoteParams {
+        input_mint: amm.state.mint_x,
+        in_amount: 1_000_000,
+        output_mint: amm.state.mint_y,
+    };
+    assert!(matches!(
+        amm.quote_breakdown(&x_to_y),
+        Err(ObricSdkError::PoolNotLoaded { pool, .. }) if pool == keyed_account.key
+    ));
+
+    // the Amm trait returns them as anyhow errors
+    let price_x = accounts_map.remove(&amm.state.x_price_feed_id).unwrap();
+    let error = amm.update(&accounts_map).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<ObricSdkError>(),
+        Some(ObricSdkError::AccountNotFound { account, .. })
+            if *account == amm.state.x_price_feed_id
+    ));
+    accounts_map.insert(amm.state.x_price_feed_id, price_account(-1));
+    let error = amm.update(&accounts_map).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<ObricSdkError>(),
+        Some(ObricSdkError::InvalidOraclePrice { oracle, .. })
+            if *oracle == amm.state.x_price_feed_id
+    ));
+    accounts_map.insert(amm.state.x_price_feed_id, price_x);
+    accounts_map.insert(amm.state.y_price_feed_id, price_account_at(1_00000000, 120));
+    let error = amm.update(&accounts_map).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<ObricSdkError>(),
+        Some(ObricSdkError::StaleOracle { oracle, publish_time: 120, now: 0, .. })
+            if *oracle == amm.state.y_price_feed_id
+    ));
+    accounts_map.insert(
+        amm.state.y_price_feed_id,
+        price_account_at(1_00000000, MAX_PRICE_AGE as i64),
+    );
+    amm.update(&accounts_map).unwrap();
+    accounts_map.get_mut(&amm.state.reserve_y).unwrap().data.truncate(10);
+    let error = amm.update(&accounts_map).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<ObricSdkError>(),
+        Some(ObricSdkError::Deserialization { account, .. }) if *account == amm.state.reserve_y
+    ));
+
+    let other_mint = Pubkey::new_unique();
+    let error = amm
+        .quote(&QuoteParams {
+            input_mint: other_mint,
+            ..x_to_y
+        })
+        .unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<ObricSdkError>(),
+        Some(ObricSdkError::UnsupportedMint { mint, .. }) if *mint == other_mint
+    ));
+}
+
+/// The accounts of `python/tests/fixtures` quote what the python tests expect.
+/// The python module prices the Token-2022 fee mint, the AMM does not load it.
+#[test]
+fn test_python_fixtures() {
+    use crate::error::ObricSdkError;
+    use crate::token::MintCache;
+    use anchor_lang::AccountDeserialize;
+    use anchor_spl::token_2022::spl_token_2022;
+    use obric_sdk_common::fixtures::{
+        token_2022_account, transfer_fee_mint_account, PRICE_FEED_OWNER,
+    };
+    use obric_solana::state::SSTradingPair;
+    use solana_sdk::account::Account;
+
+    let fee_mint = include_bytes!("../../python/tests/fixtures/fee_mint.bin");
+    // make_fixtures.py lays the mint out by hand
+    assert_eq!(&fee_mint[..], &transfer_fee_mint_account(6, 0, 100).data[..]);
+
+    let pool_data = include_bytes!("../../python/tests/fixtures/v2_pool.bin");
+    let state = SSTradingPair::try_deserialize(&mut &pool_data[..]).unwrap();
+    let keyed_account = KeyedAccount {
+        key: Pubkey::new_unique(),
+        account: Account {
+            data: pool_data.to_vec(),
+            owner: PROGRAM_ID,
+            ..Account::default()
+        },
+        params: None,
+    };
+    let account = |data: &[u8], owner| Account {
+        data: data.to_vec(),
+        owner,
+        ..Account::default()
+    };
+    let mut accounts_map = HashMap::new();
+    accounts_map.insert(state.mint_x, mint_account(6));
+    accounts_map.insert(state.mint_y, mint_account(6));
+    accounts_map.insert(state.reserve_x, token_account(state.mint_x, 1_000_000_000));
+    accounts_map.insert(state.reserve_y, token_account(state.mint_y, 150_000_000_000));
+    accounts_map.insert(
+        state.x_price_feed_id,
+        account(include_bytes!("../../python/tests/fixtures/price_x.bin"), PRICE_FEED_OWNER),
+    );
+    accounts_map.insert(
+        state.y_price_feed_id,
+        account(include_bytes!("../../python/tests/fixtures/price_y.bin"), PRICE_FEED_OWNER),
+    );
+    accounts_map.insert(solana_sdk::sysvar::clock::ID, clock_account(0));
+    let mut amm = ObricV2Amm::from_keyed_account(&keyed_account)
+        .unwrap()
+        .with_mint_cache(MintCache::default());
+    amm.update(&accounts_map).unwrap();
+
+    // the values test_v2_quotes and, before the output's transfer fee,
+    // test_v2_transfer_fees check
+    let quote = |input_mint, in_amount, output_mint| {
+        amm.quote(&QuoteParams {
+            input_mint,
+            in_amount,
+            output_mint,
+        })
+        .unwrap()
+        .out_amount
+    };
+    assert_eq!(quote(state.mint_x, 1_000_000, state.mint_y), 149_848_503);
+    assert_eq!(quote(state.mint_y, 150_000_000, state.mint_x), 998_992);
+
+    accounts_map.insert(state.mint_x, account(fee_mint, spl_token_2022::ID));
+    accounts_map.insert(
+        state.reserve_x,
+        token_2022_account(state.mint_x, 1_000_000_000),
+    );
+    let mut amm = ObricV2Amm::from_keyed_account(&keyed_account)
+        .unwrap()
+        .with_mint_cache(MintCache::default());
+    let error = amm.update(&accounts_map).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<ObricSdkError>(),
+        Some(ObricSdkError::UnsupportedTokenProgram { mint, .. }) if *mint == state.mint_x
+    ));
+}
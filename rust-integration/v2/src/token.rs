@@ -0,0 +1,55 @@
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+pub use obric_sdk_common::token::{unpack_token_amount, MintInfo};
+
+/// Mint metadata shared across pools, so that a mint quoted by several pools is
+/// fetched once per epoch. Entries are kept with the epoch they were read at, as
+/// the transfer fee config and supply change over time. Clones share the same map.
+#[derive(Clone, Debug, Default)]
+pub struct MintCache(Arc<RwLock<HashMap<Pubkey, (u64, MintInfo)>>>);
+
+/// A cache does not change what a pool quotes, so pools compare equal whichever
+/// cache they use.
+impl PartialEq for MintCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl MintCache {
+    /// Process-wide cache used by `ObricV2Amm::from_keyed_account`.
+    pub fn global() -> Self {
+        static GLOBAL: OnceLock<MintCache> = OnceLock::new();
+        GLOBAL.get_or_init(MintCache::default).clone()
+    }
+
+    // Every write replaces a whole entry, so a writer that panicked cannot have
+    // left the map half-updated and the poison can be ignored.
+    fn read(&self) -> RwLockReadGuard<'_, HashMap<Pubkey, (u64, MintInfo)>> {
+        self.0.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, HashMap<Pubkey, (u64, MintInfo)>> {
+        self.0.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The metadata of `mint` with the epoch it was read at.
+    pub fn get(&self, mint: &Pubkey) -> Option<(u64, MintInfo)> {
+        self.read().get(mint).copied()
+    }
+
+    pub fn insert(&self, mint: Pubkey, epoch: u64, info: MintInfo) {
+        self.write().insert(mint, (epoch, info));
+    }
+
+    /// Drops `mint`, so the next pool to update reads it again.
+    pub fn invalidate(&self, mint: &Pubkey) {
+        self.write().remove(mint);
+    }
+
+    pub fn clear(&self) {
+        self.write().clear();
+    }
+}
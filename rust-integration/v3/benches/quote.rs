@@ -0,0 +1,73 @@
+use anchor_lang::AccountSerialize;
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use jupiter_amm_interface::{Amm, KeyedAccount};
+use obric_solana_v3::state::{Amount, SSTradingPair};
+use obric_v3_sdk::constants::PROGRAM_ID;
+use obric_v3_sdk::obric_v3_amm::ObricV3Amm;
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+
+/*
+Quotes per second on an offline $150 / $1 pool at target. `update` needs the Larix reserves, so
+the pool is decoded only and the curve point `update` keeps is benchmarked on the state:
+- `state`: the accounts crate quote, which runs `get_pool_values_for_quoting` on every call, as
+  `quote` did before the curve point was kept
+- `state at curve point`: the same quote from a curve point computed once
+*/
+
+fn decoded_pool() -> ObricV3Amm {
+    let state = SSTradingPair {
+        is_initialized: true,
+        mint_x: Pubkey::new_unique(),
+        mint_y: Pubkey::new_unique(),
+        concentration: 100,
+        mult_x: 150_000,
+        mult_y: 1_000,
+        target_y: 150_000_000_000,
+        deposit_y: 150_000_000_000,
+        fee_millionth: 1_000,
+        protocol_fee_share_thousandth: 200,
+        ..SSTradingPair::default()
+    };
+    let mut data = vec![];
+    state.try_serialize(&mut data).unwrap();
+    ObricV3Amm::from_keyed_account(&KeyedAccount {
+        key: Pubkey::new_unique(),
+        account: Account {
+            data,
+            owner: PROGRAM_ID,
+            ..Account::default()
+        },
+        params: None,
+    })
+    .unwrap()
+}
+
+fn quote(c: &mut Criterion) {
+    let amm = decoded_pool();
+    let point = amm.state.curve_point().unwrap();
+
+    let mut group = c.benchmark_group("quote");
+    group.throughput(Throughput::Elements(1));
+    let mut in_amount = 1_000_000;
+    group.bench_function("state", |b| {
+        b.iter(|| {
+            in_amount += 1;
+            amm.state
+                .quote_x_to_y_breakdown(Amount::new(black_box(in_amount)))
+                .unwrap()
+        })
+    });
+    group.bench_function("state at curve point", |b| {
+        b.iter(|| {
+            in_amount += 1;
+            amm.state
+                .quote_x_to_y_at(&point, Amount::new(black_box(in_amount)))
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, quote);
+criterion_main!(benches);
@@ -0,0 +1,37 @@
+use crate::obric_v3_amm::ObricV3Amm;
+pub use obric_sdk_common::analytics::{
+    estimate_fees, AnalyticsSource, PoolAnalytics, VolumeBucket,
+};
+
+impl AnalyticsSource for ObricV3Amm {
+    fn cumulative_volume(&self) -> u64 {
+        self.state.cumulative_volume
+    }
+
+    fn volume_records(&self) -> (&[u64], &[i64]) {
+        (&self.state.volume_records, &self.state.volume_time_records)
+    }
+
+    fn fee_millionth(&self) -> u64 {
+        self.state.fee_millionth
+    }
+
+    fn protocol_fee_share_thousandth(&self) -> u64 {
+        self.state.protocol_fee_share_thousandth
+    }
+
+    /// Deposits net of borrows, like `compute_target_y`
+    fn pool_value(&self) -> u64 {
+        let state = &self.state;
+        if state.mult_y == 0 {
+            return 0;
+        }
+        let value = |x: u64, y: u64| {
+            (x as u128 * state.mult_x as u128).saturating_add(y as u128 * state.mult_y as u128)
+        };
+        let deposit_value = value(state.deposit_x, state.deposit_y);
+        let borrow_value = value(state.borrow_x, state.borrow_y);
+        u64::try_from(deposit_value.saturating_sub(borrow_value) / state.mult_y as u128)
+            .unwrap_or(u64::MAX)
+    }
+}
@@ -0,0 +1,127 @@
+use anyhow::Result;
+use obric_sdk_common::backtest::{check_series, events, Event};
+pub use obric_sdk_common::backtest::{parse_prices_csv, parse_trades_csv, PricePoint, Trade};
+use obric_solana_v3::state::{Amount, SSTradingPair};
+
+/*
+Offline replay of an oracle price series and a trade flow through the v3 curve, see
+`obric_sdk_common::backtest` for the inputs. The target is refreshed at every price as on every
+swap. Every value is reported in raw units of Y at the oracle price of the moment, net of borrows.
+Swaps are book-kept on deposits and borrows the way the lending interface reports them, repaying
+borrows before depositing.
+*/
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BacktestReport {
+    pub trades_executed: u64,
+    /// Trades the pool could not fill
+    pub trades_skipped: u64,
+    pub initial_value: i128,
+    pub final_value: i128,
+    /// Initial net inventory valued at the final prices
+    pub hold_value: i128,
+    pub pnl_vs_hold: i128,
+    pub lp_fee_income: u64,
+    pub protocol_fee_income: u64,
+    /// Always 0, v3 pays no rebate
+    pub rebate_paid: u64,
+    /// `deposit_x - borrow_x` at the end, 0 at target, and its largest absolute value along the way
+    pub final_drift_x: i128,
+    pub max_drift_x: u128,
+    /// `deposit_y - target_y` at the end
+    pub final_drift_y: i128,
+}
+
+#[derive(Clone)]
+pub struct Backtest {
+    pub state: SSTradingPair,
+}
+
+impl Backtest {
+    fn net(&self) -> (i128, i128) {
+        (
+            self.state.deposit_x as i128 - self.state.borrow_x as i128,
+            self.state.deposit_y as i128 - self.state.borrow_y as i128,
+        )
+    }
+
+    fn value(&self, (x, y): (i128, i128)) -> i128 {
+        x * self.state.mult_x as i128 / self.state.mult_y as i128 + y
+    }
+
+    fn x_in_y(&self, x: u64) -> u64 {
+        self.value((x as i128, 0)) as u64
+    }
+
+    /// Replays `prices` and `trades`, both sorted by timestamp. The first price
+    /// must not come after the first trade.
+    pub fn run(&mut self, prices: &[PricePoint], trades: &[Trade]) -> Result<BacktestReport> {
+        check_series(prices, trades)?;
+
+        let mut report = BacktestReport::default();
+        let initial = self.net();
+        for event in events(prices, trades) {
+            match event {
+                Event::Price(index, price) => {
+                    let (price_x, price_y) = price.normalized()?;
+                    self.state.update_price(price_x, price_y)?;
+                    let target_y = self.state.compute_target_y()?;
+                    self.state.update_target_y(target_y)?;
+                    if index == 0 {
+                        report.initial_value = self.value(initial);
+                    }
+                }
+                Event::Trade(trade) => {
+                    self.swap(trade, &mut report)?;
+                    report.max_drift_x = report.max_drift_x.max(self.net().0.unsigned_abs());
+                }
+            }
+        }
+
+        report.final_value = self.value(self.net());
+        report.hold_value = self.value(initial);
+        report.pnl_vs_hold = report.final_value - report.hold_value;
+        report.final_drift_x = self.net().0;
+        report.final_drift_y = self.state.deposit_y as i128 - self.state.target_y as i128;
+        Ok(report)
+    }
+
+    fn swap(&mut self, trade: &Trade, report: &mut BacktestReport) -> Result<()> {
+        let quote = if trade.is_x_to_y {
+            self.state
+                .quote_x_to_y_breakdown(Amount::new(trade.in_amount))
+        } else {
+            self.state
+                .quote_y_to_x_breakdown(Amount::new(trade.in_amount))
+        };
+        let breakdown = match quote {
+            Ok(breakdown) if breakdown.output_after_fee > 0 => breakdown,
+            _ => {
+                report.trades_skipped += 1;
+                return Ok(());
+            }
+        };
+
+        // the LP fee stays deposited, the protocol fee leaves with the output
+        let reserve_out = breakdown.output_after_fee + breakdown.protocol_fee;
+        let state = &mut self.state;
+        if trade.is_x_to_y {
+            let repay_x = std::cmp::min(state.borrow_x, trade.in_amount);
+            state.borrow_x -= repay_x;
+            state.deposit_x += trade.in_amount - repay_x;
+            state.deposit_y -= reserve_out;
+            report.lp_fee_income += breakdown.lp_fee;
+            report.protocol_fee_income += breakdown.protocol_fee;
+        } else {
+            let withdraw_x = std::cmp::min(state.deposit_x, reserve_out);
+            state.deposit_x -= withdraw_x;
+            state.borrow_x += reserve_out - withdraw_x;
+            state.deposit_y += trade.in_amount;
+            report.lp_fee_income += self.x_in_y(breakdown.lp_fee);
+            report.protocol_fee_income += self.x_in_y(breakdown.protocol_fee);
+        }
+        report.trades_executed += 1;
+        Ok(())
+    }
+}
@@ -1,5 +1,11 @@
+pub mod analytics;
+pub mod backtest;
 pub mod constants;
+pub use obric_sdk_common::error;
 pub mod obric_v3_amm;
+pub mod simulation;
+pub mod snapshot;
+pub mod sweep;
 
 #[cfg(test)]
 pub mod test_harness;
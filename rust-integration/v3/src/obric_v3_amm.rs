@@ -6,19 +6,172 @@ use jupiter_amm_interface::{
     Amm, KeyedAccount, Quote, QuoteParams, Swap, SwapAndAccountMetas, SwapParams,
 };
 use larix_lending::state::reserve::Reserve;
+use solana_sdk::program_pack::Pack;
 use obric_solana_v3::consts;
 use obric_solana_v3::state::PriceFeed;
 use obric_solana_v3::state::SSTradingPair;
-use solana_sdk::account::Account;
+use obric_solana_v3::state::CurvePoint;
+use obric_solana_v3::state::Amount;
+pub use obric_solana_v3::state::QuoteBreakdown;
+use solana_sdk::account::{from_account, Account};
+use solana_sdk::clock::Clock;
 use solana_sdk::instruction::AccountMeta;
+use solana_sdk::sysvar;
 use std::collections::HashMap;
+use crate::error::ObricSdkError;
+use obric_math::MathError;
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct ObricV3Amm {
     pub key: Pubkey,
     pub state: SSTradingPair,
     pub obligation: Pubkey,
     pub larix_reserve_x: Option<Reserve>,
     pub larix_reserve_y: Option<Reserve>,
+    /// `None` takes prices of any age
+    max_price_age: Option<u64>,
+    /// Computed by `update`, used while the state it was computed at is unchanged
+    curve_point: Option<(StateVersion, CurvePoint)>,
+}
+
+/// Everything a quote depends on besides its input. Quotes compare it rather
+/// than a counter, so changes made directly to `state` are picked up too.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct StateVersion {
+    concentration: u64,
+    target_y: u64,
+    mult_x: u64,
+    mult_y: u64,
+    deposit_x: u64,
+    deposit_y: u64,
+    borrow_x: u64,
+    fee_millionth: u64,
+    protocol_fee_share_thousandth: u64,
+}
+
+impl ObricV3Amm {
+    /// Fails `update` with `StaleOracle` on prices published more than
+    /// `seconds` from the clock, `MAX_PRICE_AGE` is a reasonable limit.
+    pub fn with_max_price_age(mut self, seconds: u64) -> Self {
+        self.max_price_age = Some(seconds);
+        self
+    }
+
+    /// Splits the quote for `quote_params` into fee, protocol and LP shares.
+    pub fn quote_breakdown(
+        &self,
+        quote_params: &QuoteParams,
+    ) -> std::result::Result<QuoteBreakdown, ObricSdkError> {
+        let is_x_to_y = if quote_params.input_mint.eq(&self.state.mint_x) {
+            true
+        } else if quote_params.input_mint.eq(&self.state.mint_y) {
+            false
+        } else {
+            return Err(ObricSdkError::UnsupportedMint {
+                pool: self.key,
+                mint: quote_params.input_mint,
+            });
+        };
+        let in_amount = quote_params.in_amount;
+        let version = self.state_version();
+        let curve = self.state.curve();
+        let point = match self.curve_point {
+            Some((point_version, point)) if point_version == version => Ok(point),
+            _ => curve.curve_point(),
+        };
+        point
+            .and_then(|point| match is_x_to_y {
+                true => curve.quote_x_to_y_at(&point, Amount::new(in_amount)),
+                false => curve.quote_y_to_x_at(&point, Amount::new(in_amount)),
+            })
+            .map_err(|source| {
+                ObricSdkError::from_quote(self.key, quote_params.input_mint, in_amount, source)
+            })
+    }
+
+    fn account<'a>(
+        &self,
+        accounts_map: &'a HashMap<Pubkey, Account>,
+        account: &Pubkey,
+    ) -> std::result::Result<&'a Account, ObricSdkError> {
+        accounts_map
+            .get(account)
+            .ok_or(ObricSdkError::AccountNotFound {
+                pool: self.key,
+                account: *account,
+            })
+    }
+
+    fn deserialization_error<E: Into<Box<dyn std::error::Error + Send + Sync>>>(
+        &self,
+        account: Pubkey,
+        kind: &'static str,
+    ) -> impl FnOnce(E) -> ObricSdkError {
+        let pool = self.key;
+        move |source| ObricSdkError::Deserialization {
+            pool,
+            account,
+            kind,
+            source: source.into(),
+        }
+    }
+
+    fn larix_reserve(&self, mint: &Pubkey) -> std::result::Result<Pubkey, ObricSdkError> {
+        consts::mint_to_larix_reserve(mint).map_err(|_| ObricSdkError::NoLarixReserve {
+            pool: self.key,
+            mint: *mint,
+        })
+    }
+
+    fn load_larix_reserve(
+        &self,
+        accounts_map: &HashMap<Pubkey, Account>,
+        mint: &Pubkey,
+    ) -> std::result::Result<Reserve, ObricSdkError> {
+        let reserve = self.larix_reserve(mint)?;
+        Reserve::unpack(&self.account(accounts_map, &reserve)?.data)
+            .map_err(self.deserialization_error(reserve, "Larix reserve"))
+    }
+
+    /// The price account of `oracle`, if its price was published within
+    /// `max_price_age` of the clock's timestamp `now`, read only with a limit set.
+    fn price_feed(
+        &self,
+        accounts_map: &HashMap<Pubkey, Account>,
+        oracle: &Pubkey,
+        now: Option<i64>,
+    ) -> std::result::Result<PriceFeed, ObricSdkError> {
+        let data = &mut &self.account(accounts_map, oracle)?.data[..];
+        let feed = PriceFeed::try_deserialize(data)
+            .map_err(self.deserialization_error(*oracle, "price account"))?;
+        if let (Some(max_age), Some(now)) = (self.max_price_age, now) {
+            let publish_time = feed.get_price_unchecked().publish_time;
+            ObricSdkError::check_price_age(self.key, *oracle, publish_time, now, max_age)?;
+        }
+        Ok(feed)
+    }
+
+    fn state_version(&self) -> StateVersion {
+        StateVersion {
+            concentration: self.state.concentration,
+            target_y: self.state.target_y,
+            mult_x: self.state.mult_x,
+            mult_y: self.state.mult_y,
+            deposit_x: self.state.deposit_x,
+            deposit_y: self.state.deposit_y,
+            borrow_x: self.state.borrow_x,
+            fee_millionth: self.state.fee_millionth,
+            protocol_fee_share_thousandth: self.state.protocol_fee_share_thousandth,
+        }
+    }
+
+    /// The curve point computed by the last `update`, `None` once the pool has
+    /// changed since or when the curve could not place it.
+    pub fn curve_point(&self) -> Option<CurvePoint> {
+        self.curve_point
+            .filter(|(version, _)| version.eq(&self.state_version()))
+            .map(|(_, point)| point)
+    }
 }
 
 impl Amm for ObricV3Amm {
@@ -32,7 +185,13 @@ impl Amm for ObricV3Amm {
 
     fn from_keyed_account(keyed_account: &KeyedAccount) -> Result<Self> {
         let data = &mut &keyed_account.account.data.clone()[0..];
-        let ss_trading_pair = SSTradingPair::try_deserialize(data).unwrap();
+        let ss_trading_pair =
+            SSTradingPair::try_deserialize(data).map_err(|source| ObricSdkError::Deserialization {
+                pool: keyed_account.key,
+                account: keyed_account.key,
+                kind: "v3 SSTradingPair",
+                source: source.into(),
+            })?;
         let (obligation, _) = Pubkey::find_program_address(
             &[
                 consts::LARIX_OBLIGATION_SEED.as_bytes(),
@@ -47,6 +206,8 @@ impl Amm for ObricV3Amm {
             obligation,
             larix_reserve_x: None,
             larix_reserve_y: None,
+            max_price_age: None,
+            curve_point: None,
         })
     }
 
@@ -59,55 +220,75 @@ impl Amm for ObricV3Amm {
     }
 
     fn get_accounts_to_update(&self) -> Vec<Pubkey> {
+        let mut accounts = vec![
+            self.key,
+            self.state.x_price_feed_id,
+            self.state.y_price_feed_id,
+        ];
+        if self.max_price_age.is_some() {
+            // the timestamp dates the prices
+            accounts.push(sysvar::clock::ID);
+        }
         if self.larix_reserve_y.is_none() {
-            return [
-                self.key,
-                self.state.x_price_feed_id,
-                self.state.y_price_feed_id,
-                consts::mint_to_larix_reserve(&self.state.mint_x).unwrap(),
-                consts::mint_to_larix_reserve(&self.state.mint_y).unwrap(),
-            ]
-            .to_vec();
-        } else {
-            return [
-                self.key,
-                self.state.x_price_feed_id,
-                self.state.y_price_feed_id,
-            ]
-            .to_vec();
+            // a mint without a reserve fails `update` instead
+            accounts.extend(
+                [self.state.mint_x, self.state.mint_y]
+                    .iter()
+                    .filter_map(|mint| consts::mint_to_larix_reserve(mint).ok()),
+            );
         }
+        accounts
     }
 
     fn update(&mut self, accounts_map: &HashMap<Pubkey, Account>) -> Result<()> {
-        let trading_pair_data = &mut &accounts_map.get(&self.key).unwrap().data[0..];
-        let trading_pair = SSTradingPair::try_deserialize(trading_pair_data).unwrap();
-        self.state = trading_pair;
-
-        let price_x_data = &mut &accounts_map.get(&self.state.x_price_feed_id).unwrap().data[0..];
-        let price_y_data = &mut &accounts_map.get(&self.state.y_price_feed_id).unwrap().data[0..];
-        let price_x_fee = &PriceFeed::try_deserialize(price_x_data).unwrap();
-        let price_y_fee = &PriceFeed::try_deserialize(price_y_data).unwrap();
-        let price_x = price_x_fee.price_normalized()?.price as u64;
-        let price_y = price_y_fee.price_normalized()?.price as u64;
-        self.state.update_price(price_x, price_y)?;
-        let target_y = self.state.compute_target_y();
-        let _ = self.state.update_target_y(target_y)?;
+        let trading_pair_data = &mut &self.account(accounts_map, &self.key)?.data[0..];
+        self.state = SSTradingPair::try_deserialize(trading_pair_data)
+            .map_err(self.deserialization_error(self.key, "v3 SSTradingPair"))?;
+
+        let now = match self.max_price_age {
+            Some(_) => {
+                let clock: Clock = from_account(self.account(accounts_map, &sysvar::clock::ID)?)
+                    .ok_or_else(|| ObricSdkError::Deserialization {
+                        pool: self.key,
+                        account: sysvar::clock::ID,
+                        kind: "clock",
+                        source: "not a clock sysvar".into(),
+                    })?;
+                Some(clock.unix_timestamp)
+            }
+            None => None,
+        };
+        let (oracle_x, oracle_y) = (self.state.x_price_feed_id, self.state.y_price_feed_id);
+        let price_x = self.price_feed(accounts_map, &oracle_x, now)?;
+        let price_y = self.price_feed(accounts_map, &oracle_y, now)?;
+        let (price_x, price_y, _) = PriceFeed::normalized_pair(&price_x, &price_y)
+            .map_err(|err| ObricSdkError::from_price(self.key, oracle_x, oracle_y, err))?;
+        // the price and target updates only fail on values past their types
+        let overflow = |_| ObricSdkError::Math {
+            pool: self.key,
+            source: MathError::Overflow,
+        };
+        self.state.update_price(price_x, price_y).map_err(overflow)?;
+        if self.larix_reserve_y.is_none() {
+            let reserve_x = self.load_larix_reserve(accounts_map, &self.state.mint_x)?;
+            let reserve_y = self.load_larix_reserve(accounts_map, &self.state.mint_y)?;
+            self.larix_reserve_x = Some(reserve_x);
+            self.larix_reserve_y = Some(reserve_y);
+        }
+        let target_y = self.state.compute_target_y().map_err(overflow)?;
+        self.state.update_target_y(target_y).map_err(overflow)?;
+        // a pool the curve cannot place fails its quotes instead
+        self.curve_point = self
+            .state
+            .curve_point()
+            .ok()
+            .map(|point| (self.state_version(), point));
         Ok(())
     }
 
     fn quote(&self, quote_params: &QuoteParams) -> Result<Quote> {
-        let (output_after_fee, protocol_fee, _) = if quote_params.input_mint.eq(&self.state.mint_x)
-        {
-            self.state
-                .quote_x_to_y(quote_params.in_amount.clone())
-                .unwrap()
-        } else if quote_params.input_mint.eq(&self.state.mint_y) {
-            self.state
-                .quote_y_to_x(quote_params.in_amount.clone())
-                .unwrap()
-        } else {
-            (0u64, 0u64, 0u64)
-        };
+        let breakdown = self.quote_breakdown(quote_params)?;
+        let (output_after_fee, protocol_fee) = (breakdown.output_after_fee, breakdown.protocol_fee);
         Ok(Quote {
             out_amount: output_after_fee,
             fee_amount: protocol_fee,
@@ -117,51 +298,7 @@ impl Amm for ObricV3Amm {
     }
 
     fn clone_amm(&self) -> Box<dyn Amm + Send + Sync> {
-        let state = &self.state;
-        Box::new(Self {
-            key: self.key,
-            state: SSTradingPair {
-                is_initialized: state.is_initialized,
-                x_price_feed_id: state.x_price_feed_id,
-                y_price_feed_id: state.y_price_feed_id,
-                reserve_x: state.reserve_x,
-                reserve_y: state.reserve_y,
-                reserve_x_ctoken: state.reserve_x_ctoken,
-                reserve_y_ctoken: state.reserve_y_ctoken,
-
-                protocol_fee_x: state.protocol_fee_x,
-                protocol_fee_y: state.protocol_fee_y,
-                bump: state.bump,
-                mint_x: state.mint_x,
-                mint_y: state.mint_y,
-
-                deposit_x: state.deposit_x,
-                borrow_x: state.borrow_x,
-
-                deposit_y: state.deposit_y,
-                borrow_y: state.borrow_y,
-
-                target_y: state.target_y,
-
-                concentration: state.concentration,
-                big_k: state.big_k,
-                cumulative_volume: state.cumulative_volume,
-                mult_x: state.mult_x,
-                mult_y: state.mult_y,
-                fee_millionth: state.fee_millionth,
-                rebate_percentage: state.rebate_percentage,
-                protocol_fee_share_thousandth: state.protocol_fee_share_thousandth,
-                decimals_x: state.decimals_x,
-                decimals_y: state.decimals_y,
-                volume_records: state.volume_records,
-                padding: state.padding,
-                volume_time_records: state.volume_time_records,
-                padding2: state.padding2,
-            },
-            obligation: self.obligation,
-            larix_reserve_x: self.larix_reserve_x.clone(),
-            larix_reserve_y: self.larix_reserve_y.clone(),
-        })
+        Box::new(self.clone())
     }
 
     fn program_id(&self) -> Pubkey {
@@ -176,15 +313,28 @@ impl Amm for ObricV3Amm {
                     swap_params.destination_token_account,
                     self.state.protocol_fee_y,
                 )
-            } else {
+            } else if swap_params.source_mint.eq(&self.state.mint_y) {
                 (
                     swap_params.destination_token_account,
                     swap_params.source_token_account,
                     self.state.protocol_fee_x,
                 )
+            } else {
+                return Err(ObricSdkError::UnsupportedMint {
+                    pool: self.key,
+                    mint: swap_params.source_mint,
+                }
+                .into());
             };
-        let larix_reserve_x = &self.larix_reserve_x.as_ref().unwrap();
-        let larix_reserve_y = &self.larix_reserve_y.as_ref().unwrap();
+        let (Some(larix_reserve_x), Some(larix_reserve_y)) =
+            (&self.larix_reserve_x, &self.larix_reserve_y)
+        else {
+            return Err(ObricSdkError::PoolNotLoaded {
+                pool: self.key,
+                what: "its Larix reserves",
+            }
+            .into());
+        };
         let mut account_metas = vec![
             AccountMeta::new(self.key(), false),
             AccountMeta::new_readonly(self.state.mint_x, false),
@@ -204,14 +354,8 @@ impl Amm for ObricV3Amm {
             AccountMeta::new(larix_reserve_y.liquidity.supply_pubkey, false),
             AccountMeta::new(larix_reserve_x.collateral.supply_pubkey, false),
             AccountMeta::new(larix_reserve_y.collateral.supply_pubkey, false),
-            AccountMeta::new(
-                consts::mint_to_larix_reserve(&self.state.mint_x).unwrap(),
-                false,
-            ),
-            AccountMeta::new(
-                consts::mint_to_larix_reserve(&self.state.mint_y).unwrap(),
-                false,
-            ),
+            AccountMeta::new(self.larix_reserve(&self.state.mint_x)?, false),
+            AccountMeta::new(self.larix_reserve(&self.state.mint_y)?, false),
             AccountMeta::new(self.obligation, false),
             AccountMeta::new(larix_reserve_x.lending_market, false),
             AccountMeta::new(consts::larix::market::authority::id(), false),
@@ -237,4 +381,9 @@ impl Amm for ObricV3Amm {
             account_metas,
         })
     }
+
+    /// The swap from Y, which also passes the Larix oracle, mint and fee receiver
+    fn get_accounts_len(&self) -> usize {
+        31
+    }
 }
@@ -0,0 +1,83 @@
+use crate::obric_v3_amm::ObricV3Amm;
+use anchor_lang::InstructionData;
+use anyhow::Result;
+use jupiter_amm_interface::{Amm, SwapAndAccountMetas, SwapParams};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+
+pub use obric_sdk_common::simulation::{
+    QuoteConsistencyChecker, RpcSimulator, SimulatedAmm, SimulatedSwap,
+};
+
+/// Builds the swap instruction for `amm` from `get_swap_and_account_metas`.
+pub fn swap_instruction(
+    amm: &ObricV3Amm,
+    source_mint: Pubkey,
+    user: Pubkey,
+    source_token_account: Pubkey,
+    destination_token_account: Pubkey,
+    in_amount: u64,
+    min_output_amt: u64,
+) -> Result<Instruction> {
+    let is_x_to_y = source_mint.eq(&amm.state.mint_x);
+    let destination_mint = if is_x_to_y {
+        amm.state.mint_y
+    } else {
+        amm.state.mint_x
+    };
+    let program_id = amm.program_id();
+    let SwapAndAccountMetas { account_metas, .. } =
+        amm.get_swap_and_account_metas(&SwapParams {
+            in_amount,
+            out_amount: min_output_amt,
+            source_mint,
+            destination_mint,
+            source_token_account,
+            destination_token_account,
+            token_transfer_authority: user,
+            open_order_address: None,
+            quote_mint_to_referrer: None,
+            jupiter_program_id: &program_id,
+            missing_dynamic_accounts_as_default: false,
+        })?;
+
+    Ok(Instruction {
+        program_id,
+        accounts: account_metas,
+        data: if is_x_to_y {
+            obric_solana_v3::instruction::SwapXToY {
+                _input_x: in_amount,
+                _min_output_amt: min_output_amt,
+            }
+            .data()
+        } else {
+            obric_solana_v3::instruction::SwapYToX {
+                _input_y: in_amount,
+                _min_output_amt: min_output_amt,
+            }
+            .data()
+        },
+    })
+}
+
+impl SimulatedAmm for ObricV3Amm {
+    fn swap_instruction(
+        &self,
+        source_mint: Pubkey,
+        user: Pubkey,
+        source_token_account: Pubkey,
+        destination_token_account: Pubkey,
+        in_amount: u64,
+        min_output_amt: u64,
+    ) -> Result<Instruction> {
+        swap_instruction(
+            self,
+            source_mint,
+            user,
+            source_token_account,
+            destination_token_account,
+            in_amount,
+            min_output_amt,
+        )
+    }
+}
@@ -0,0 +1,113 @@
+use crate::analytics::PoolAnalytics;
+use crate::error::ObricSdkError;
+use crate::obric_v3_amm::ObricV3Amm;
+use anchor_lang::AccountDeserialize;
+use jupiter_amm_interface::Amm;
+use obric_math::MathError;
+use obric_solana_v3::state::{CurvePoint, PriceFeed, SSTradingPair};
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+
+/// Decoded pool state together with the values the curve derives from it, as
+/// of one `update`. With the `serde` feature it serializes to JSON with
+/// pubkeys in base58, so snapshots can be stored and diffed.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PoolSnapshot {
+    #[cfg_attr(feature = "serde", serde(with = "obric_solana_v3::serde_pubkey"))]
+    pub pool: Pubkey,
+    pub state: SSTradingPair,
+    /// Oracle prices as fed to `update_price`, `price_x * 10^price_expo` dollars
+    pub price_x: u64,
+    pub price_y: u64,
+    pub price_expo: i32,
+    /// At target deposit_x = borrow_x = 0, so X only has a target on the curve
+    pub target_x_k: u128,
+    pub target_y: u64,
+    pub target_y_k: u128,
+    pub current_x_k: u128,
+    pub current_y_k: u128,
+    /// `big_k` at the current point, as used by the quotes
+    pub big_k: u128,
+    /// Y per X in UI units at the current point of the curve, before fees
+    pub marginal_price: f64,
+    pub available_x: u64,
+    pub available_y: u64,
+    pub analytics: PoolAnalytics,
+}
+
+impl PoolSnapshot {
+    /// `accounts_map` holds the accounts last passed to `amm.update`, `now` is
+    /// the unix time the volume analytics are measured up to.
+    pub fn new(
+        amm: &ObricV3Amm,
+        accounts_map: &HashMap<Pubkey, Account>,
+        now: i64,
+    ) -> Result<Self, ObricSdkError> {
+        let (pool, state) = (amm.key(), &amm.state);
+        let price_feed = |oracle: Pubkey| {
+            let account = accounts_map
+                .get(&oracle)
+                .ok_or(ObricSdkError::AccountNotFound {
+                    pool,
+                    account: oracle,
+                })?;
+            PriceFeed::try_deserialize(&mut &account.data[..]).map_err(|source| {
+                ObricSdkError::Deserialization {
+                    pool,
+                    account: oracle,
+                    kind: "price account",
+                    source: source.into(),
+                }
+            })
+        };
+        let (oracle_x, oracle_y) = (state.x_price_feed_id, state.y_price_feed_id);
+        let (price_x, price_y, price_expo) =
+            PriceFeed::normalized_pair(&price_feed(oracle_x)?, &price_feed(oracle_y)?)
+                .map_err(|err| ObricSdkError::from_price(pool, oracle_x, oracle_y, err))?;
+
+        let CurvePoint {
+            big_k,
+            current_x_k,
+            current_y_k,
+            available_x,
+            available_y,
+        } = state
+            .curve()
+            .curve_point()
+            .map_err(|source| ObricSdkError::Math { pool, source })?;
+        let overflow = || ObricSdkError::Math {
+            pool,
+            source: MathError::Overflow,
+        };
+        let target_y_k = (state.concentration as u128)
+            .checked_mul(state.target_y as u128)
+            .ok_or_else(overflow)?;
+        let target_x_k = target_y_k
+            .checked_mul(state.mult_y as u128)
+            .and_then(|value| value.checked_div(state.mult_x as u128))
+            .ok_or_else(overflow)?;
+        let (current_x_k, current_y_k) = (current_x_k.get(), current_y_k.get());
+        let marginal_price = current_y_k as f64 / current_x_k as f64
+            * 10f64.powi(i32::from(state.decimals_x) - i32::from(state.decimals_y));
+
+        Ok(Self {
+            pool,
+            state: state.clone(),
+            price_x: price_x.get(),
+            price_y: price_y.get(),
+            price_expo,
+            target_x_k,
+            target_y: state.target_y,
+            target_y_k,
+            current_x_k,
+            current_y_k,
+            big_k: big_k.get(),
+            marginal_price,
+            available_x: available_x.get(),
+            available_y: available_y.get(),
+            analytics: PoolAnalytics::new(amm, now),
+        })
+    }
+}
@@ -0,0 +1,75 @@
+use crate::backtest::{Backtest, PricePoint, Trade};
+use anyhow::{anyhow, Result};
+use obric_sdk_common::sweep::slippage_bps;
+pub use obric_sdk_common::sweep::{format_table, synthetic_trades, SweepRanges, SweepResult};
+use obric_solana_v3::state::Amount;
+
+/*
+Parameter sweep over the backtest: every combination of the ranges is replayed against the same
+prices and trades. Slippage is measured after the target refresh at the first price; sizes the
+pool cannot fill report 10_000 bps.
+*/
+
+/// Replays `prices` and `trades` from `base` for every combination of `ranges`.
+/// `sizes_x` and `sizes_y` are the standard trade sizes slippage is reported at.
+/// v3 pays no rebate, so `rebate_percentage` is carried into the results but
+/// does not change them.
+pub fn sweep(
+    base: &Backtest,
+    prices: &[PricePoint],
+    trades: &[Trade],
+    ranges: &SweepRanges,
+    sizes_x: &[u64],
+    sizes_y: &[u64],
+) -> Result<Vec<SweepResult>> {
+    let (price_x, price_y) = prices
+        .first()
+        .ok_or(anyhow!("empty price series"))?
+        .normalized()?;
+    let mut results = vec![];
+    for &concentration in &ranges.concentration {
+        for &fee_millionth in &ranges.fee_millionth {
+            for &rebate_percentage in &ranges.rebate_percentage {
+                for &protocol_fee_share_thousandth in &ranges.protocol_fee_share_thousandth {
+                    let mut backtest = base.clone();
+                    let state = &mut backtest.state;
+                    state.concentration = concentration;
+                    state.fee_millionth = fee_millionth;
+                    state.rebate_percentage = rebate_percentage;
+                    state.protocol_fee_share_thousandth = protocol_fee_share_thousandth;
+                    state.update_price(price_x, price_y)?;
+                    let target_y = state.compute_target_y()?;
+                    state.update_target_y(target_y)?;
+
+                    let (mult_x, mult_y) = (state.mult_x as u128, state.mult_y as u128);
+                    let mut slippage_x_to_y_bps = vec![];
+                    for &size in sizes_x {
+                        let quote = state.quote_x_to_y(Amount::new(size));
+                        let out = quote.map_or(0, |(out, _, _)| out);
+                        slippage_x_to_y_bps.push(slippage_bps(out, size as u128 * mult_x / mult_y));
+                    }
+                    let mut slippage_y_to_x_bps = vec![];
+                    for &size in sizes_y {
+                        let quote = state.quote_y_to_x(Amount::new(size));
+                        let out = quote.map_or(0, |(out, _, _)| out);
+                        slippage_y_to_x_bps.push(slippage_bps(out, size as u128 * mult_y / mult_x));
+                    }
+
+                    let report = backtest.run(prices, trades)?;
+                    results.push(SweepResult {
+                        concentration,
+                        fee_millionth,
+                        rebate_percentage,
+                        protocol_fee_share_thousandth,
+                        slippage_x_to_y_bps,
+                        slippage_y_to_x_bps,
+                        lp_fee_income: report.lp_fee_income,
+                        pnl_vs_hold: report.pnl_vs_hold,
+                        max_drift_x: report.max_drift_x,
+                    });
+                }
+            }
+        }
+    }
+    Ok(results)
+}
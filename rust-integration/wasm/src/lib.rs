@@ -0,0 +1,343 @@
+//! WebAssembly bindings quoting Obric V2 pools offline from raw account data,
+//! for frontends and notebooks without a Rust service. Account data is passed
+//! base64 encoded, as `getAccountInfo` returns it with `encoding: "base64"`,
+//! with the owner for token accounts and mints. Results come back as JSON,
+//! with integers of 64 bits and more as strings so they survive `JSON.parse`.
+//!
+//! ```js
+//! const pool = new Pool(poolData);
+//! const accounts = {};
+//! for (const key of pool.accounts()) {
+//!   const { data, owner } = await fetchAccount(key);
+//!   accounts[key] = { data: data[0], owner };
+//! }
+//! pool.update(JSON.stringify(accounts), epoch);
+//! const quote = JSON.parse(pool.quote(pool.mintX, 1_000_000n));
+//! const outAmount = BigInt(quote.out_amount);
+//! ```
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountDeserialize;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use obric_math::quote::{exact_in, QuoteBreakdown};
+use obric_math::units::Amount;
+use obric_math::v2::{Curve, CurvePoint};
+use obric_sdk_common::token::{unpack_token_amount, MintInfo};
+use obric_solana::state::{PriceFeed, SSTradingPair};
+use serde::{Deserialize, Serialize};
+use serde_json::ser::{Formatter, Serializer};
+use std::collections::HashMap;
+use std::io;
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+
+/// An Obric V2 pool decoded from its `SSTradingPair` account. Quoting needs an
+/// `update` with the accounts listed by `accounts` first.
+#[wasm_bindgen]
+pub struct Pool {
+    state: SSTradingPair,
+    loaded: Option<Loaded>,
+}
+
+/// What `update` read from the pool's other accounts.
+struct Loaded {
+    current_x: u64,
+    current_y: u64,
+    mint_x: MintInfo,
+    mint_y: MintInfo,
+    price_x: u64,
+    price_y: u64,
+    price_expo: i32,
+    epoch: u64,
+    curve: Curve,
+    /// `None` when the curve cannot place the reserves, its quotes fail
+    point: Option<CurvePoint>,
+}
+
+/// A quote as `ObricV2Amm::quote` returns it, with the split of the fee.
+#[derive(Serialize)]
+struct QuoteJson {
+    in_amount: u64,
+    out_amount: u64,
+    fee_amount: u64,
+    fee_mint: String,
+    not_enough_liquidity: bool,
+    breakdown: QuoteBreakdown,
+}
+
+/// Decoded state and the values the curve derives from it, as of the last
+/// `update`. Field names follow the SDK's `PoolSnapshot`.
+#[derive(Serialize)]
+struct PoolJson<'a> {
+    state: &'a SSTradingPair,
+    price_x: u64,
+    price_y: u64,
+    price_expo: i32,
+    x_decimals: u8,
+    y_decimals: u8,
+    current_x: u64,
+    current_y: u64,
+    target_x: u64,
+    target_y: u64,
+    /// Y per X in UI units at the current point of the curve, before fees
+    marginal_price: Option<f64>,
+}
+
+/// An account passed to `update`: its base64 data and base58 owner.
+#[derive(Deserialize)]
+struct AccountJson {
+    data: String,
+    owner: String,
+}
+
+fn decode(data: &str) -> Result<Vec<u8>, JsError> {
+    Ok(STANDARD.decode(data)?)
+}
+
+/// JSON numbers lose precision past 2^53 in JavaScript, so integers of 64
+/// bits and more are written as strings.
+struct StringIntegers;
+
+impl Formatter for StringIntegers {
+    fn write_i64<W: ?Sized + io::Write>(&mut self, writer: &mut W, value: i64) -> io::Result<()> {
+        write!(writer, "\"{value}\"")
+    }
+
+    fn write_u64<W: ?Sized + io::Write>(&mut self, writer: &mut W, value: u64) -> io::Result<()> {
+        write!(writer, "\"{value}\"")
+    }
+
+    fn write_i128<W: ?Sized + io::Write>(&mut self, writer: &mut W, value: i128) -> io::Result<()> {
+        write!(writer, "\"{value}\"")
+    }
+
+    fn write_u128<W: ?Sized + io::Write>(&mut self, writer: &mut W, value: u128) -> io::Result<()> {
+        write!(writer, "\"{value}\"")
+    }
+}
+
+fn json(value: &impl Serialize) -> Result<String, JsError> {
+    let mut json = vec![];
+    value.serialize(&mut Serializer::with_formatter(&mut json, StringIntegers))?;
+    Ok(String::from_utf8(json)?)
+}
+
+impl Loaded {
+    fn point(&self) -> Result<CurvePoint, JsError> {
+        match self.point {
+            Some(point) => Ok(point),
+            None => {
+                let (current_x, current_y) =
+                    (Amount::new(self.current_x), Amount::new(self.current_y));
+                Ok(self.curve.curve_point(current_x, current_y)?)
+            }
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Pool {
+    /// Decodes the base64 data of an `SSTradingPair` account.
+    #[wasm_bindgen(constructor)]
+    pub fn new(data: &str) -> Result<Pool, JsError> {
+        let state = SSTradingPair::try_deserialize(&mut &decode(data)?[..])?;
+        Ok(Pool {
+            state,
+            loaded: None,
+        })
+    }
+
+    #[wasm_bindgen(getter, js_name = mintX)]
+    pub fn mint_x(&self) -> String {
+        self.state.mint_x.to_string()
+    }
+
+    #[wasm_bindgen(getter, js_name = mintY)]
+    pub fn mint_y(&self) -> String {
+        self.state.mint_y.to_string()
+    }
+
+    /// Addresses `update` reads: both reserves, both mints and both Pyth price accounts.
+    pub fn accounts(&self) -> Vec<String> {
+        [
+            self.state.reserve_x,
+            self.state.reserve_y,
+            self.state.mint_x,
+            self.state.mint_y,
+            self.state.x_price_feed_id,
+            self.state.y_price_feed_id,
+        ]
+        .iter()
+        .map(Pubkey::to_string)
+        .collect()
+    }
+
+    /// Loads the accounts listed by `accounts` from a JSON object mapping each
+    /// address to `{ "data": <base64>, "owner": <base58> }`, as
+    /// `ObricV2Amm::update` does. The owner picks the token program of the
+    /// reserves and mints. `epoch` picks the Token-2022 transfer fee of the mints.
+    pub fn update(&mut self, accounts: &str, epoch: u64) -> Result<(), JsError> {
+        let accounts: HashMap<String, AccountJson> = serde_json::from_str(accounts)?;
+        let account = |key: &Pubkey| -> Result<(Vec<u8>, Pubkey), JsError> {
+            let key = key.to_string();
+            let account = accounts
+                .get(&key)
+                .ok_or_else(|| JsError::new(&format!("account {key} not found")))?;
+            Ok((decode(&account.data)?, Pubkey::from_str(&account.owner)?))
+        };
+
+        let (reserve_x, reserve_x_owner) = account(&self.state.reserve_x)?;
+        let (reserve_y, reserve_y_owner) = account(&self.state.reserve_y)?;
+        let current_x = unpack_token_amount(&reserve_x, &reserve_x_owner)?;
+        let current_y = unpack_token_amount(&reserve_y, &reserve_y_owner)?;
+        let (mint_x, mint_x_owner) = account(&self.state.mint_x)?;
+        let (mint_y, mint_y_owner) = account(&self.state.mint_y)?;
+        let mint_x = MintInfo::unpack(&mint_x, &mint_x_owner)?;
+        let mint_y = MintInfo::unpack(&mint_y, &mint_y_owner)?;
+
+        let (price_x_data, _) = account(&self.state.x_price_feed_id)?;
+        let (price_y_data, _) = account(&self.state.y_price_feed_id)?;
+        let price_x = PriceFeed::try_deserialize(&mut &price_x_data[..])?;
+        let price_y = PriceFeed::try_deserialize(&mut &price_y_data[..])?;
+        let (price_x, price_y, price_expo) = PriceFeed::normalized_pair(&price_x, &price_y)?;
+        self.state
+            .update_price(price_x, price_y, mint_x.decimals, mint_y.decimals)?;
+
+        let curve = self.state.curve();
+        let point = curve
+            .curve_point(Amount::new(current_x), Amount::new(current_y))
+            .ok();
+        self.loaded = Some(Loaded {
+            current_x,
+            current_y,
+            mint_x,
+            mint_y,
+            price_x: price_x.get(),
+            price_y: price_y.get(),
+            price_expo,
+            epoch,
+            curve,
+            point,
+        });
+        Ok(())
+    }
+
+    /// Quote for `in_amount` of `input_mint` as JSON, net of the Token-2022
+    /// transfer fees on both sides. `ObricV2Amm` does not load Token-2022
+    /// pools, which the program cannot swap.
+    pub fn quote(&self, input_mint: &str, in_amount: u64) -> Result<String, JsError> {
+        let quote = self.quote_amount(self.is_x_to_y(input_mint)?, in_amount)?;
+        json(&quote)
+    }
+
+    /// Quote of the smallest input of `input_mint` paying at least
+    /// `out_amount`, as JSON.
+    #[wasm_bindgen(js_name = quoteExactOut)]
+    pub fn quote_exact_out(&self, input_mint: &str, out_amount: u64) -> Result<String, JsError> {
+        let is_x_to_y = self.is_x_to_y(input_mint)?;
+        let quote = |in_amount| {
+            self.quote_amount(is_x_to_y, in_amount)
+                .ok()
+                .filter(|quote| !quote.not_enough_liquidity)
+        };
+        let (_, quote) = exact_in(out_amount, quote, |quote| quote.out_amount)
+            .ok_or_else(|| JsError::new(&format!("no input pays {out_amount}")))?;
+        json(&quote)
+    }
+
+    /// Quote of the input of `input_mint` that moves the marginal price of the
+    /// curve by `bps` basis points, as JSON.
+    pub fn depth(&self, input_mint: &str, bps: u32) -> Result<String, JsError> {
+        let is_x_to_y = self.is_x_to_y(input_mint)?;
+        if bps >= 10_000 {
+            return Err(JsError::new("bps must be below 10000"));
+        }
+        let point = self.loaded()?.point()?;
+        let current_k = match is_x_to_y {
+            true => point.current_x_k.get(),
+            false => point.current_y_k.get(),
+        };
+        // the marginal price y_K / x_K = big_k / x_K^2 moves by `bps` once the
+        // input side grows by 1 / sqrt(1 - bps / 10^4)
+        let growth = 1.0 / (1.0 - f64::from(bps) / 10_000.0).sqrt() - 1.0;
+        let quote = self.quote_amount(is_x_to_y, (current_k as f64 * growth) as u64)?;
+        json(&quote)
+    }
+
+    /// The decoded pool and its curve values as JSON.
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> Result<String, JsError> {
+        let loaded = self.loaded()?;
+        let (current_x, current_y) = (Amount::new(loaded.current_x), Amount::new(loaded.current_y));
+        let (target_x, target_y) = loaded.curve.target_xy(current_x, current_y)?;
+        let (x_decimals, y_decimals) = (loaded.mint_x.decimals, loaded.mint_y.decimals);
+        let marginal_price = loaded.point.map(|point| {
+            point.current_y_k.get() as f64 / point.current_x_k.get() as f64
+                * 10f64.powi(i32::from(x_decimals) - i32::from(y_decimals))
+        });
+        json(&PoolJson {
+            state: &self.state,
+            price_x: loaded.price_x,
+            price_y: loaded.price_y,
+            price_expo: loaded.price_expo,
+            x_decimals,
+            y_decimals,
+            current_x: loaded.current_x,
+            current_y: loaded.current_y,
+            target_x: target_x.get(),
+            target_y: target_y.get(),
+            marginal_price,
+        })
+    }
+}
+
+impl Pool {
+    fn loaded(&self) -> Result<&Loaded, JsError> {
+        self.loaded
+            .as_ref()
+            .ok_or_else(|| JsError::new("pool has not been updated"))
+    }
+
+    fn is_x_to_y(&self, input_mint: &str) -> Result<bool, JsError> {
+        if input_mint == self.mint_x() {
+            Ok(true)
+        } else if input_mint == self.mint_y() {
+            Ok(false)
+        } else {
+            Err(JsError::new(&format!(
+                "mint {input_mint} is not traded by this pool"
+            )))
+        }
+    }
+
+    fn quote_amount(&self, is_x_to_y: bool, in_amount: u64) -> Result<QuoteJson, JsError> {
+        let loaded = self.loaded()?;
+        let (input, output, output_mint) = match is_x_to_y {
+            true => (loaded.mint_x, loaded.mint_y, self.state.mint_y),
+            false => (loaded.mint_y, loaded.mint_x, self.state.mint_x),
+        };
+        let net_in = in_amount - input.transfer_fee(loaded.epoch, in_amount);
+        let breakdown = if net_in == 0 {
+            QuoteBreakdown::default()
+        } else if is_x_to_y {
+            loaded
+                .curve
+                .quote_x_to_y_at(&loaded.point()?, Amount::new(net_in))?
+        } else {
+            loaded
+                .curve
+                .quote_y_to_x_at(&loaded.point()?, Amount::new(net_in))?
+        };
+        let out_amount = breakdown.output_after_fee
+            - output.transfer_fee(loaded.epoch, breakdown.output_after_fee);
+        Ok(QuoteJson {
+            in_amount,
+            out_amount,
+            fee_amount: breakdown.protocol_fee + breakdown.lp_fee,
+            fee_mint: output_mint.to_string(),
+            not_enough_liquidity: out_amount == 0,
+            breakdown,
+        })
+    }
+}
@@ -0,0 +1,121 @@
+//! Runs in Node: `wasm-pack test --node` from this directory.
+#![cfg(target_arch = "wasm32")]
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountSerialize;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use obric_sdk_common::fixtures::{mint_account, price_account, token_account};
+use obric_solana::state::SSTradingPair;
+use obric_wasm::Pool;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+/// A pool quoting $150 X against $1 Y, 6 decimals on both sides, balanced at
+/// 1000 X and 150000 Y with a concentration of 100, and the accounts it reads.
+fn offline_pool() -> (Pool, String) {
+    let target_x_k = 100 * 1_000_000_000u128;
+    let state = SSTradingPair {
+        is_initialized: true,
+        x_price_feed_id: Pubkey::new_unique(),
+        y_price_feed_id: Pubkey::new_unique(),
+        reserve_x: Pubkey::new_unique(),
+        reserve_y: Pubkey::new_unique(),
+        mint_x: Pubkey::new_unique(),
+        mint_y: Pubkey::new_unique(),
+        target_x: 1_000_000_000,
+        big_k: target_x_k * target_x_k * 150,
+        fee_millionth: 1_000,
+        rebate_percentage: 50,
+        protocol_fee_share_thousandth: 200,
+        ..SSTradingPair::default()
+    };
+    let accounts: HashMap<String, Value> = [
+        (state.mint_x, mint_account(6)),
+        (state.mint_y, mint_account(6)),
+        (state.reserve_x, token_account(state.mint_x, 1_000_000_000)),
+        (
+            state.reserve_y,
+            token_account(state.mint_y, 150_000_000_000),
+        ),
+        (state.x_price_feed_id, price_account(150_00000000)),
+        (state.y_price_feed_id, price_account(1_00000000)),
+    ]
+    .into_iter()
+    .map(|(key, account)| {
+        let account = json!({
+            "data": STANDARD.encode(account.data),
+            "owner": account.owner.to_string(),
+        });
+        (key.to_string(), account)
+    })
+    .collect();
+
+    let mut data = vec![];
+    state.try_serialize(&mut data).unwrap();
+    let pool = Pool::new(&STANDARD.encode(data)).unwrap();
+    (pool, serde_json::to_string(&accounts).unwrap())
+}
+
+fn parse(json: String) -> Value {
+    serde_json::from_str(&json).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn quotes_after_update() {
+    let (mut pool, accounts) = offline_pool();
+    assert!(pool.quote(&pool.mint_x(), 1_000_000).is_err());
+    assert_eq!(pool.accounts().len(), 6);
+    pool.update(&accounts, 0).unwrap();
+
+    // 1 X at $150, with a 0.1% fee of which the protocol takes a fifth
+    let quote = parse(pool.quote(&pool.mint_x(), 1_000_000).unwrap());
+    assert_eq!(quote["out_amount"], "149848503");
+    assert_eq!(quote["fee_amount"], "149998");
+    assert_eq!(quote["fee_mint"], pool.mint_y());
+    assert_eq!(quote["breakdown"]["protocol_fee"], "29999");
+    assert_eq!(quote["not_enough_liquidity"], false);
+
+    // more than the reserve
+    let quote = parse(pool.quote(&pool.mint_y(), 1 << 60).unwrap());
+    assert_eq!(quote["not_enough_liquidity"], true);
+
+    assert!(pool.quote(&Pubkey::new_unique().to_string(), 1).is_err());
+}
+
+#[wasm_bindgen_test]
+fn exact_out_and_depth() {
+    let (mut pool, accounts) = offline_pool();
+    pool.update(&accounts, 0).unwrap();
+
+    let quote = parse(pool.quote_exact_out(&pool.mint_x(), 149_848_503).unwrap());
+    assert_eq!(quote["in_amount"], "1000000");
+    assert!(pool.quote_exact_out(&pool.mint_y(), 1_000_000_000).is_err());
+
+    // 1% off the marginal price takes about 0.5% of curve-K's 100000 X
+    let quote = parse(pool.depth(&pool.mint_x(), 100).unwrap());
+    let in_amount: u64 = quote["in_amount"].as_str().unwrap().parse().unwrap();
+    assert!((503_000_000..504_000_000).contains(&in_amount));
+    assert_eq!(quote["not_enough_liquidity"], false);
+    assert!(pool.depth(&pool.mint_x(), 10_000).is_err());
+}
+
+#[wasm_bindgen_test]
+fn decoded_pool() {
+    let (mut pool, accounts) = offline_pool();
+    assert!(pool.to_json().is_err());
+    pool.update(&accounts, 0).unwrap();
+
+    let decoded = parse(pool.to_json().unwrap());
+    assert_eq!(decoded["state"]["mint_x"], pool.mint_x());
+    assert_eq!(decoded["state"]["mult_x"], "150000");
+    assert_eq!(decoded["price_expo"], -3);
+    assert_eq!(decoded["target_y"], "150000000000");
+    // past 2^53, exact as a string
+    assert_eq!(
+        decoded["state"]["big_k"],
+        (100_000_000_000u128 * 100_000_000_000 * 150).to_string()
+    );
+    assert_eq!(decoded["marginal_price"], 150.0);
+}